@@ -0,0 +1,63 @@
+use namagiri::sizing::{self, Distribution, SizeReport, SweepConfig};
+use std::env;
+use std::process;
+
+const USAGE: &str = "usage: flquire_sizing [--n N] [--es ES] [--len LEN] [--trials TRIALS] [--dist bits|uniform:LO,HI|normal:MEAN,STD] [--sizes FROM-TO] [--target RATE] [--seed SEED]";
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}\n{}", msg, USAGE);
+    process::exit(2)
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: Option<String>) -> T {
+    value.and_then(|v| v.parse().ok()).unwrap_or_else(|| fail(&format!("invalid value for {}", name)))
+}
+
+fn main() {
+    let mut n: u8 = 8;
+    let mut es: u8 = 1;
+    let mut cfg = SweepConfig::default();
+    let mut sizes = (sizing::MIN_SIZE, sizing::MAX_SIZE);
+    let mut target = 1e-3;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--n" => n = parse(&arg, args.next()),
+            "--es" => es = parse(&arg, args.next()),
+            "--len" => cfg.len = parse(&arg, args.next()),
+            "--trials" => cfg.trials = parse(&arg, args.next()),
+            "--seed" => cfg.seed = parse(&arg, args.next()),
+            "--target" => target = parse(&arg, args.next()),
+            "--dist" => cfg.dist = args.next().ok_or_else(String::new).and_then(|d| d.parse::<Distribution>()).unwrap_or_else(|e| fail(&e)),
+            "--sizes" => {
+                let range: String = parse(&arg, args.next());
+                sizes = match range.split_once('-') {
+                    Some((lo, hi)) => (parse(&arg, Some(lo.to_string())), parse(&arg, Some(hi.to_string()))),
+                    None => fail("--sizes expects FROM-TO"),
+                };
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            _ => fail(&format!("unknown argument `{}`", arg)),
+        }
+    }
+
+    let sizes: Vec<u16> = (sizes.0..=sizes.1).collect();
+
+    let reports: Vec<SizeReport> = sizing::sweep_format(n, es, &cfg, &sizes)
+        .unwrap_or_else(|| fail(&format!("posit<{}, {}> is not supported by the exact quire", n, es)));
+
+    println!("posit<{}, {}> len={} trials={} dist={:?}", n, es, cfg.len, cfg.trials, cfg.dist);
+    println!("{:>5} {:>10} {:>12} {:>9} {:>10}", "size", "mismatches", "rate", "max ulps", "mean ulps");
+    for r in &reports {
+        println!("{}", r);
+    }
+
+    match sizing::recommend(&reports, target) {
+        Some(size) => println!("recommended SIZE for mismatch rate <= {}: {}", target, size),
+        None => println!("no swept SIZE reaches mismatch rate <= {}", target),
+    }
+}
@@ -0,0 +1,311 @@
+use crate::posit::Posit;
+use crate::quire::Quire;
+use crate::exact::ExactSum;
+use crate::flquire::FLQuire;
+use crate::window::Window;
+use ndarray::{Array2, ArrayView1, ArrayView2};
+use num_traits::identities::Zero;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+pub trait Accumulator<P>: Copy {
+    fn init() -> Self;
+    fn fma(&mut self, a: P, b: P);
+    fn add(&mut self, a: P);
+    fn finish(self) -> P;
+}
+
+// rounds after every multiply and every add
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Naive<P>(pub P);
+
+impl<P> Accumulator<P> for Naive<P>
+    where P: Copy + Zero + Add<Output = P> + Mul<Output = P>
+{
+    fn init() -> Self {
+        Self(P::zero())
+    }
+
+    fn fma(&mut self, a: P, b: P) {
+        self.0 = self.0 + a * b;
+    }
+
+    fn add(&mut self, a: P) {
+        self.0 = self.0 + a;
+    }
+
+    fn finish(self) -> P {
+        self.0
+    }
+}
+
+// compensated summation of the rounded products
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Kahan<P> {
+    pub sum: P,
+    pub c: P,
+}
+
+impl<P> Accumulator<P> for Kahan<P>
+    where P: Copy + Zero + Add<Output = P> + Sub<Output = P> + Mul<Output = P>
+{
+    fn init() -> Self {
+        Self{sum: P::zero(), c: P::zero()}
+    }
+
+    fn fma(&mut self, a: P, b: P) {
+        Accumulator::add(self, a * b);
+    }
+
+    fn add(&mut self, a: P) {
+        let y = a - self.c;
+        let t = self.sum + y;
+        self.c = (t - self.sum) - y;
+        self.sum = t;
+    }
+
+    fn finish(self) -> P {
+        self.sum
+    }
+}
+
+impl<const N: u8, const ES: u8> Accumulator<Posit<N, ES>> for Quire<N, ES> {
+    fn init() -> Self {
+        Self::zero()
+    }
+
+    fn fma(&mut self, a: Posit<N, ES>, b: Posit<N, ES>) {
+        *self += Quire::from(a) * Quire::from(b);
+    }
+
+    fn add(&mut self, a: Posit<N, ES>) {
+        *self += Quire::from(a);
+    }
+
+    fn finish(self) -> Posit<N, ES> {
+        self.into()
+    }
+}
+
+impl<const N: u8, const ES: u8> Accumulator<Posit<N, ES>> for ExactSum<N, ES> {
+    fn init() -> Self {
+        Self::zero()
+    }
+
+    fn fma(&mut self, a: Posit<N, ES>, b: Posit<N, ES>) {
+        ExactSum::fma(self, a, b);
+    }
+
+    fn add(&mut self, a: Posit<N, ES>) {
+        *self += ExactSum::from(a);
+    }
+
+    fn finish(self) -> Posit<N, ES> {
+        self.into()
+    }
+}
+
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> Accumulator<Posit<N, ES>> for FLQuire<N, ES, SIZE, W> {
+    fn init() -> Self {
+        Self::zero()
+    }
+
+    fn fma(&mut self, a: Posit<N, ES>, b: Posit<N, ES>) {
+        *self += FLQuire::from(a) * FLQuire::from(b);
+    }
+
+    fn add(&mut self, a: Posit<N, ES>) {
+        *self += FLQuire::from(a);
+    }
+
+    fn finish(self) -> Posit<N, ES> {
+        self.into()
+    }
+}
+
+impl<const N: u8, const ES: u8> Accumulator<Posit<N, ES>> for f32 {
+    fn init() -> Self {
+        0.0
+    }
+
+    fn fma(&mut self, a: Posit<N, ES>, b: Posit<N, ES>) {
+        *self += f32::from(a) * f32::from(b);
+    }
+
+    fn add(&mut self, a: Posit<N, ES>) {
+        *self += f32::from(a);
+    }
+
+    fn finish(self) -> Posit<N, ES> {
+        self.into()
+    }
+}
+
+impl Accumulator<f32> for f32 {
+    fn init() -> Self {
+        0.0
+    }
+
+    fn fma(&mut self, a: f32, b: f32) {
+        *self += a * b;
+    }
+
+    fn add(&mut self, a: f32) {
+        *self += a;
+    }
+
+    fn finish(self) -> f32 {
+        self
+    }
+}
+
+impl Accumulator<f64> for f64 {
+    fn init() -> Self {
+        0.0
+    }
+
+    fn fma(&mut self, a: f64, b: f64) {
+        *self += a * b;
+    }
+
+    fn add(&mut self, a: f64) {
+        *self += a;
+    }
+
+    fn finish(self) -> f64 {
+        self
+    }
+}
+
+pub fn dot<P: Copy, A: Accumulator<P>>(a: ArrayView1<P>, b: ArrayView1<P>) -> P {
+    assert_eq!(a.len(), b.len(), "dot: length mismatch");
+
+    let mut acc = A::init();
+    for (&x, &y) in a.iter().zip(b.iter()) {
+        acc.fma(x, y);
+    }
+    acc.finish()
+}
+
+pub fn sum<P: Copy, A: Accumulator<P>>(a: ArrayView1<P>) -> P {
+    let mut acc = A::init();
+    for &x in a.iter() {
+        Accumulator::add(&mut acc, x);
+    }
+    acc.finish()
+}
+
+pub fn matmul<P: Copy, A: Accumulator<P>>(a: ArrayView2<P>, b: ArrayView2<P>) -> Array2<P> {
+    assert_eq!(a.ncols(), b.nrows(), "matmul: inner dimensions differ");
+
+    Array2::from_shape_fn((a.nrows(), b.ncols()), |(i, j)| dot::<P, A>(a.row(i), b.column(j)))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Policy {
+    Naive,
+    Kahan,
+    Quire,
+    // window size, rounded up to one of FLQUIRE_SIZES; at most 64
+    FLQuire(u16),
+    F32,
+}
+
+// every size has to hold the fraction of any format the exact quire supports
+pub const FLQUIRE_SIZES: [u16; 9] = [16, 20, 24, 28, 32, 40, 48, 56, 64];
+
+impl FromStr for Policy {
+    type Err = String;
+
+    // "naive", "kahan", "quire", "f32", "flquire" or "flquire:SIZE"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("flquire", size)) => match size.parse::<u16>() {
+                Ok(size) if size <= 64 => Ok(Policy::FLQuire(size)),
+                Ok(size) => Err(format!("flquire window of {} bits is wider than 64", size)),
+                Err(e) => Err(e.to_string()),
+            },
+            Some(_) => Err(format!("unknown accumulation policy `{}`", s)),
+            None => match s {
+                "naive" => Ok(Policy::Naive),
+                "kahan" => Ok(Policy::Kahan),
+                "quire" => Ok(Policy::Quire),
+                "flquire" => Ok(Policy::FLQuire(20)),
+                "f32" => Ok(Policy::F32),
+                _ => Err(format!("unknown accumulation policy `{}`", s)),
+            },
+        }
+    }
+}
+
+// the FLQuire windows a format can use, only those of at least N - ES bits, so
+// dispatching never instantiates a window too narrow for the fraction
+pub trait FLQuireSizes: Sized {
+    fn flquire_dot(size: u16, a: ArrayView1<Self>, b: ArrayView1<Self>) -> Self;
+    fn flquire_matmul(size: u16, a: ArrayView2<Self>, b: ArrayView2<Self>) -> Array2<Self>;
+}
+
+// the smallest of FLQUIRE_SIZES holding both size and the format's fraction
+fn window(size: u16, fraction: u16) -> u16 {
+    let want = size.max(fraction);
+    FLQUIRE_SIZES.iter().copied().find(|&s| s >= want)
+        .unwrap_or_else(|| panic!("FLQuire window of {} bits is wider than the largest dispatched size, 64", size))
+}
+
+macro_rules! flquire_sizes {
+    ($($n:literal $es:literal: $($s:literal)*;)*) => {
+        $(
+            impl FLQuireSizes for Posit<$n, $es> {
+                fn flquire_dot(size: u16, a: ArrayView1<Self>, b: ArrayView1<Self>) -> Self {
+                    match window(size, $n - $es) {
+                        $($s => dot::<Self, FLQuire<$n, $es, $s>>(a, b),)*
+                        _ => unreachable!(),
+                    }
+                }
+
+                fn flquire_matmul(size: u16, a: ArrayView2<Self>, b: ArrayView2<Self>) -> Array2<Self> {
+                    match window(size, $n - $es) {
+                        $($s => matmul::<Self, FLQuire<$n, $es, $s>>(a, b),)*
+                        _ => unreachable!(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+flquire_sizes! {
+    8 0: 16 20 24 28 32 40 48 56 64;
+    8 1: 16 20 24 28 32 40 48 56 64;
+    8 2: 16 20 24 28 32 40 48 56 64;
+    16 0: 16 20 24 28 32 40 48 56 64;
+    16 1: 16 20 24 28 32 40 48 56 64;
+    16 2: 16 20 24 28 32 40 48 56 64;
+    32 2: 32 40 48 56 64;
+}
+
+impl Policy {
+    pub fn dot<const N: u8, const ES: u8>(self, a: ArrayView1<Posit<N, ES>>, b: ArrayView1<Posit<N, ES>>) -> Posit<N, ES>
+        where Posit<N, ES>: FLQuireSizes
+    {
+        match self {
+            Policy::Naive => dot::<_, Naive<Posit<N, ES>>>(a, b),
+            Policy::Kahan => dot::<_, Kahan<Posit<N, ES>>>(a, b),
+            Policy::Quire => dot::<_, ExactSum<N, ES>>(a, b),
+            Policy::FLQuire(size) => Posit::flquire_dot(size, a, b),
+            Policy::F32 => dot::<_, f32>(a, b),
+        }
+    }
+
+    pub fn matmul<const N: u8, const ES: u8>(self, a: ArrayView2<Posit<N, ES>>, b: ArrayView2<Posit<N, ES>>) -> Array2<Posit<N, ES>>
+        where Posit<N, ES>: FLQuireSizes
+    {
+        match self {
+            Policy::Naive => matmul::<_, Naive<Posit<N, ES>>>(a, b),
+            Policy::Kahan => matmul::<_, Kahan<Posit<N, ES>>>(a, b),
+            Policy::Quire => matmul::<_, ExactSum<N, ES>>(a, b),
+            Policy::FLQuire(size) => Posit::flquire_matmul(size, a, b),
+            Policy::F32 => matmul::<_, f32>(a, b),
+        }
+    }
+}
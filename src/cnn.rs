@@ -0,0 +1,213 @@
+use crate::posit::Posit;
+use crate::gemm::{fused_dot, fused_sum};
+use ndarray::{Array1, Array4, ArrayView1, ArrayView4, Axis};
+use ndarray::parallel::prelude::*;
+use num_traits::identities::{One, Zero};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CnnError {
+    // a zero kernel, stride or dilation
+    Zero,
+    // window span, input length and padding of an axis the window does not fit
+    Window(usize, usize, usize),
+    // groups, input channels and output channels when the groups do not divide both
+    Groups(usize, usize, usize),
+    // channels per group the weight expects, and those the input has
+    Channels(usize, usize),
+    // bias or batch norm parameters not matching the given output channels
+    Parameters(usize),
+    // padding wider than half the pooling kernel
+    Padding,
+}
+
+impl fmt::Display for CnnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CnnError::Zero => write!(f, "kernel, stride and dilation must be positive"),
+            CnnError::Window(span, len, pad) => write!(f, "window of {} does not fit an input of {} with padding {}", span, len, pad),
+            CnnError::Groups(g, c, o) => write!(f, "groups {} must divide {} input and {} output channels", g, c, o),
+            CnnError::Channels(w, x) => write!(f, "weight expects {} channels per group, input has {}", w, x),
+            CnnError::Parameters(o) => write!(f, "bias and batch norm parameters need {} entries", o),
+            CnnError::Padding => write!(f, "padding must be at most half the kernel"),
+        }
+    }
+}
+
+impl std::error::Error for CnnError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conv2dConfig {
+    // (height, width) for each
+    pub stride: (usize, usize),
+    pub padding: (usize, usize),
+    pub dilation: (usize, usize),
+    pub groups: usize,
+}
+
+impl Default for Conv2dConfig {
+    fn default() -> Self {
+        Self{stride: (1, 1), padding: (0, 0), dilation: (1, 1), groups: 1}
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pool2dConfig {
+    pub kernel: (usize, usize),
+    pub stride: (usize, usize),
+    pub padding: (usize, usize),
+}
+
+impl Pool2dConfig {
+    // non-overlapping windows
+    pub fn new(kernel: (usize, usize)) -> Self {
+        Self{kernel, stride: kernel, padding: (0, 0)}
+    }
+}
+
+// inference-time statistics and affine parameters, one entry per channel
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchNorm<const N: u8, const ES: u8> {
+    pub gamma: Array1<Posit<N, ES>>,
+    pub beta: Array1<Posit<N, ES>>,
+    pub mean: Array1<Posit<N, ES>>,
+    pub var: Array1<Posit<N, ES>>,
+    pub eps: f64,
+}
+
+fn out_len(len: usize, k: usize, stride: usize, pad: usize, dilation: usize) -> Result<usize, CnnError> {
+    if k == 0 || stride == 0 || dilation == 0 {
+        return Err(CnnError::Zero);
+    }
+
+    let span = dilation * (k - 1) + 1;
+    if len + 2 * pad < span {
+        return Err(CnnError::Window(span, len, pad));
+    }
+
+    Ok((len + 2 * pad - span) / stride + 1)
+}
+
+// input coordinate of output o at kernel offset k, None inside the padding
+#[inline]
+fn source(o: usize, k: usize, stride: usize, pad: usize, dilation: usize, len: usize) -> Option<usize> {
+    (o * stride + k * dilation).checked_sub(pad).filter(|&i| i < len)
+}
+
+// x is (batch, channels, height, width), w is (out channels, channels / groups, kh, kw);
+// every output pixel, bias included, is rounded once
+pub fn conv2d<const N: u8, const ES: u8>(x: ArrayView4<Posit<N, ES>>, w: ArrayView4<Posit<N, ES>>, bias: Option<ArrayView1<Posit<N, ES>>>, cfg: Conv2dConfig) -> Result<Array4<Posit<N, ES>>, CnnError> {
+    let (n, c, h, wd) = x.dim();
+    let (o, cg, kh, kw) = w.dim();
+    let g = cfg.groups;
+
+    if g == 0 || c % g != 0 || o % g != 0 {
+        return Err(CnnError::Groups(g, c, o));
+    }
+    if cg != c / g {
+        return Err(CnnError::Channels(cg, c / g));
+    }
+    if matches!(bias, Some(b) if b.len() != o) {
+        return Err(CnnError::Parameters(o));
+    }
+
+    let oh = out_len(h, kh, cfg.stride.0, cfg.padding.0, cfg.dilation.0)?;
+    let ow = out_len(wd, kw, cfg.stride.1, cfg.padding.1, cfg.dilation.1)?;
+    let per_group = o / g;
+
+    let mut out = Array4::<Posit<N, ES>>::zeros((n, o, oh, ow));
+
+    out.axis_iter_mut(Axis(1)).into_par_iter().enumerate().for_each(|(oc, mut plane)| {
+        let c0 = oc / per_group * cg;
+        let b = bias.map_or(Posit::zero(), |b| b[oc]);
+
+        for ((bi, i, j), y) in plane.indexed_iter_mut() {
+            let rows = (0..kh).filter_map(|ky| source(i, ky, cfg.stride.0, cfg.padding.0, cfg.dilation.0, h).map(|iy| (ky, iy)));
+            let terms = rows.flat_map(|(ky, iy)| {
+                (0..kw).filter_map(move |kx| source(j, kx, cfg.stride.1, cfg.padding.1, cfg.dilation.1, wd).map(|ix| (kx, ix)))
+                    .flat_map(move |(kx, ix)| (0..cg).map(move |ci| (w[[oc, ci, ky, kx]], x[[bi, c0 + ci, iy, ix]])))
+            });
+
+            *y = fused_dot(b, terms);
+        }
+    });
+
+    Ok(out)
+}
+
+// applies f to the in-bounds values of every window, channels in parallel
+fn pool<const N: u8, const ES: u8, F>(x: ArrayView4<Posit<N, ES>>, cfg: Pool2dConfig, f: F) -> Result<Array4<Posit<N, ES>>, CnnError>
+    where F: Fn(&[Posit<N, ES>]) -> Posit<N, ES> + Sync
+{
+    let (n, c, h, w) = x.dim();
+    let (kh, kw) = cfg.kernel;
+
+    if cfg.padding.0 * 2 > kh || cfg.padding.1 * 2 > kw {
+        return Err(CnnError::Padding);
+    }
+
+    // windows over an empty axis see padding only and have nothing to pool
+    if h == 0 || w == 0 {
+        return Err(if h == 0 {CnnError::Window(kh, h, cfg.padding.0)} else {CnnError::Window(kw, w, cfg.padding.1)});
+    }
+
+    let oh = out_len(h, kh, cfg.stride.0, cfg.padding.0, 1)?;
+    let ow = out_len(w, kw, cfg.stride.1, cfg.padding.1, 1)?;
+
+    let mut out = Array4::<Posit<N, ES>>::zeros((n, c, oh, ow));
+
+    out.axis_iter_mut(Axis(1)).into_par_iter().enumerate().for_each(|(ch, mut plane)| {
+        let mut window = Vec::with_capacity(kh * kw);
+
+        for ((bi, i, j), y) in plane.indexed_iter_mut() {
+            window.clear();
+            for iy in (0..kh).filter_map(|ky| source(i, ky, cfg.stride.0, cfg.padding.0, 1, h)) {
+                window.extend((0..kw).filter_map(|kx| source(j, kx, cfg.stride.1, cfg.padding.1, 1, w)).map(|ix| x[[bi, ch, iy, ix]]));
+            }
+
+            *y = f(&window);
+        }
+    });
+
+    Ok(out)
+}
+
+// padding never wins; NaR anywhere in the window gives NaR
+pub fn max_pool2d<const N: u8, const ES: u8>(x: ArrayView4<Posit<N, ES>>, cfg: Pool2dConfig) -> Result<Array4<Posit<N, ES>>, CnnError> {
+    pool(x, cfg, |v| {
+        if v.iter().any(|p| p.is_nar()) {Posit::NAR} else {v.iter().cloned().fold(v[0], |m, p| if p > m {p} else {m})}
+    })
+}
+
+// exact window sum divided with one rounding; padding counts as zeros when count_include_pad
+pub fn avg_pool2d<const N: u8, const ES: u8>(x: ArrayView4<Posit<N, ES>>, cfg: Pool2dConfig, count_include_pad: bool) -> Result<Array4<Posit<N, ES>>, CnnError> {
+    let full = (cfg.kernel.0 * cfg.kernel.1) as i64;
+
+    pool(x, cfg, |v| {
+        let count = if count_include_pad {full} else {v.len() as i64};
+        fused_sum(Posit::zero(), v.iter().map(|&p| (p, Posit::one()))).div_int(count)
+    })
+}
+
+// conv followed by batch norm as one conv: w' = w s, b' = (b - mean) s + beta with s = gamma / sqrt(var + eps);
+// evaluated in f64 and rounded once per parameter
+pub fn fold_batch_norm<const N: u8, const ES: u8>(w: ArrayView4<Posit<N, ES>>, bias: Option<ArrayView1<Posit<N, ES>>>, bn: &BatchNorm<N, ES>) -> Result<(Array4<Posit<N, ES>>, Array1<Posit<N, ES>>), CnnError> {
+    let o = w.dim().0;
+    if [&bn.gamma, &bn.beta, &bn.mean, &bn.var].iter().any(|v| v.len() != o) || matches!(bias, Some(b) if b.len() != o) {
+        return Err(CnnError::Parameters(o));
+    }
+
+    let scale: Vec<f64> = bn.gamma.iter().zip(&bn.var).map(|(&g, &v)| f64::from(g) / (f64::from(v) + bn.eps).sqrt()).collect();
+
+    let mut folded = w.to_owned();
+    for (mut filter, &s) in folded.axis_iter_mut(Axis(0)).zip(&scale) {
+        filter.mapv_inplace(|p| Posit::from(f64::from(p) * s));
+    }
+
+    let b = (0..o).map(|k| {
+        let b = bias.map_or(0.0, |b| f64::from(b[k]));
+        Posit::from((b - f64::from(bn.mean[k])) * scale[k] + f64::from(bn.beta[k]))
+    }).collect();
+
+    Ok((folded, b))
+}
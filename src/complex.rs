@@ -0,0 +1,254 @@
+use crate::posit::Posit;
+use crate::exact::ExactSum;
+use crate::accumulator::Accumulator;
+use crate::gemm::{fused_dot, fused_sum};
+use ndarray::{Array, ArrayView, ArrayView1, Dimension, ScalarOperand, Zip};
+use num_traits::identities::{One, Zero};
+use num_traits::sign::Signed;
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct ComplexPosit<const N: u8, const ES: u8> {
+    pub re: Posit<N, ES>,
+    pub im: Posit<N, ES>,
+}
+
+impl<const N: u8, const ES: u8> ComplexPosit<N, ES> {
+    pub const NAR: Self = Self::new(Posit::NAR, Posit::NAR);
+
+    #[inline]
+    pub const fn new(re: Posit<N, ES>, im: Posit<N, ES>) -> Self {
+        Self{re, im}
+    }
+
+    pub fn i() -> Self {
+        Self::new(Posit::zero(), Posit::one())
+    }
+
+    #[inline]
+    pub fn is_nar(self) -> bool {
+        self.re.is_nar() || self.im.is_nar()
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    // |z|^2 rounded once
+    pub fn norm_sqr(self) -> Posit<N, ES> {
+        fused_dot(Posit::zero(), [(self.re, self.re), (self.im, self.im)])
+    }
+
+    // |z| from the exact sum of squares, rounded once
+    pub fn abs(self) -> Posit<N, ES> {
+        fused_sum(Posit::zero(), [(self.re, self.re), (self.im, self.im)]).sqrt()
+    }
+
+    // atan2 through f64, NaR for NaR input
+    pub fn arg(self) -> Posit<N, ES> {
+        if self.is_nar() {Posit::NAR} else {f64::atan2(self.im.into(), self.re.into()).into()}
+    }
+
+    pub fn from_polar(r: f64, theta: f64) -> Self {
+        Self::new((r * theta.cos()).into(), (r * theta.sin()).into())
+    }
+
+    // r e^{i theta} with both parts rounded from f64
+    pub fn cis(theta: f64) -> Self {
+        Self::from_polar(1.0, theta)
+    }
+
+    // a b + c with each part rounded once
+    pub fn mul_add(self, b: Self, c: Self) -> Self {
+        Self::new(
+            fused_dot(c.re, [(self.re, b.re), (-self.im, b.im)]),
+            fused_dot(c.im, [(self.re, b.im), (self.im, b.re)]),
+        )
+    }
+}
+
+impl<const N: u8, const ES: u8> fmt::Display for ComplexPosit<N, ES> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.im.is_negative() && !self.im.is_nar() {""} else {"+"};
+        write!(f, "{}{}{}i", f32::from(self.re), sign, f32::from(self.im))
+    }
+}
+
+impl<const N: u8, const ES: u8> From<Posit<N, ES>> for ComplexPosit<N, ES> {
+    fn from(re: Posit<N, ES>) -> Self {
+        Self::new(re, Posit::zero())
+    }
+}
+
+impl<const N: u8, const ES: u8> From<(f64, f64)> for ComplexPosit<N, ES> {
+    fn from((re, im): (f64, f64)) -> Self {
+        Self::new(re.into(), im.into())
+    }
+}
+
+impl<const N: u8, const ES: u8> From<ComplexPosit<N, ES>> for (f64, f64) {
+    fn from(z: ComplexPosit<N, ES>) -> Self {
+        (z.re.into(), z.im.into())
+    }
+}
+
+impl<const N: u8, const ES: u8> Neg for ComplexPosit<N, ES> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+impl<const N: u8, const ES: u8> Add for ComplexPosit<N, ES> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl<const N: u8, const ES: u8> Sub for ComplexPosit<N, ES> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+// ac - bd and ad + bc, each rounded once
+impl<const N: u8, const ES: u8> Mul for ComplexPosit<N, ES> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.mul_add(other, Self::zero())
+    }
+}
+
+impl<const N: u8, const ES: u8> Mul<Posit<N, ES>> for ComplexPosit<N, ES> {
+    type Output = Self;
+
+    fn mul(self, other: Posit<N, ES>) -> Self {
+        Self::new(self.re * other, self.im * other)
+    }
+}
+
+// (ac + bd) / (c^2 + d^2) and (bc - ad) / (c^2 + d^2) as ratios of exact sums, each rounded once
+impl<const N: u8, const ES: u8> Div for ComplexPosit<N, ES> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let (a, b, c, d) = (self.re, self.im, other.re, other.im);
+
+        let den = fused_sum(Posit::zero(), [(c, c), (d, d)]);
+        let re = fused_sum(Posit::zero(), [(a, c), (b, d)]);
+        let im = fused_sum(Posit::zero(), [(b, c), (-a, d)]);
+
+        Self::new(re.ratio(den), im.ratio(den))
+    }
+}
+
+impl<const N: u8, const ES: u8> Div<Posit<N, ES>> for ComplexPosit<N, ES> {
+    type Output = Self;
+
+    fn div(self, other: Posit<N, ES>) -> Self {
+        Self::new(self.re / other, self.im / other)
+    }
+}
+
+impl<const N: u8, const ES: u8> AddAssign for ComplexPosit<N, ES> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const N: u8, const ES: u8> SubAssign for ComplexPosit<N, ES> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const N: u8, const ES: u8> MulAssign for ComplexPosit<N, ES> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl<const N: u8, const ES: u8> Zero for ComplexPosit<N, ES> {
+    fn zero() -> Self {
+        Self::new(Posit::zero(), Posit::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.re.is_zero() && self.im.is_zero()
+    }
+}
+
+impl<const N: u8, const ES: u8> One for ComplexPosit<N, ES> {
+    fn one() -> Self {
+        Self::new(Posit::one(), Posit::zero())
+    }
+}
+
+impl<const N: u8, const ES: u8> ScalarOperand for ComplexPosit<N, ES> {}
+
+// exact real and imaginary sums of complex products
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct ComplexQuire<const N: u8, const ES: u8> {
+    pub re: ExactSum<N, ES>,
+    pub im: ExactSum<N, ES>,
+}
+
+impl<const N: u8, const ES: u8> Accumulator<ComplexPosit<N, ES>> for ComplexQuire<N, ES> {
+    fn init() -> Self {
+        Self{re: ExactSum::zero(), im: ExactSum::zero()}
+    }
+
+    // a NaR part makes both parts NaR
+    fn fma(&mut self, a: ComplexPosit<N, ES>, b: ComplexPosit<N, ES>) {
+        if a.is_nar() || b.is_nar() {
+            *self = Self{re: ExactSum::NAR, im: ExactSum::NAR};
+            return;
+        }
+
+        self.re.fma(a.re, b.re);
+        self.re.fma(-a.im, b.im);
+        self.im.fma(a.re, b.im);
+        self.im.fma(a.im, b.re);
+    }
+
+    fn add(&mut self, a: ComplexPosit<N, ES>) {
+        self.fma(a, ComplexPosit::one());
+    }
+
+    fn finish(self) -> ComplexPosit<N, ES> {
+        if self.re.is_nar() || self.im.is_nar() {ComplexPosit::NAR} else {ComplexPosit::new(self.re.into(), self.im.into())}
+    }
+}
+
+// sum(conj(a) b) with each part rounded once
+pub fn dotc<const N: u8, const ES: u8>(a: ArrayView1<ComplexPosit<N, ES>>, b: ArrayView1<ComplexPosit<N, ES>>) -> ComplexPosit<N, ES> {
+    assert_eq!(a.len(), b.len(), "dotc: lengths differ");
+
+    let mut acc = ComplexQuire::init();
+    a.iter().zip(b).for_each(|(&x, &y)| acc.fma(x.conj(), y));
+    acc.finish()
+}
+
+// sum(a b) without conjugation
+pub fn dotu<const N: u8, const ES: u8>(a: ArrayView1<ComplexPosit<N, ES>>, b: ArrayView1<ComplexPosit<N, ES>>) -> ComplexPosit<N, ES> {
+    assert_eq!(a.len(), b.len(), "dotu: lengths differ");
+
+    let mut acc = ComplexQuire::init();
+    a.iter().zip(b).for_each(|(&x, &y)| acc.fma(x, y));
+    acc.finish()
+}
+
+pub fn from_parts<const N: u8, const ES: u8, D: Dimension>(re: ArrayView<Posit<N, ES>, D>, im: ArrayView<Posit<N, ES>, D>) -> Array<ComplexPosit<N, ES>, D> {
+    Zip::from(re).and(im).map_collect(|&re, &im| ComplexPosit::new(re, im))
+}
+
+pub fn to_parts<const N: u8, const ES: u8, D: Dimension>(z: ArrayView<ComplexPosit<N, ES>, D>) -> (Array<Posit<N, ES>, D>, Array<Posit<N, ES>, D>) {
+    (z.map(|c| c.re), z.map(|c| c.im))
+}
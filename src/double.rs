@@ -0,0 +1,331 @@
+use crate::posit::Posit;
+use ndarray::ArrayView1;
+use num_traits::identities::{One, Zero};
+use num_traits::sign::Signed;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// Error-free transformations for posits, and an unevaluated pair hi + lo built on them.
+//
+// With IEEE floats, a + b - fl(a + b) and a b - fl(a b) are always floats
+// (barring overflow), which is what Knuth's TwoSum, Dekker's TwoProduct and
+// double-double arithmetic rest on. Posits break that in several places:
+//
+// - Precision tapers with magnitude, and the error of a product sits about
+//   one significand below the product, out where there are fewer fraction
+//   bits. For posit32 with es = 2, 1/3 times itself leaves an error near
+//   2^-32 needing about 27 bits where only 20 are left, and nearly half of
+//   all products of normally distributed operands are likewise inexact. The
+//   error of a sum is made of low bits of the smaller operand, loses at
+//   least a bit of length for each bit of fraction the taper costs it, and
+//   has been a posit in every case tried.
+// - Nothing overflows or underflows: sums beyond maxpos saturate and tiny
+//   products stop at minpos, leaving errors as large as the result.
+// - The algorithms themselves assume every intermediate difference is exact
+//   (Sterbenz) and split significands with a fixed-width constant (Veltkamp),
+//   neither of which holds, so the pairs here are computed from the exact
+//   integer sum or product instead of posit arithmetic.
+//
+// The error is therefore returned rounded to the nearest posit; the *_exact
+// variants report whether that rounding lost anything. A DoublePosit gets
+// roughly twice the fraction bits near 1 but fewer as its magnitude leaves
+// the sweet spot, since lo lives further out on the taper than hi.
+
+// m 2^e held exactly
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Exact {
+    pub(crate) m: i128,
+    pub(crate) e: i32,
+}
+
+impl Exact {
+    pub(crate) fn of<const N: u8, const ES: u8>(p: Posit<N, ES>) -> Self {
+        if p.is_zero() {
+            return Self{m: 0, e: 0};
+        }
+
+        let (s, f, e) = p.to_scaled();
+        let tz = f.trailing_zeros();
+        let m = (f >> tz) as i128;
+
+        Self{m: if s {-m} else {m}, e: e + tz as i32}
+    }
+
+    // None when the terms are too far apart to align in 128 bits
+    pub(crate) fn add(self, other: Self) -> Option<Self> {
+        if self.m == 0 || other.m == 0 {
+            return Some(if self.m == 0 {other} else {self});
+        }
+
+        let (hi, lo) = if self.e >= other.e {(self, other)} else {(other, self)};
+        let shift = (hi.e - lo.e) as u32;
+        if shift + 2 > hi.m.unsigned_abs().leading_zeros() {
+            return None;
+        }
+
+        (hi.m << shift).checked_add(lo.m).map(|m| Self{m, e: lo.e})
+    }
+
+    // None when the product needs more than 128 bits
+    pub(crate) fn mul(self, other: Self) -> Option<Self> {
+        let m = self.m.checked_mul(other.m)?;
+        let tz = if m == 0 {0} else {m.trailing_zeros()};
+
+        Some(Self{m: m >> tz, e: self.e + other.e + tz as i32})
+    }
+
+    pub(crate) fn round<const N: u8, const ES: u8>(self) -> Posit<N, ES> {
+        Posit::from_scaled(self.m < 0, self.m.unsigned_abs(), self.e)
+    }
+
+    // self / other rounded once, NaR for a zero other
+    pub(crate) fn ratio<const N: u8, const ES: u8>(self, other: Self) -> Posit<N, ES> {
+        Posit::from_ratio((self.m < 0) != (other.m < 0), self.m.unsigned_abs(), other.m.unsigned_abs(), self.e - other.e)
+    }
+}
+
+// the bit length of |x| plus its exponent
+fn magnitude(x: Exact) -> i32 {
+    x.e + 128 - x.m.unsigned_abs().leading_zeros() as i32
+}
+
+// (round(x), round(x - round(x)), whether the second is exact)
+fn split<const N: u8, const ES: u8>(x: Exact) -> (Posit<N, ES>, Posit<N, ES>, bool) {
+    let s = x.round();
+
+    match x.add(Exact::of(-s)) {
+        Some(r) => {
+            let e = r.round();
+            (s, e, matches!(r.add(Exact::of(-e)), Some(d) if d.m == 0))
+        },
+        // x and s too far apart to align: either x lies far beyond maxpos and
+        // x - s saturates to s, or x is far below minpos and x - s is about -s
+        None => if magnitude(x) > magnitude(Exact::of(s)) {(s, s, false)} else {(s, -s, false)},
+    }
+}
+
+fn two_sum_flagged<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> (Posit<N, ES>, Posit<N, ES>, bool) {
+    if a.is_nar() || b.is_nar() {
+        return (Posit::NAR, Posit::NAR, true);
+    }
+
+    match Exact::of(a).add(Exact::of(b)) {
+        Some(t) => split(t),
+        // the smaller term lies below half an ulp of the larger, which is the sum
+        None => if a.abs() >= b.abs() {(a, b, true)} else {(b, a, true)},
+    }
+}
+
+fn two_product_flagged<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> (Posit<N, ES>, Posit<N, ES>, bool) {
+    if a.is_nar() || b.is_nar() {
+        return (Posit::NAR, Posit::NAR, true);
+    }
+
+    let (x, y) = (Exact::of(a), Exact::of(b));
+    split(Exact{m: x.m * y.m, e: x.e + y.e})
+}
+
+// s = round(a + b) and the error a + b - s rounded to the nearest posit
+pub fn two_sum<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> (Posit<N, ES>, Posit<N, ES>) {
+    let (s, e, _) = two_sum_flagged(a, b);
+    (s, e)
+}
+
+// (s, e) with s + e = a + b exactly, None when the error is not a posit
+pub fn two_sum_exact<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> Option<(Posit<N, ES>, Posit<N, ES>)> {
+    let (s, e, exact) = two_sum_flagged(a, b);
+    if exact {Some((s, e))} else {None}
+}
+
+// p = round(a b) and the error a b - p rounded to the nearest posit
+pub fn two_product<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> (Posit<N, ES>, Posit<N, ES>) {
+    let (p, e, _) = two_product_flagged(a, b);
+    (p, e)
+}
+
+// (p, e) with p + e = a b exactly, None when the error is not a posit
+pub fn two_product_exact<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> Option<(Posit<N, ES>, Posit<N, ES>)> {
+    let (p, e, exact) = two_product_flagged(a, b);
+    if exact {Some((p, e))} else {None}
+}
+
+// correctly rounded a + b, a b and a / b for any width
+fn add<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> Posit<N, ES> {
+    two_sum(a, b).0
+}
+
+fn mul<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> Posit<N, ES> {
+    two_product(a, b).0
+}
+
+fn quotient<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> Posit<N, ES> {
+    if a.is_nar() || b.is_nar() || b.is_zero() {
+        return Posit::NAR;
+    }
+
+    Exact::of(a).ratio(Exact::of(b))
+}
+
+// the unevaluated sum hi + lo with |lo| at most about half an ulp of hi
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct DoublePosit<const N: u8, const ES: u8> {
+    pub hi: Posit<N, ES>,
+    pub lo: Posit<N, ES>,
+}
+
+impl<const N: u8, const ES: u8> DoublePosit<N, ES> {
+    pub const NAR: Self = Self{hi: Posit::NAR, lo: Posit::NAR};
+
+    // hi + lo renormalised
+    pub fn new(hi: Posit<N, ES>, lo: Posit<N, ES>) -> Self {
+        let (hi, lo) = two_sum(hi, lo);
+        Self{hi, lo}
+    }
+
+    #[inline]
+    pub fn is_nar(self) -> bool {
+        self.hi.is_nar() || self.lo.is_nar()
+    }
+
+    // Newton step from the posit root: x + (a - x^2) / 2x
+    pub fn sqrt(self) -> Self {
+        if self.is_nar() || self.hi.is_negative() {
+            return Self::NAR;
+        }
+
+        let x = self.hi.sqrt();
+        if x.is_zero() {
+            return Self::zero();
+        }
+
+        let r = self - Self::from(two_product(x, x));
+        Self::new(x, quotient(r.hi, mul(x, Posit::from(2.0))))
+    }
+}
+
+impl<const N: u8, const ES: u8> fmt::Display for DoublePosit<N, ES> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", f64::from(*self))
+    }
+}
+
+impl<const N: u8, const ES: u8> From<Posit<N, ES>> for DoublePosit<N, ES> {
+    fn from(hi: Posit<N, ES>) -> Self {
+        Self{hi, lo: Posit::zero()}
+    }
+}
+
+// an error-free pair, as from two_sum or two_product
+impl<const N: u8, const ES: u8> From<(Posit<N, ES>, Posit<N, ES>)> for DoublePosit<N, ES> {
+    fn from((hi, lo): (Posit<N, ES>, Posit<N, ES>)) -> Self {
+        Self::new(hi, lo)
+    }
+}
+
+impl<const N: u8, const ES: u8> From<f64> for DoublePosit<N, ES> {
+    fn from(v: f64) -> Self {
+        let hi = Posit::from(v);
+        if hi.is_nar() {Self::NAR} else {Self::new(hi, Posit::from(v - f64::from(hi)))}
+    }
+}
+
+impl<const N: u8, const ES: u8> From<DoublePosit<N, ES>> for f64 {
+    fn from(x: DoublePosit<N, ES>) -> Self {
+        if x.is_nar() {f64::INFINITY} else {f64::from(x.hi) + f64::from(x.lo)}
+    }
+}
+
+impl<const N: u8, const ES: u8> Neg for DoublePosit<N, ES> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self{hi: -self.hi, lo: -self.lo}
+    }
+}
+
+// the accurate double-double sum: both halves added error-free
+impl<const N: u8, const ES: u8> Add for DoublePosit<N, ES> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (s, e) = two_sum(self.hi, other.hi);
+        let (t, f) = two_sum(self.lo, other.lo);
+        let (s, e) = two_sum(s, add(e, t));
+
+        Self::new(s, add(e, f))
+    }
+}
+
+impl<const N: u8, const ES: u8> Sub for DoublePosit<N, ES> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl<const N: u8, const ES: u8> Mul for DoublePosit<N, ES> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let (p, e) = two_product(self.hi, other.hi);
+        let cross = add(mul(self.hi, other.lo), mul(self.lo, other.hi));
+
+        Self::new(p, add(e, cross))
+    }
+}
+
+// three quotient digits, each correcting the remainder of the last
+impl<const N: u8, const ES: u8> Div for DoublePosit<N, ES> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let q1 = quotient(self.hi, other.hi);
+        let r = self - other * Self::from(q1);
+        let q2 = quotient(r.hi, other.hi);
+        let r = r - other * Self::from(q2);
+        let q3 = quotient(r.hi, other.hi);
+
+        Self::new(q1, q2) + Self::from(q3)
+    }
+}
+
+impl<const N: u8, const ES: u8> Zero for DoublePosit<N, ES> {
+    fn zero() -> Self {
+        Self::from(Posit::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.hi.is_zero() && self.lo.is_zero()
+    }
+}
+
+impl<const N: u8, const ES: u8> One for DoublePosit<N, ES> {
+    fn one() -> Self {
+        Self::from(Posit::one())
+    }
+}
+
+// compensated summation: the rounding errors of the running sum are collected
+// and added back once, as if summed in twice the precision
+pub fn sum2<const N: u8, const ES: u8>(x: ArrayView1<Posit<N, ES>>) -> DoublePosit<N, ES> {
+    let (s, c) = x.iter().fold((Posit::zero(), Posit::zero()), |(s, c), &v| {
+        let (s, e) = two_sum(s, v);
+        (s, add(c, e))
+    });
+
+    DoublePosit::new(s, c)
+}
+
+// Ogita, Rump and Oishi's Dot2: the dot product as if computed in twice the precision
+pub fn dot2<const N: u8, const ES: u8>(a: ArrayView1<Posit<N, ES>>, b: ArrayView1<Posit<N, ES>>) -> DoublePosit<N, ES> {
+    assert_eq!(a.len(), b.len(), "dot2: lengths differ");
+
+    let (p, c) = a.iter().zip(b).fold((Posit::zero(), Posit::zero()), |(p, c), (&x, &y)| {
+        let (h, r) = two_product(x, y);
+        let (p, q) = two_sum(p, h);
+        (p, add(c, add(q, r)))
+    });
+
+    DoublePosit::new(p, c)
+}
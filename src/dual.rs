@@ -0,0 +1,309 @@
+use crate::posit::Posit;
+use crate::exact::ExactSum;
+use crate::gemm::{fused_dot, fused_sum};
+use ndarray::{Array1, ArrayView1, ScalarOperand};
+use num_traits::identities::{One, Zero};
+use num_traits::sign::Signed;
+use num_traits::Num;
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, Sub, SubAssign};
+
+// re + eps ε with ε^2 = 0: the value of a function and its derivative along one direction
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Dual<P> {
+    pub re: P,
+    pub eps: P,
+}
+
+impl<const N: u8, const ES: u8> Dual<Posit<N, ES>> {
+    pub const NAR: Self = Self::new(Posit::NAR, Posit::NAR);
+
+    #[inline]
+    pub const fn new(re: Posit<N, ES>, eps: Posit<N, ES>) -> Self {
+        Self{re, eps}
+    }
+
+    // a constant, with zero derivative
+    pub fn constant(re: Posit<N, ES>) -> Self {
+        Self::new(re, Posit::zero())
+    }
+
+    // the variable being differentiated, with unit derivative
+    pub fn variable(re: Posit<N, ES>) -> Self {
+        Self::new(re, Posit::one())
+    }
+
+    #[inline]
+    pub fn is_nar(self) -> bool {
+        self.re.is_nar() || self.eps.is_nar()
+    }
+
+    // (f(re), f'(re) eps) with f and f' evaluated in f64, each part rounded once
+    fn chain<F, D>(self, f: F, df: D) -> Self
+        where F: Fn(f64) -> f64, D: Fn(f64) -> f64
+    {
+        if self.is_nar() {
+            return Self::NAR;
+        }
+
+        let x = f64::from(self.re);
+        Self::new(Posit::from(f(x)), Posit::from(df(x) * f64::from(self.eps)))
+    }
+
+    // a b + c with each part rounded once
+    pub fn mul_add(self, b: Self, c: Self) -> Self {
+        Self::new(
+            fused_dot(c.re, [(self.re, b.re)]),
+            fused_dot(c.eps, [(self.re, b.eps), (self.eps, b.re)]),
+        )
+    }
+
+    pub fn abs(self) -> Self {
+        if self.re.is_negative() {-self} else {self}
+    }
+
+    // 1 / x and -eps / x^2 as exact ratios
+    pub fn recip(self) -> Self {
+        Self::one() / self
+    }
+
+    // the value from the correctly rounded posit square root
+    pub fn sqrt(self) -> Self {
+        let d = self.chain(f64::sqrt, |x| 0.5 / x.sqrt());
+        Self::new(self.re.sqrt(), d.eps)
+    }
+
+    pub fn exp(self) -> Self {
+        self.chain(f64::exp, f64::exp)
+    }
+
+    pub fn ln(self) -> Self {
+        self.chain(f64::ln, |x| 1.0 / x)
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        self.chain(|x| x.powi(n), |x| n as f64 * x.powi(n - 1))
+    }
+
+    pub fn powf(self, a: f64) -> Self {
+        self.chain(|x| x.powf(a), |x| a * x.powf(a - 1.0))
+    }
+
+    pub fn sin(self) -> Self {
+        self.chain(f64::sin, f64::cos)
+    }
+
+    pub fn cos(self) -> Self {
+        self.chain(f64::cos, |x| -x.sin())
+    }
+
+    pub fn tan(self) -> Self {
+        self.chain(f64::tan, |x| 1.0 / x.cos().powi(2))
+    }
+
+    pub fn asin(self) -> Self {
+        self.chain(f64::asin, |x| 1.0 / (1.0 - x * x).sqrt())
+    }
+
+    pub fn acos(self) -> Self {
+        self.chain(f64::acos, |x| -1.0 / (1.0 - x * x).sqrt())
+    }
+
+    pub fn atan(self) -> Self {
+        self.chain(f64::atan, |x| 1.0 / (1.0 + x * x))
+    }
+
+    pub fn sinh(self) -> Self {
+        self.chain(f64::sinh, f64::cosh)
+    }
+
+    pub fn cosh(self) -> Self {
+        self.chain(f64::cosh, f64::sinh)
+    }
+
+    pub fn tanh(self) -> Self {
+        self.chain(f64::tanh, |x| 1.0 - x.tanh().powi(2))
+    }
+}
+
+impl<const N: u8, const ES: u8> fmt::Display for Dual<Posit<N, ES>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.eps.is_negative() && !self.eps.is_nar() {""} else {"+"};
+        write!(f, "{}{}{}ε", f32::from(self.re), sign, f32::from(self.eps))
+    }
+}
+
+impl<const N: u8, const ES: u8> From<Posit<N, ES>> for Dual<Posit<N, ES>> {
+    fn from(re: Posit<N, ES>) -> Self {
+        Self::constant(re)
+    }
+}
+
+impl<const N: u8, const ES: u8> Neg for Dual<Posit<N, ES>> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.eps)
+    }
+}
+
+impl<const N: u8, const ES: u8> Add for Dual<Posit<N, ES>> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.eps + other.eps)
+    }
+}
+
+impl<const N: u8, const ES: u8> Sub for Dual<Posit<N, ES>> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.eps - other.eps)
+    }
+}
+
+// product rule, the derivative a db + b da rounded once
+impl<const N: u8, const ES: u8> Mul for Dual<Posit<N, ES>> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.mul_add(other, Self::zero())
+    }
+}
+
+// a / b and (b da - a db) / b^2 as ratios of exact sums, each rounded once
+impl<const N: u8, const ES: u8> Div for Dual<Posit<N, ES>> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let (a, da, b, db) = (self.re, self.eps, other.re, other.eps);
+
+        let re = ExactSum::from(a).ratio(ExactSum::from(b));
+        let eps = fused_sum(Posit::zero(), [(b, da), (-a, db)]).ratio(fused_sum(Posit::zero(), [(b, b)]));
+
+        Self::new(re, eps)
+    }
+}
+
+// a - q b with q = trunc(a / b), so the derivative is da - q db
+impl<const N: u8, const ES: u8> Rem for Dual<Posit<N, ES>> {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        if self.is_nar() || other.is_nar() || other.re.is_zero() {
+            return Self::NAR;
+        }
+
+        let q = -Posit::from((f64::from(self.re) / f64::from(other.re)).trunc());
+        Self::new(fused_dot(self.re, [(q, other.re)]), fused_dot(self.eps, [(q, other.eps)]))
+    }
+}
+
+impl<const N: u8, const ES: u8> Mul<Posit<N, ES>> for Dual<Posit<N, ES>> {
+    type Output = Self;
+
+    fn mul(self, other: Posit<N, ES>) -> Self {
+        Self::new(self.re * other, self.eps * other)
+    }
+}
+
+impl<const N: u8, const ES: u8> Div<Posit<N, ES>> for Dual<Posit<N, ES>> {
+    type Output = Self;
+
+    fn div(self, other: Posit<N, ES>) -> Self {
+        self / Self::constant(other)
+    }
+}
+
+impl<const N: u8, const ES: u8> AddAssign for Dual<Posit<N, ES>> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const N: u8, const ES: u8> SubAssign for Dual<Posit<N, ES>> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const N: u8, const ES: u8> MulAssign for Dual<Posit<N, ES>> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl<const N: u8, const ES: u8> DivAssign for Dual<Posit<N, ES>> {
+    fn div_assign(&mut self, other: Self) {
+        *self = *self / other;
+    }
+}
+
+impl<const N: u8, const ES: u8> Zero for Dual<Posit<N, ES>> {
+    fn zero() -> Self {
+        Self::new(Posit::zero(), Posit::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.re.is_zero() && self.eps.is_zero()
+    }
+}
+
+impl<const N: u8, const ES: u8> One for Dual<Posit<N, ES>> {
+    fn one() -> Self {
+        Self::constant(Posit::one())
+    }
+}
+
+// parses a constant
+impl<const N: u8, const ES: u8> Num for Dual<Posit<N, ES>> {
+    type FromStrRadixErr = <Posit<N, ES> as Num>::FromStrRadixErr;
+
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        Ok(Self::constant(Posit::from_str_radix(src, radix)?))
+    }
+}
+
+impl<const N: u8, const ES: u8> ScalarOperand for Dual<Posit<N, ES>> {}
+
+// sum(a b) with the value and the derivative each accumulated exactly and rounded once
+pub fn dot<const N: u8, const ES: u8>(a: ArrayView1<Dual<Posit<N, ES>>>, b: ArrayView1<Dual<Posit<N, ES>>>) -> Dual<Posit<N, ES>> {
+    assert_eq!(a.len(), b.len(), "dot: lengths differ");
+
+    Dual::new(
+        fused_dot(Posit::zero(), a.iter().zip(b).map(|(x, y)| (x.re, y.re))),
+        fused_dot(Posit::zero(), a.iter().zip(b).flat_map(|(x, y)| [(x.re, y.eps), (x.eps, y.re)])),
+    )
+}
+
+// (f(x), f'(x))
+pub fn derivative<const N: u8, const ES: u8, F>(f: F, x: Posit<N, ES>) -> (Posit<N, ES>, Posit<N, ES>)
+    where F: Fn(Dual<Posit<N, ES>>) -> Dual<Posit<N, ES>>
+{
+    let y = f(Dual::variable(x));
+    (y.re, y.eps)
+}
+
+// the derivative of f at x along v
+pub fn jvp<const N: u8, const ES: u8, F>(f: F, x: ArrayView1<Posit<N, ES>>, v: ArrayView1<Posit<N, ES>>) -> (Posit<N, ES>, Posit<N, ES>)
+    where F: Fn(ArrayView1<Dual<Posit<N, ES>>>) -> Dual<Posit<N, ES>>
+{
+    assert_eq!(x.len(), v.len(), "jvp: lengths differ");
+
+    let seeded: Array1<_> = x.iter().zip(v).map(|(&x, &v)| Dual::new(x, v)).collect();
+    let y = f(seeded.view());
+    (y.re, y.eps)
+}
+
+// (f(x), grad f(x)) from one forward pass per coordinate
+pub fn gradient<const N: u8, const ES: u8, F>(f: F, x: ArrayView1<Posit<N, ES>>) -> (Posit<N, ES>, Array1<Posit<N, ES>>)
+    where F: Fn(ArrayView1<Dual<Posit<N, ES>>>) -> Dual<Posit<N, ES>>
+{
+    let value = f(x.mapv(Dual::constant).view()).re;
+    let grad = (0..x.len()).map(|k| {
+        jvp(&f, x, Array1::from_shape_fn(x.len(), |i| if i == k {Posit::one()} else {Posit::zero()}).view()).1
+    }).collect();
+
+    (value, grad)
+}
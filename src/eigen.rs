@@ -0,0 +1,198 @@
+use crate::accumulator;
+use crate::krylov::{rotate, Scalar};
+use crate::linalg::LinalgError;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JacobiConfig {
+    // pairs with |a_pq| <= tol sqrt(|a_pp a_qq|) are treated as decoupled
+    pub tol: f64,
+    pub max_sweeps: usize,
+}
+
+impl Default for JacobiConfig {
+    fn default() -> Self {
+        Self{tol: 1e-7, max_sweeps: 30}
+    }
+}
+
+// A = V diag(values) V^T with values ascending
+#[derive(Clone, Debug, PartialEq)]
+pub struct Eigh<P> {
+    pub values: Array1<P>,
+    pub vectors: Array2<P>,
+    pub sweeps: usize,
+    pub converged: bool,
+    pub orthogonality_loss: f64,
+}
+
+// thin A = U diag(s) V^T with s descending
+#[derive(Clone, Debug, PartialEq)]
+pub struct Svd<P> {
+    pub u: Array2<P>,
+    pub s: Array1<P>,
+    pub vt: Array2<P>,
+    pub sweeps: usize,
+    pub converged: bool,
+    // of U and of V
+    pub orthogonality_loss: (f64, f64),
+}
+
+// max |Q^T Q - I| evaluated in f64
+pub fn orthogonality_loss<P: Scalar>(q: ArrayView2<P>) -> f64 {
+    let q = q.mapv(P::to_f64);
+    let g = q.t().dot(&q);
+
+    g.indexed_iter().map(|((i, j), &v)| (v - if i == j {1.0} else {0.0}).abs()).fold(0.0, f64::max)
+}
+
+fn abs<P: Scalar>(x: P) -> P {
+    if x < P::zero() {-x} else {x}
+}
+
+fn dot<P: Scalar>(a: ArrayView1<P>, b: ArrayView1<P>) -> P {
+    accumulator::dot::<P, P::Acc>(a, b)
+}
+
+fn check_finite<P: Scalar>(a: ArrayView2<P>) -> Result<(), LinalgError> {
+    if a.iter().all(|x| x.to_f64().is_finite()) {Ok(())} else {Err(LinalgError::NaR(0))}
+}
+
+// (c, s) of the rotation annihilating the coupling, from zeta = (a_qq - a_pp) / (2 a_pq)
+fn rotation<P: Scalar>(zeta: P) -> (P, P) {
+    let one = P::one();
+    let t = one / (abs(zeta) + (zeta * zeta + one).sqrt());
+    let t = if zeta < P::zero() {-t} else {t};
+    let c = one / (t * t + one).sqrt();
+
+    (c, t * c)
+}
+
+// replaces columns p and q of w by c w_p - s w_q and s w_p + c w_q
+fn rotate_columns<P: Scalar>(w: &mut Array2<P>, p: usize, q: usize, c: P, s: P) {
+    for k in 0..w.nrows() {
+        let (x, y) = (w[[k, p]], w[[k, q]]);
+        w[[k, p]] = rotate::<P, P::Acc>(c, x, -s, y);
+        w[[k, q]] = rotate::<P, P::Acc>(s, x, c, y);
+    }
+}
+
+fn decoupled<P: Scalar>(apq: P, app: P, aqq: P, tol: f64) -> bool {
+    apq.to_f64().abs() <= tol * (app.to_f64() * aqq.to_f64()).abs().sqrt()
+}
+
+// cyclic Jacobi; only the lower triangle of a is read
+pub fn eigh<P: Scalar>(a: ArrayView2<P>, cfg: JacobiConfig) -> Result<Eigh<P>, LinalgError> {
+    let n = a.nrows();
+    if a.ncols() != n {
+        return Err(LinalgError::Shape);
+    }
+
+    let mut w = Array2::from_shape_fn((n, n), |(i, j)| if i >= j {a[[i, j]]} else {a[[j, i]]});
+    check_finite(w.view())?;
+    let mut v = Array2::from_shape_fn((n, n), |(i, j)| if i == j {P::one()} else {P::zero()});
+    let mut sweeps = 0;
+    let mut converged = false;
+
+    while !converged && sweeps < cfg.max_sweeps {
+        sweeps += 1;
+        converged = true;
+
+        for p in 0..n {
+            for q in p + 1..n {
+                let (app, aqq, apq) = (w[[p, p]], w[[q, q]], w[[p, q]]);
+                if apq.is_zero() || decoupled(apq, app, aqq, cfg.tol) {
+                    continue;
+                }
+                converged = false;
+
+                let two = P::one() + P::one();
+                let (c, s) = rotation((aqq - app) / (two * apq));
+
+                // J^T W J applied to columns, then to rows through the symmetry
+                rotate_columns(&mut w, p, q, c, s);
+                for k in 0..n {
+                    w[[p, k]] = w[[k, p]];
+                    w[[q, k]] = w[[k, q]];
+                }
+
+                let t = s / c;
+                w[[p, p]] = app - t * apq;
+                w[[q, q]] = aqq + t * apq;
+                w[[p, q]] = P::zero();
+                w[[q, p]] = P::zero();
+
+                rotate_columns(&mut v, p, q, c, s);
+            }
+        }
+    }
+
+    check_finite(w.view()).and(check_finite(v.view())).map_err(|_| LinalgError::NaR(sweeps))?;
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| w[[i, i]].partial_cmp(&w[[j, j]]).unwrap());
+
+    let values = order.iter().map(|&i| w[[i, i]]).collect();
+    let vectors = v.select(Axis(1), &order);
+    let orthogonality_loss = orthogonality_loss(vectors.view());
+
+    Ok(Eigh{values, vectors, sweeps, converged, orthogonality_loss})
+}
+
+// one-sided (Hestenes) Jacobi, orthogonalizing the columns of A
+pub fn svd<P: Scalar>(a: ArrayView2<P>, cfg: JacobiConfig) -> Result<Svd<P>, LinalgError> {
+    let (m, n) = a.dim();
+    if m < n {
+        let f = svd(a.t(), cfg)?;
+        let (lu, lv) = f.orthogonality_loss;
+        return Ok(Svd{u: f.vt.reversed_axes(), s: f.s, vt: f.u.reversed_axes(), sweeps: f.sweeps, converged: f.converged, orthogonality_loss: (lv, lu)});
+    }
+    check_finite(a)?;
+
+    let mut u = a.to_owned();
+    let mut v = Array2::from_shape_fn((n, n), |(i, j)| if i == j {P::one()} else {P::zero()});
+    let mut sweeps = 0;
+    let mut converged = false;
+
+    while !converged && sweeps < cfg.max_sweeps {
+        sweeps += 1;
+        converged = true;
+
+        for p in 0..n {
+            for q in p + 1..n {
+                let alpha = dot(u.column(p), u.column(p));
+                let beta = dot(u.column(q), u.column(q));
+                let gamma = dot(u.column(p), u.column(q));
+                if gamma.is_zero() || decoupled(gamma, alpha, beta, cfg.tol) {
+                    continue;
+                }
+                converged = false;
+
+                let two = P::one() + P::one();
+                let (c, s) = rotation((beta - alpha) / (two * gamma));
+
+                rotate_columns(&mut u, p, q, c, s);
+                rotate_columns(&mut v, p, q, c, s);
+            }
+        }
+    }
+
+    check_finite(u.view()).and(check_finite(v.view())).map_err(|_| LinalgError::NaR(sweeps))?;
+
+    let norms: Vec<P> = (0..n).map(|j| dot(u.column(j), u.column(j)).sqrt()).collect();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| norms[j].partial_cmp(&norms[i]).unwrap());
+
+    let s: Array1<P> = order.iter().map(|&j| norms[j]).collect();
+    let mut u = u.select(Axis(1), &order);
+    for (mut col, &sj) in u.columns_mut().into_iter().zip(&s) {
+        if !sj.is_zero() {
+            col.mapv_inplace(|x| x / sj);
+        }
+    }
+    let v = v.select(Axis(1), &order);
+
+    let orthogonality_loss = (orthogonality_loss(u.view()), orthogonality_loss(v.view()));
+
+    Ok(Svd{u, s, vt: v.reversed_axes(), sweeps, converged, orthogonality_loss})
+}
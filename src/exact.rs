@@ -0,0 +1,350 @@
+use crate::posit::{Posit, isqrt};
+use crate::quire::Quire;
+use crate::window::{Window, Wide};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Neg, Sub};
+use num_traits::identities::{One, Zero};
+
+// storage once a format's quire outgrows 128 bits: posit32 with es = 2 needs
+// 482 bits for a product, the rest is carry room
+type Store = Wide<8>;
+
+// a posit as signed significand * 2^exp, NaR and zero both pack to 0
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Packed {
+    m: i32,
+    e: i32,
+}
+
+impl Packed {
+    // exp is biased by `bias` so a product lands at quire position ea + eb
+    pub(crate) fn new<const N: u8, const ES: u8>(p: Posit<N, ES>, bias: i32) -> Self {
+        if p.is_zero() || p.is_nar() {
+            return Self::default();
+        }
+
+        let (s, f, e) = p.to_scaled();
+        let k = 32 - (N - ES - 1) as i32;
+        let m = (f >> k) as i32;
+
+        Self{m: if s {-m} else {m}, e: e + k + bias}
+    }
+}
+
+// exact product of two packed posits as a quire integer; low bits dropped by
+// a right shift are always zero
+#[inline(always)]
+pub(crate) fn product<W: Window>(a: Packed, b: Packed) -> W {
+    let p = (a.m as i64 * b.m as i64) as i128;
+    let shift = a.e + b.e;
+
+    if shift >= 0 {W::from_i128(p).shl(shift as u32)} else {W::from_i128(p >> -shift)}
+}
+
+// sign, magnitude with the dropped bits stuck to its last bit, and the shift of that magnitude
+fn split<W: Window>(v: W) -> (bool, u128, i32) {
+    let (m, shift) = v.abs().to_u128_sticky();
+    (v.is_negative(), m, shift as i32)
+}
+
+// v * 2^exp rounded once
+pub(crate) fn round<W: Window, const N: u8, const ES: u8>(v: W, exp: i32) -> Posit<N, ES> {
+    let (s, m, shift) = split(v);
+    Posit::from_scaled(s, m, exp + shift)
+}
+
+// num / den * 2^exp for non-negative num and den, correctly rounded
+fn quotient<W: Window, const N: u8, const ES: u8>(s: bool, num: W, den: W, exp: i32) -> Posit<N, ES> {
+    if den.is_zero() {
+        return Posit::NAR;
+    }
+
+    if num.is_zero() {
+        return Posit::zero();
+    }
+
+    // the leading one of num just under the sign bit, the quotient keeps over BITS - 3 bits
+    let la = num.leading_zeros() - 1;
+    let k = W::BITS - 1 - den.leading_zeros();
+
+    let (q, st) = num.shl(la).div_shl(den, k);
+    let (top, shift) = q.to_u128_sticky();
+
+    Posit::from_scaled(s, top | st as u128, exp + shift as i32 - la as i32 - k as i32)
+}
+
+// square root of a non-negative v * 2^exp, correctly rounded
+fn root<W: Window, const N: u8, const ES: u8>(v: W, exp: i32) -> Posit<N, ES> {
+    let (_, m, shift) = split(v);
+    let e = exp + shift;
+
+    if m == 0 {
+        return Posit::zero();
+    }
+
+    // normalise with an even total exponent so it halves exactly; m has at most 127 bits
+    let lz = m.leading_zeros() as i32;
+    let sh = lz - ((lz - e) & 1);
+
+    let m = m << sh;
+    let r = isqrt(m);
+
+    Posit::from_scaled(false, (r << 1) | (r * r != m) as u128, (e - sh) / 2 - 1)
+}
+
+/// `c + sum(a b)` held exactly: in 128 bits while the format's quire fits there, in 512 otherwise.
+///
+/// Formats whose products outgrow 512 bits are rejected at compile time:
+///
+/// ```compile_fail
+/// use namagiri::{posit::Posit, exact::ExactSum};
+/// let _ = ExactSum::from(Posit::<32, 3>(0x4000_0000));
+/// ```
+#[derive(Clone, Copy, Default)]
+pub struct ExactSum<const N: u8, const ES: u8> {
+    narrow: u128,
+    wide: Store,
+    nar: bool,
+}
+
+impl<const N: u8, const ES: u8> ExactSum<N, ES> {
+    const VALID: () = assert!(
+        Quire::<N, ES>::FITS || 2 * (Quire::<N, ES>::BIAS + 1) + 30 <= <Store as Window>::BITS as i32,
+        "ExactSum: the format's products must fit 512 bits with room for carries"
+    );
+
+    // one unit of the integer is 2^-FRAC
+    pub(crate) const FRAC: i32 = Quire::<N, ES>::BIAS + 1;
+
+    pub const NAR: Self = Self{narrow: 0, wide: Wide([0; 8]), nar: true};
+
+    #[inline]
+    pub fn is_nar(self) -> bool {
+        self.nar
+    }
+
+    #[inline]
+    pub fn is_negative(self) -> bool {
+        !self.nar && if Quire::<N, ES>::FITS {self.narrow.is_negative()} else {self.wide.is_negative()}
+    }
+
+    #[inline]
+    pub(crate) fn add_packed(&mut self, a: Packed, b: Packed) {
+        if Quire::<N, ES>::FITS {
+            self.narrow = self.narrow.wrapping_add(product(a, b));
+        }
+        else {
+            self.wide = self.wide.wrapping_add(product(a, b));
+        }
+    }
+
+    // packs a posit so its products with a plain packed posit land on this scale
+    pub(crate) fn pack(p: Posit<N, ES>) -> Packed {
+        Packed::new(p, Self::FRAC)
+    }
+
+    // += a b, exactly
+    #[inline]
+    pub fn fma(&mut self, a: Posit<N, ES>, b: Posit<N, ES>) {
+        self.nar |= a.is_nar() || b.is_nar();
+        self.add_packed(Self::pack(a), Packed::new(b, 0));
+    }
+
+    // the exact product a b
+    pub fn product(a: Posit<N, ES>, b: Posit<N, ES>) -> Self {
+        let mut q = Self::zero();
+        q.fma(a, b);
+        q
+    }
+
+    pub fn abs(self) -> Self {
+        if self.is_negative() {-self} else {self}
+    }
+
+    // correctly rounded into any posit format
+    pub fn to_posit<const M: u8, const MS: u8>(self) -> Posit<M, MS> {
+        if self.nar {
+            return Posit::NAR;
+        }
+
+        if Quire::<N, ES>::FITS {round(self.narrow, -Self::FRAC)} else {round(self.wide, -Self::FRAC)}
+    }
+
+    pub fn div_posit(self, other: Posit<N, ES>) -> Posit<N, ES> {
+        if self.nar || other.is_nar() || other.is_zero() {
+            return Posit::NAR;
+        }
+
+        let (so, f, e) = other.to_scaled();
+        let s = self.is_negative() ^ so;
+
+        if Quire::<N, ES>::FITS {
+            quotient(s, self.narrow.abs(), f as u128, -Self::FRAC - e)
+        }
+        else {
+            quotient(s, self.wide.abs(), Store::from_i128(f as i128), -Self::FRAC - e)
+        }
+    }
+
+    pub fn div_int(self, n: i64) -> Posit<N, ES> {
+        if self.nar {
+            return Posit::NAR;
+        }
+
+        let s = self.is_negative() ^ (n < 0);
+        let d = n.unsigned_abs() as i128;
+
+        if Quire::<N, ES>::FITS {
+            quotient(s, self.narrow.abs(), d as u128, -Self::FRAC)
+        }
+        else {
+            quotient(s, self.wide.abs(), Store::from_i128(d), -Self::FRAC)
+        }
+    }
+
+    // self / other rounded once into any posit format, NaR when other is zero
+    pub fn ratio<const M: u8, const MS: u8>(self, other: Self) -> Posit<M, MS> {
+        if self.nar || other.nar {
+            return Posit::NAR;
+        }
+
+        let s = self.is_negative() ^ other.is_negative();
+
+        if Quire::<N, ES>::FITS {
+            quotient(s, self.narrow.abs(), other.narrow.abs(), 0)
+        }
+        else {
+            quotient(s, self.wide.abs(), other.wide.abs(), 0)
+        }
+    }
+
+    // correctly rounded square root of the exact value, NaR when negative
+    pub fn sqrt(self) -> Posit<N, ES> {
+        if self.nar || self.is_negative() {
+            return Posit::NAR;
+        }
+
+        if Quire::<N, ES>::FITS {root(self.narrow, -Self::FRAC)} else {root(self.wide, -Self::FRAC)}
+    }
+
+    // the integer on the 2^-FRAC scale sign-extended into another window, wrapping if it is narrower
+    pub(crate) fn widen<W: Window>(self) -> W {
+        if Quire::<N, ES>::FITS {
+            return W::from_i128(self.narrow as i128);
+        }
+
+        let (&top, rest) = self.wide.0.split_last().unwrap();
+        rest.iter().rev().fold(W::from_i128(top as i64 as i128), |v, &l| v.shl(64).wrapping_add(W::from_i128(l as i128)))
+    }
+
+    pub fn to_f64(self) -> f64 {
+        if self.nar {
+            return f64::INFINITY;
+        }
+
+        let (s, m, shift) = if Quire::<N, ES>::FITS {split(self.narrow)} else {split(self.wide)};
+        let v = m as f64 * 2f64.powi(shift - Self::FRAC);
+
+        if s {-v} else {v}
+    }
+}
+
+impl<const N: u8, const ES: u8> std::convert::From<Posit<N, ES>> for ExactSum<N, ES> {
+    fn from(item: Posit<N, ES>) -> Self {
+        Self::product(item, Posit::one())
+    }
+}
+
+impl<const N: u8, const ES: u8> std::convert::From<ExactSum<N, ES>> for Posit<N, ES> {
+    fn from(item: ExactSum<N, ES>) -> Self {
+        item.to_posit()
+    }
+}
+
+impl<const N: u8, const ES: u8> Neg for ExactSum<N, ES> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self{narrow: self.narrow.wrapping_neg(), wide: self.wide.wrapping_neg(), nar: self.nar}
+    }
+}
+
+impl<const N: u8, const ES: u8> Add for ExactSum<N, ES> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        Self{
+            narrow: self.narrow.wrapping_add(other.narrow),
+            wide: if Quire::<N, ES>::FITS {self.wide} else {self.wide.wrapping_add(other.wide)},
+            nar: self.nar || other.nar,
+        }
+    }
+}
+
+impl<const N: u8, const ES: u8> AddAssign for ExactSum<N, ES> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const N: u8, const ES: u8> Sub for ExactSum<N, ES> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + (- other)
+    }
+}
+
+// by value, with NaR below everything as for posits
+impl<const N: u8, const ES: u8> Ord for ExactSum<N, ES> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.nar, other.nar) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ if Quire::<N, ES>::FITS => (self.narrow as i128).cmp(&(other.narrow as i128)),
+            _ => {
+                let d = self.wide.wrapping_add(other.wide.wrapping_neg());
+                if d.is_zero() {Ordering::Equal} else if d.is_negative() {Ordering::Less} else {Ordering::Greater}
+            },
+        }
+    }
+}
+
+impl<const N: u8, const ES: u8> PartialOrd for ExactSum<N, ES> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: u8, const ES: u8> PartialEq for ExactSum<N, ES> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<const N: u8, const ES: u8> Eq for ExactSum<N, ES> {}
+
+impl<const N: u8, const ES: u8> Zero for ExactSum<N, ES> {
+    fn zero() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID;
+        Self::default()
+    }
+
+    fn is_zero(&self) -> bool {
+        !self.nar && if Quire::<N, ES>::FITS {self.narrow == 0} else {self.wide.is_zero()}
+    }
+}
+
+impl<const N: u8, const ES: u8> fmt::Debug for ExactSum<N, ES> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.nar {
+            write!(f, "ExactSum(NaR)")
+        }
+        else {
+            write!(f, "ExactSum({})", self.to_f64())
+        }
+    }
+}
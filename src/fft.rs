@@ -0,0 +1,171 @@
+use crate::posit::Posit;
+use crate::complex::{ComplexPosit, ComplexQuire};
+use crate::accumulator::Accumulator;
+use ndarray::{Array1, ArrayView1};
+use num_traits::Float;
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Butterfly {
+    // every twiddle product and every sum rounded
+    Rounded,
+    // each output of a radix-p butterfly accumulated in a quire and rounded once
+    Fused,
+}
+
+// smallest factors first, so powers of two run as radix-2 stages
+fn factorize(mut n: usize) -> Vec<usize> {
+    let mut factors = Vec::new();
+    let mut p = 2;
+
+    while n > 1 {
+        if p * p > n {
+            factors.push(n);
+            break;
+        }
+
+        let (q, r) = (n / p, n % p);
+        if r == 0 {
+            factors.push(p);
+            n = q;
+        }
+        else {
+            p += 1;
+        }
+    }
+
+    factors
+}
+
+// e^{-2 pi i k / n} in f64, with the exact zeros of cos and sin kept exact
+fn twiddle(k: usize, n: usize) -> (f64, f64) {
+    let (s, c) = (-2.0 * PI * k as f64 / n as f64).sin_cos();
+    let snap = |x: f64| if x.abs() < 1e-12 {0.0} else {x};
+
+    (snap(c), snap(s))
+}
+
+// the schoolbook product, each of the four real products and both sums rounded
+fn rounded_mul<const N: u8, const ES: u8>(a: ComplexPosit<N, ES>, b: ComplexPosit<N, ES>) -> ComplexPosit<N, ES> {
+    ComplexPosit::new(a.re * b.re - a.im * b.im, a.re * b.im + a.im * b.re)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FftPlan<const N: u8, const ES: u8> {
+    n: usize,
+    factors: Vec<usize>,
+    // e^{-2 pi i k / n}, each part rounded once
+    twiddles: Vec<ComplexPosit<N, ES>>,
+    pub butterfly: Butterfly,
+}
+
+impl<const N: u8, const ES: u8> FftPlan<N, ES> {
+    pub fn new(n: usize, butterfly: Butterfly) -> Self {
+        assert!(n > 0, "fft: empty transform");
+
+        let twiddles = (0..n).map(|k| ComplexPosit::from(twiddle(k, n))).collect();
+        Self{n, factors: factorize(n), twiddles, butterfly}
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn factors(&self) -> &[usize] {
+        &self.factors
+    }
+
+    pub fn twiddles(&self) -> &[ComplexPosit<N, ES>] {
+        &self.twiddles
+    }
+
+    // X[j] = sum_r W_L^{rj} Y_r[j mod m] / d over the p sub-transforms Y_r of length m = L / p
+    fn transform(&self, x: Vec<ComplexPosit<N, ES>>, factors: &[usize], d: usize) -> Vec<ComplexPosit<N, ES>> {
+        let len = x.len();
+        if len == 1 {
+            return x;
+        }
+
+        let p = factors[0];
+        let m = len / p;
+        let stride = self.n / len;
+
+        let subs: Vec<Vec<_>> = (0..p).map(|r| self.transform(x.iter().skip(r).step_by(p).cloned().collect(), &factors[1..], 1)).collect();
+        let w = |r: usize, j: usize| self.twiddles[(r * j % len) * stride];
+
+        (0..len).map(|j| match self.butterfly {
+            // the division rounds a second time
+            Butterfly::Rounded => {
+                let z = (1..p).fold(subs[0][j % m], |acc, r| acc + rounded_mul(w(r, j), subs[r][j % m]));
+                if d == 1 {z} else {z / Posit::from(d as f64)}
+            },
+            // the division happens on the exact sums, so each part still rounds once
+            Butterfly::Fused => {
+                let mut acc = ComplexQuire::init();
+                (0..p).for_each(|r| acc.fma(w(r, j), subs[r][j % m]));
+                if d == 1 {acc.finish()} else {ComplexPosit::new(acc.re.div_int(d as i64), acc.im.div_int(d as i64))}
+            },
+        }).collect()
+    }
+
+    pub fn forward(&self, x: ArrayView1<ComplexPosit<N, ES>>) -> Array1<ComplexPosit<N, ES>> {
+        assert_eq!(x.len(), self.n, "fft: length differs from the plan");
+        Array1::from(self.transform(x.to_vec(), &self.factors, 1))
+    }
+
+    // conj(F conj(x)) / n with 1/n folded into the last stage
+    pub fn inverse(&self, x: ArrayView1<ComplexPosit<N, ES>>) -> Array1<ComplexPosit<N, ES>> {
+        assert_eq!(x.len(), self.n, "ifft: length differs from the plan");
+
+        let y = self.transform(x.iter().map(|z| z.conj()).collect(), &self.factors, self.n);
+        y.into_iter().map(|z| z.conj()).collect()
+    }
+}
+
+pub fn fft<const N: u8, const ES: u8>(x: ArrayView1<ComplexPosit<N, ES>>) -> Array1<ComplexPosit<N, ES>> {
+    FftPlan::new(x.len(), Butterfly::Fused).forward(x)
+}
+
+pub fn ifft<const N: u8, const ES: u8>(x: ArrayView1<ComplexPosit<N, ES>>) -> Array1<ComplexPosit<N, ES>> {
+    FftPlan::new(x.len(), Butterfly::Fused).inverse(x)
+}
+
+fn transform_float<T: Float>(x: Vec<(T, T)>, factors: &[usize], sign: f64) -> Vec<(T, T)> {
+    let len = x.len();
+    if len == 1 {
+        return x;
+    }
+
+    let p = factors[0];
+    let m = len / p;
+
+    let subs: Vec<Vec<_>> = (0..p).map(|r| transform_float(x.iter().skip(r).step_by(p).cloned().collect(), &factors[1..], sign)).collect();
+
+    (0..len).map(|j| (1..p).fold(subs[0][j % m], |(re, im), r| {
+        let (c, s) = twiddle(r * j % len, len);
+        let (c, s) = (T::from(c).unwrap(), T::from(sign * s).unwrap());
+        let (a, b) = subs[r][j % m];
+
+        (re + (c * a - s * b), im + (c * b + s * a))
+    })).collect()
+}
+
+// the same mixed-radix transform in any float type (f32, f64 or an fp16 type implementing Float), as a baseline
+pub fn fft_float<T: Float>(x: &[(T, T)], inverse: bool) -> Vec<(T, T)> {
+    assert!(!x.is_empty(), "fft: empty transform");
+
+    let y = transform_float(x.to_vec(), &factorize(x.len()), if inverse {-1.0} else {1.0});
+    let n = T::from(x.len()).unwrap();
+
+    if inverse {y.into_iter().map(|(re, im)| (re / n, im / n)).collect()} else {y}
+}
+
+// 10 log10(sum |ref|^2 / sum |ref - x|^2)
+pub fn snr_db(reference: &[(f64, f64)], x: &[(f64, f64)]) -> f64 {
+    assert_eq!(reference.len(), x.len(), "snr: lengths differ");
+
+    let signal: f64 = reference.iter().map(|(a, b)| a * a + b * b).sum();
+    let noise: f64 = reference.iter().zip(x).map(|((a, b), (c, d))| (a - c).powi(2) + (b - d).powi(2)).sum();
+
+    10.0 * (signal / noise).log10()
+}
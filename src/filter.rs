@@ -0,0 +1,138 @@
+use crate::posit::Posit;
+use crate::gemm::fused_dot;
+use ndarray::{Array1, ArrayView1};
+use num_traits::identities::Zero;
+
+pub trait Filter<P: Copy> {
+    fn process(&mut self, x: P) -> P;
+    fn reset(&mut self);
+
+    fn process_block(&mut self, x: ArrayView1<P>) -> Array1<P> {
+        x.iter().map(|&v| self.process(v)).collect()
+    }
+}
+
+// y[n] = sum_k h[k] x[n - k], each output rounded once
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fir<const N: u8, const ES: u8> {
+    taps: Vec<Posit<N, ES>>,
+    // circular history, history[pos] is the newest sample
+    history: Vec<Posit<N, ES>>,
+    pos: usize,
+}
+
+impl<const N: u8, const ES: u8> Fir<N, ES> {
+    pub fn new(taps: Vec<Posit<N, ES>>) -> Self {
+        assert!(!taps.is_empty(), "fir: no taps");
+
+        let history = vec![Posit::zero(); taps.len()];
+        Self{taps, history, pos: 0}
+    }
+
+    pub fn taps(&self) -> &[Posit<N, ES>] {
+        &self.taps
+    }
+}
+
+impl<const N: u8, const ES: u8> Filter<Posit<N, ES>> for Fir<N, ES> {
+    fn process(&mut self, x: Posit<N, ES>) -> Posit<N, ES> {
+        let n = self.taps.len();
+        self.pos = (self.pos + 1) % n;
+        self.history[self.pos] = x;
+
+        let (pos, history) = (self.pos, &self.history);
+        fused_dot(Posit::zero(), self.taps.iter().enumerate().map(|(k, &h)| (h, history[(pos + n - k) % n])))
+    }
+
+    fn reset(&mut self) {
+        self.history.fill(Posit::zero());
+        self.pos = 0;
+    }
+}
+
+// direct form I section with a0 = 1:
+// y[n] = b0 x[n] + b1 x[n-1] + b2 x[n-2] - a1 y[n-1] - a2 y[n-2], rounded once
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Biquad<const N: u8, const ES: u8> {
+    pub b: [Posit<N, ES>; 3],
+    pub a: [Posit<N, ES>; 2],
+    x: [Posit<N, ES>; 2],
+    y: [Posit<N, ES>; 2],
+}
+
+impl<const N: u8, const ES: u8> Biquad<N, ES> {
+    pub fn new(b: [Posit<N, ES>; 3], a: [Posit<N, ES>; 2]) -> Self {
+        Self{b, a, x: [Posit::zero(); 2], y: [Posit::zero(); 2]}
+    }
+
+    // normalises by a[0] in f64, so each coefficient is rounded once
+    pub fn from_f64(b: [f64; 3], a: [f64; 3]) -> Self {
+        assert!(a[0] != 0.0, "biquad: a0 is zero");
+
+        let b = b.map(|v| Posit::from(v / a[0]));
+        Self::new(b, [Posit::from(a[1] / a[0]), Posit::from(a[2] / a[0])])
+    }
+}
+
+impl<const N: u8, const ES: u8> Filter<Posit<N, ES>> for Biquad<N, ES> {
+    fn process(&mut self, x: Posit<N, ES>) -> Posit<N, ES> {
+        let (b, a) = (self.b, self.a);
+        let y = fused_dot(Posit::zero(), [(b[0], x), (b[1], self.x[0]), (b[2], self.x[1]), (-a[0], self.y[0]), (-a[1], self.y[1])]);
+
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+
+    fn reset(&mut self) {
+        self.x = [Posit::zero(); 2];
+        self.y = [Posit::zero(); 2];
+    }
+}
+
+// second-order sections applied in order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sos<const N: u8, const ES: u8> {
+    pub sections: Vec<Biquad<N, ES>>,
+}
+
+impl<const N: u8, const ES: u8> Filter<Posit<N, ES>> for Sos<N, ES> {
+    fn process(&mut self, x: Posit<N, ES>) -> Posit<N, ES> {
+        self.sections.iter_mut().fold(x, |v, s| s.process(v))
+    }
+
+    fn reset(&mut self) {
+        self.sections.iter_mut().for_each(Biquad::reset);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConvMode {
+    // every overlap, len(x) + len(h) - 1 outputs
+    Full,
+    // centred on x, len(x) outputs
+    Same,
+    // complete overlaps only, len(x) - len(h) + 1 outputs
+    Valid,
+}
+
+// linear convolution with one rounding per output
+pub fn convolve<const N: u8, const ES: u8>(x: ArrayView1<Posit<N, ES>>, h: ArrayView1<Posit<N, ES>>, mode: ConvMode) -> Array1<Posit<N, ES>> {
+    let (n, m) = (x.len(), h.len());
+    if n == 0 || m == 0 {
+        return Array1::zeros(0);
+    }
+
+    let full = n + m - 1;
+    let (start, len) = match mode {
+        ConvMode::Full => (0, full),
+        ConvMode::Same => ((m - 1) / 2, n),
+        ConvMode::Valid => (m.min(n) - 1, n.max(m) - n.min(m) + 1),
+    };
+
+    (start..start + len).map(|i| {
+        let lo = i.saturating_sub(m - 1);
+        let hi = i.min(n - 1);
+        fused_dot(Posit::zero(), (lo..=hi).map(|j| (x[j], h[i - j])))
+    }).collect()
+}
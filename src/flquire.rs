@@ -1,160 +1,255 @@
 use crate::posit::Posit;
-use std::cmp::min;
-
-#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
-pub struct FLQuire<const N: u8, const ES: u8, const SIZE: u8> {
-    pub quire:u128,
+use crate::quire::Quire;
+use crate::window::Window;
+
+/// A floating window of `SIZE` bits stored in `W`.
+///
+/// `SIZE` has to leave room for the sign, the guard bit and the `N - ES - 2` fraction bits,
+/// and must fit the storage, so this is rejected at compile time:
+///
+/// ```compile_fail
+/// use namagiri::{posit::Posit, flquire::FLQuire};
+/// let _ = FLQuire::<16, 1, 12>::from(Posit::<16, 1>(0x4000));
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct FLQuire<const N: u8, const ES: u8, const SIZE: u16, W: Window = u128> {
+    pub quire:W,
     pub sf:i32,
+    // the exact value lies strictly between quire and quire + 1 ulp
+    pub sticky:bool,
 }
 
-impl<const N: u8, const ES: u8, const SIZE: u8> FLQuire<N, ES, SIZE> {
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> FLQuire<N, ES, SIZE, W> {
+    const VALID: () = assert!(
+        SIZE as u32 >= (N - ES) as u32 && SIZE as u32 <= W::MAX_SIZE,
+        "FLQuire: SIZE must be at least N - ES and fit the window storage"
+    );
+
+    const FRAC: u32 = SIZE as u32 - 3;
+
     #[inline]
-    pub const fn new(quire: u128, sf: i32) -> Self {
-        Self{quire, sf}
+    pub const fn new(quire: W, sf: i32) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID;
+        Self{quire, sf, sticky: false}
     }
 
     pub const BIAS: i32 = 2i32.pow(ES as u32 + 1) * (N  as i32 - 2);
-}
 
-impl<const N: u8, const ES: u8, const SIZE: u8> std::convert::From<Posit<N, ES>> for FLQuire<N, ES, SIZE> {
-    fn from(item: Posit<N, ES>) -> Self {
+    // the storage's sign bit alone, which no normalised window reaches
+    pub fn nar() -> Self {
+        Self{quire: W::from_i128(1).shl(W::BITS - 1), sf: 0, sticky: false}
+    }
 
-        if item.is_zero() {
-            return Self{quire:0x0, sf:0x0};
+    #[inline]
+    pub fn is_nar(self) -> bool {
+        self.quire == W::from_i128(1).shl(W::BITS - 1)
+    }
+
+    pub fn scale(self, k: i32) -> Self {
+        if self.is_zero() {
+            self
+        }
+        else {
+            Self{sf: self.sf + k, ..self}
         }
+    }
 
-        let (_, s, rc, r, e, f) = item.encode();
+    // sign, magnitude and exponent with the sticky bit appended below the window
+    fn magnitude(self) -> (bool, u128, i32) {
+        let s = self.quire.is_negative();
+        let (quire, shift) = self.quire.abs().to_u128_sticky();
 
-        let rg = if rc {(!r).wrapping_add(1)} else {r};
+        let m = if !self.sticky {quire << 1} else if s {(quire << 1) - 1} else {(quire << 1) + 1};
 
-        let sf = (rg as i32) * 2i32.pow(ES as u32) + (e as i32);
+        (s, m, self.sf - Self::FRAC as i32 - 1 + shift as i32)
+    }
 
-        let mut quire: u128 = (f >> (32 - (N - ES - 2))) as u128;
+    fn normalize(quire: W, sf: i32, sticky: bool) -> Self {
+        if quire.is_zero() {
+            return Self{quire, sf: 0, sticky};
+        }
 
-        quire = quire << (SIZE as u32  - 2 - (N as u32 - ES as u32 - 2));
+        let lz = quire.abs().leading_zeros() as i32;
 
-        quire = if s {(!quire).wrapping_add(1)} else {quire};
+        // the sign and guard bits sit at SIZE - 1 and SIZE - 2
+        let shift = lz - (W::BITS as i32 - (SIZE as i32 - 2));
 
-        Self{quire, sf}
+        if shift > 0 {
+            Self{quire: quire.shl(shift as u32), sf: sf - shift, sticky}
+        }
+        else if shift < 0 {
+            let lost = quire.low_bits(-shift as u32);
+            let q = quire.sar(-shift as u32);
+
+            // flooring a negative value can carry it up to -2^(SIZE - 2)
+            if q == W::from_i128(-1).shl(SIZE as u32 - 2) {
+                Self{quire: q.sar(1), sf: sf - shift + 1, sticky: sticky | lost}
+            }
+            else {
+                Self{quire: q, sf: sf - shift, sticky: sticky | lost}
+            }
+        }
+        else {
+            Self{quire, sf, sticky}
+        }
+    }
+
+    pub fn div_posit(self, other: Posit<N, ES>) -> Posit<N, ES> {
+        if self.is_nar() || other.is_nar() || other.is_zero() {
+            return Posit::NAR;
+        }
+
+        let (s, m, e) = self.magnitude();
+        let (so, f, eo) = other.to_scaled();
+
+        Posit::from_ratio(s ^ so, m, f as u128, e - eo)
+    }
+
+    pub fn div_int(self, n: i64) -> Posit<N, ES> {
+        if self.is_nar() {
+            return Posit::NAR;
+        }
+
+        let (s, m, e) = self.magnitude();
+
+        Posit::from_ratio(s ^ (n < 0), m, n.unsigned_abs() as u128, e)
     }
 }
 
-impl<const N: u8, const ES: u8, const SIZE: u8> std::convert::From<FLQuire<N, ES, SIZE>> for Posit<N, ES> {
-    fn from(item: FLQuire<N, ES, SIZE>) -> Self {
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> std::convert::From<Posit<N, ES>> for FLQuire<N, ES, SIZE, W> {
+    fn from(item: Posit<N, ES>) -> Self {
 
-        if item.quire == 0 {
+        if item.is_zero() {
             return Self::zero();
         }
 
-        let s = (1 << 127) & item.quire != 0;
-        let quire = if s {(!item.quire).wrapping_add(1)} else {item.quire};
-        
-        let lod = SIZE as u32  - (128 - quire.leading_zeros());
+        if item.is_nar() {
+            return Self::nar();
+        }
 
-        let f = quire << lod;
+        let (_, s, rc, r, e, f) = item.encode();
 
-        let quire_e = (item.sf  - (lod as i32 + 1) + 3) as u32;
+        let rg = if rc {(!r).wrapping_add(1)} else {r};
 
-        let e_o = quire_e & (0xffffffffu32 >> (32 - ES));
-        let r_o = min(if quire_e & (1 << (Self::RS + ES + 1)) == 0 {(quire_e >> ES) + 1} else {(!quire_e >> ES).wrapping_add(1)}, (N - 1).into());
+        let sf = (rg as i32) * 2i32.pow(ES as u32) + (e as i32);
 
-        let e_msb = (quire_e << (31 - Self::RS - ES - 1)) & 0x80000000u32;
+        let mut quire = W::from_i128((f >> (32 - (N - ES - 2))) as i128);
 
-        let mut rem = (f << (32 - SIZE + 1)) as u32;
+        quire = quire.shl(SIZE as u32  - 2 - (N as u32 - ES as u32 - 2));
 
-        rem = (e_o << 32 - ES) | (rem >> ES);
+        quire = if s {quire.wrapping_neg()} else {quire};
 
-        rem = e_msb | (rem >> 1);
+        Self::new(quire, sf)
+    }
+}
+
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> std::convert::From<FLQuire<N, ES, SIZE, W>> for Posit<N, ES> {
+    fn from(item: FLQuire<N, ES, SIZE, W>) -> Self {
+
+        if item.quire.is_zero() {
+            return Self::zero();
+        }
 
-        rem = rem >> N;
+        if item.is_nar() {
+            return Self::NAR;
+        }
 
-        rem = if e_msb == 0 {(0xffffffffu32 << (32 - N)) | rem} else {rem};
+        let (s, m, e) = item.magnitude();
 
-        rem = rem >> r_o;
+        Self::from_scaled(s, m, e)
+    }
+}
 
-        let mut p: u32 = (rem << N) >> (32 - N + 1);
+// exact as long as the window fits inside the quire; the sticky bit is dropped
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> std::convert::From<FLQuire<N, ES, SIZE, W>> for Quire<N, ES> {
+    fn from(item: FLQuire<N, ES, SIZE, W>) -> Self {
 
-        let l = (0x80000000u32 >> (2 * N - 2)) & rem != 0;
-        let g = (0x80000000u32 >> (2 * N - 1)) & rem != 0;
-        let r = (0x80000000u32 >> (2 * N)) & rem != 0;
-        
-        let st = (rem << (2 * N)) != 0;
+        if item.quire.is_zero() {
+            return Self::zero();
+        }
 
-        let ulp = if (g & (r | st)) | (l & g & !(r | st)) {1} else {0};
+        if item.is_nar() {
+            return Self::NAR;
+        }
 
-        p = if r_o < (N - 1).into() {p + ulp} else {p};
+        let shift = item.sf - FLQuire::<N, ES, SIZE, W>::FRAC as i32 + Quire::<N, ES>::BIAS + 1;
 
-        if p == 0 {
-            return if s {
-                - Self::MINPOS
-            } else {
-                Self::MINPOS
-            };
+        if shift >= 0 {
+            Self::new(item.quire.low_u128()).scale(shift)
+        }
+        else {
+            Self::new(item.quire.sar(-shift as u32).low_u128())
         }
+    }
+}
 
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> std::convert::From<Quire<N, ES>> for FLQuire<N, ES, SIZE, W> {
+    fn from(item: Quire<N, ES>) -> Self {
 
-        p = if s {((!p).wrapping_add(1) & Self::BODY_MASK) | 0x1u32 << N - 1} else {p};
+        if item.is_zero() {
+            return Self::zero();
+        }
 
-        Self(p)
+        if item.is_nar() {
+            return Self::nar();
+        }
 
+        Self::normalize(W::from_i128(item.0 as i128), Self::FRAC as i32 - (Quire::<N, ES>::BIAS + 1), false)
     }
 }
 
 use std::ops::{Neg, Add, Sub, Mul, Div, AddAssign};
 use num_traits::identities::{One, Zero};
 
-impl<const N: u8, const ES: u8, const SIZE: u8> Neg for FLQuire<N, ES, SIZE> {
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> Neg for FLQuire<N, ES, SIZE, W> {
     type Output = Self;
 
     fn neg(self) -> Self::Output {
-        Self::new(
-            (!self.quire).wrapping_add(1),
-            self.sf
-        )
+        // -(q + eps) == (-q - 1) + (1 - eps)
+        let quire = if self.sticky {self.quire.not()} else {self.quire.wrapping_neg()};
+
+        Self{quire, ..self}
     }
 }
 
-impl<const N: u8, const ES: u8, const SIZE: u8> Add for FLQuire<N, ES, SIZE> {
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> Add for FLQuire<N, ES, SIZE, W> {
     type Output = Self;
 
     fn add(self, other: Self) -> Self::Output {
 
+        if self.is_nar() || other.is_nar() {
+            return Self::nar();
+        }
+
         if self.is_zero() {
-            return other;
+            return Self{sticky: self.sticky | other.sticky, ..other};
         }
 
         if other.is_zero() {
-            return self;
+            return Self{sticky: self.sticky | other.sticky, ..self};
         }
 
         let (a, b) = if self.sf > other.sf {(self, other)} else {(other, self)};
 
-        let mut quire = a.quire + (b.quire as i128 >> (a.sf - b.sf)) as u128;
-
-        let s = (quire & (1 << SIZE - 1)) != 0;
-        let g = (quire & (1 << SIZE - 2)) != 0;
+        let diff = (a.sf - b.sf) as u32;
 
-        let ovf = s ^ g;
+        let aligned = b.quire.sar(diff);
+        let lost = b.quire.low_bits(diff);
 
-        let sf = if ovf {
-            quire = (quire as i128 >> 1) as u128;
-            a.sf + 1
+        let quire = a.quire.wrapping_add(aligned);
 
-        } else {
-            a.sf
-        };
-
-        Self{quire, sf}
+        Self::normalize(quire, a.sf, a.sticky | b.sticky | lost)
     }
 }
 
-impl<const N: u8, const ES: u8, const SIZE: u8> AddAssign for FLQuire<N, ES, SIZE> {
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> AddAssign for FLQuire<N, ES, SIZE, W> {
     fn add_assign(&mut self, other: Self) {
         *self = *self + other;
     }
 }
 
-impl<const N: u8, const ES: u8, const SIZE: u8> Sub for FLQuire<N, ES, SIZE> {
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> Sub for FLQuire<N, ES, SIZE, W> {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self::Output {
@@ -162,11 +257,14 @@ impl<const N: u8, const ES: u8, const SIZE: u8> Sub for FLQuire<N, ES, SIZE> {
     }
 }
 
-impl<const N: u8, const ES: u8, const SIZE: u8> Mul for FLQuire<N, ES, SIZE> {
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> Mul for FLQuire<N, ES, SIZE, W> {
     type Output = Self;
 
     fn mul(self, other: Self) -> Self::Output {
-        if self.is_zero() || other.is_zero() {
+        if self.is_nar() || other.is_nar() {
+            Self::nar()
+        }
+        else if self.is_zero() || other.is_zero() {
             Self::zero()
         }
         else if self.is_one() {
@@ -176,53 +274,78 @@ impl<const N: u8, const ES: u8, const SIZE: u8> Mul for FLQuire<N, ES, SIZE> {
             self
         }
         else {
-            let mut quire = ((self.quire * other.quire) as i128 >> SIZE - 3) as u128;
+            let (quire, lost) = self.quire.mul_sar(other.quire, Self::FRAC);
+
+            Self::normalize(quire, self.sf + other.sf, self.sticky | other.sticky | lost)
+        }
+    }
+}
+
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> Div for FLQuire<N, ES, SIZE, W> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self::Output {
+        if self.is_nar() || other.is_nar() || other.is_zero() {
+            return Self::nar();
+        }
 
-            let s = (quire & (1 << SIZE - 1)) != 0;
-            let g = (quire & (1 << SIZE - 2)) != 0;
+        if self.is_zero() {
+            return Self::zero();
+        }
 
-            let ovf = s ^ g;
+        let s1 = self.quire.is_negative();
+        let s2 = other.quire.is_negative();
+        let a = self.quire.abs();
+        let b = other.quire.abs();
 
-            let sf = if ovf {
-                quire = (quire as i128 >> 1) as u128;
-                self.sf + other.sf + 1
+        // align both leading ones to bit SIZE - 3 so the quotient lands in [1/2, 2)
+        let la = a.leading_zeros() as i32 - (W::BITS as i32 - (SIZE as i32 - 2));
+        let lb = b.leading_zeros() as i32 - (W::BITS as i32 - (SIZE as i32 - 2));
+        let a = if la >= 0 {a.shl(la as u32)} else {a.sar(-la as u32)};
+        let b = if lb >= 0 {b.shl(lb as u32)} else {b.sar(-lb as u32)};
 
-            } else {
-                self.sf + other.sf
-            };
+        let (mut quire, mut st) = a.div_shl(b, Self::FRAC);
+        let mut sf = self.sf - la - other.sf + lb;
 
-            Self{quire, sf}
+        if quire.leading_zeros() > W::BITS - Self::FRAC - 1 {
+            (quire, st) = a.div_shl(b, Self::FRAC + 1);
+            sf -= 1;
         }
+
+        // the truncated magnitude is below the exact one, so a negative quotient steps one ulp down
+        quire = if s1 ^ s2 {if st {quire.not()} else {quire.wrapping_neg()}} else {quire};
+
+        Self{quire, sf, sticky: st | self.sticky | other.sticky}
     }
 }
 
-impl<const N: u8, const ES: u8, const SIZE: u8> Div for FLQuire<N, ES, SIZE> {
-    type Output = Self;
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> Div<Posit<N, ES>> for FLQuire<N, ES, SIZE, W> {
+    type Output = Posit<N, ES>;
 
-    fn div(self, _other: Self) -> Self::Output {
-        Self::zero()
+    fn div(self, other: Posit<N, ES>) -> Self::Output {
+        self.div_posit(other)
     }
 }
 
-impl<const N: u8, const ES: u8, const SIZE: u8> Zero for FLQuire<N, ES, SIZE> {
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> Zero for FLQuire<N, ES, SIZE, W> {
 
     fn zero() -> Self {
-        Self::new(0x0, 0x0)
+        Self::new(W::default(), 0x0)
     }
 
     fn is_zero(self:&Self) -> bool {
-        self.quire == 0
+        self.quire.is_zero()
     }
 
 }
 
-impl<const N: u8, const ES: u8, const SIZE: u8> One for FLQuire<N, ES, SIZE> {
+impl<const N: u8, const ES: u8, const SIZE: u16, W: Window> One for FLQuire<N, ES, SIZE, W> {
 
     fn one() -> Self {
-        Self::new(1 << SIZE - 2, 0x0)
+        Self::new(W::from_i128(1).shl(Self::FRAC), 0x0)
     }
 
     fn is_one(self:&Self) -> bool {
-        self.quire == 1 << SIZE - 2 && self.sf == 0
+        self.quire == W::from_i128(1).shl(Self::FRAC) && self.sf == 0 && !self.sticky
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,152 @@
+use crate::posit::Posit;
+use crate::quire::Quire;
+use crate::exact::{ExactSum, Packed, product, round};
+use crate::window::{Window, Wide};
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+use ndarray::parallel::prelude::*;
+use num_traits::identities::Zero;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trans {
+    No,
+    Yes,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GemmConfig {
+    // rows of C per task, columns of C per tile and depth of each k block
+    pub mc: usize,
+    pub nc: usize,
+    pub kc: usize,
+    pub parallel: bool,
+}
+
+impl Default for GemmConfig {
+    fn default() -> Self {
+        Self{mc: 32, nc: 64, kc: 256, parallel: true}
+    }
+}
+
+// c + sum(a * b) held exactly, NaR if any input is NaR
+pub(crate) fn fused_sum<const N: u8, const ES: u8, I>(c: Posit<N, ES>, terms: I) -> ExactSum<N, ES>
+    where I: IntoIterator<Item = (Posit<N, ES>, Posit<N, ES>)>
+{
+    terms.into_iter().fold(ExactSum::from(c), |mut acc, (a, b)| {
+        acc.fma(a, b);
+        acc
+    })
+}
+
+// c + sum(a * b) rounded once, NaR if any input is NaR
+pub fn fused_dot<const N: u8, const ES: u8, I>(c: Posit<N, ES>, terms: I) -> Posit<N, ES>
+    where I: IntoIterator<Item = (Posit<N, ES>, Posit<N, ES>)>
+{
+    fused_sum(c, terms).into()
+}
+
+fn op<T>(t: Trans, a: ArrayView2<T>) -> ArrayView2<T> {
+    match t {
+        Trans::No => a,
+        Trans::Yes => a.reversed_axes(),
+    }
+}
+
+fn pack_rows<const N: u8, const ES: u8>(a: ArrayView2<Posit<N, ES>>, bias: i32) -> (Vec<Packed>, Vec<bool>) {
+    let packed = a.rows().into_iter().flat_map(|r| r.into_iter().map(|&p| Packed::new(p, bias)).collect::<Vec<_>>()).collect();
+    let nar = a.rows().into_iter().map(|r| r.iter().any(|p| p.is_nar())).collect();
+
+    (packed, nar)
+}
+
+pub fn gemm<const N: u8, const ES: u8>(ta: Trans, a: ArrayView2<Posit<N, ES>>, tb: Trans, b: ArrayView2<Posit<N, ES>>) -> Array2<Posit<N, ES>> {
+    gemm_with(GemmConfig::default(), ta, a, tb, b)
+}
+
+// C = op(A) op(B), every element accumulated exactly and rounded once
+pub fn gemm_with<const N: u8, const ES: u8>(cfg: GemmConfig, ta: Trans, a: ArrayView2<Posit<N, ES>>, tb: Trans, b: ArrayView2<Posit<N, ES>>) -> Array2<Posit<N, ES>> {
+    let a = op(ta, a);
+    let b = op(tb, b);
+    assert_eq!(a.ncols(), b.nrows(), "gemm: inner dimensions differ");
+
+    // the same blocking either way, the wide sums only for formats whose quire outgrows 128 bits
+    if Quire::<N, ES>::FITS {kernel::<N, ES, u128>(cfg, a, b)} else {kernel::<N, ES, Wide<8>>(cfg, a, b)}
+}
+
+fn kernel<const N: u8, const ES: u8, W: Window + Send>(cfg: GemmConfig, a: ArrayView2<Posit<N, ES>>, b: ArrayView2<Posit<N, ES>>) -> Array2<Posit<N, ES>> {
+    let (m, k) = a.dim();
+    let n = b.ncols();
+
+    let (mc, nc, kc) = (cfg.mc.max(1), cfg.nc.max(1), cfg.kc.max(1));
+
+    // A row-major, B column-major, so every dot product walks contiguous memory
+    let (pa, nar_a) = pack_rows(a, Quire::<N, ES>::BIAS + 1);
+    let (pb, nar_b) = pack_rows(b.t(), 0);
+
+    let mut c = Array2::<Posit<N, ES>>::zeros((m, n));
+
+    let block = |(ib, mut cb): (usize, ndarray::ArrayViewMut2<Posit<N, ES>>)| {
+        let i0 = ib * mc;
+        let rows = cb.nrows();
+        let mut acc = vec![W::default(); rows * nc];
+
+        for j0 in (0..n).step_by(nc) {
+            let cols = nc.min(n - j0);
+            acc.iter_mut().for_each(|x| *x = W::default());
+
+            for p0 in (0..k).step_by(kc) {
+                let depth = kc.min(k - p0);
+
+                for i in 0..rows {
+                    let ar = &pa[(i0 + i) * k + p0..][..depth];
+
+                    for j in 0..cols {
+                        let bc = &pb[(j0 + j) * k + p0..][..depth];
+
+                        acc[i * nc + j] = ar.iter().zip(bc).fold(acc[i * nc + j], |s, (&x, &y)| s.wrapping_add(product(x, y)));
+                    }
+                }
+            }
+
+            for i in 0..rows {
+                for j in 0..cols {
+                    cb[[i, j0 + j]] = if nar_a[i0 + i] || nar_b[j0 + j] {
+                        Posit::NAR
+                    } else {
+                        round(acc[i * nc + j], -(Quire::<N, ES>::BIAS + 1))
+                    };
+                }
+            }
+        }
+    };
+
+    if cfg.parallel {
+        c.axis_chunks_iter_mut(Axis(0), mc).into_par_iter().enumerate().for_each(block);
+    }
+    else {
+        c.axis_chunks_iter_mut(Axis(0), mc).enumerate().for_each(block);
+    }
+
+    c
+}
+
+// y = op(A) x, one exact dot product per row
+pub fn gemv<const N: u8, const ES: u8>(ta: Trans, a: ArrayView2<Posit<N, ES>>, x: ArrayView1<Posit<N, ES>>) -> Array1<Posit<N, ES>> {
+    let a = op(ta, a);
+    assert_eq!(a.ncols(), x.len(), "gemv: dimensions differ");
+
+    let (px, nar_x) = pack_rows(x.insert_axis(Axis(0)), 0);
+
+    let mut y = Array1::<Posit<N, ES>>::zeros(a.nrows());
+
+    y.as_slice_mut().unwrap().par_iter_mut().zip(a.axis_iter(Axis(0)).into_par_iter()).for_each(|(y, row)| {
+        *y = if nar_x[0] || row.iter().any(|p| p.is_nar()) {
+            Posit::NAR
+        } else {
+            let mut acc = ExactSum::zero();
+            row.iter().zip(&px).for_each(|(&r, &x)| acc.add_packed(ExactSum::pack(r), x));
+            acc.into()
+        };
+    });
+
+    y
+}
@@ -0,0 +1,409 @@
+use crate::posit::Posit;
+use crate::exact::ExactSum;
+use ndarray::ArrayView1;
+use num_traits::identities::{One, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// Sets of reals on the projective circle of posits, where NaR is the point at
+// infinity. An arc runs upward through the bit patterns from lo to hi, both
+// closed; when lo lies above hi it wraps through infinity, as Gustafson's
+// valids do. NaR as an endpoint stands for infinity itself, so [x, NaR] is
+// [x, +inf] and [NaR, x] is [-inf, x]. Unlike valids there are no open
+// endpoints, so results always round outward to the next posit.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Interval<const N: u8, const ES: u8> {
+    Empty,
+    Arc{lo: Posit<N, ES>, hi: Posit<N, ES>},
+    Everything,
+}
+
+// an endpoint of an affine piece of an arc
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Bound<const N: u8, const ES: u8> {
+    NegInf,
+    Fin(Posit<N, ES>),
+    PosInf,
+}
+
+use Bound::{Fin, NegInf, PosInf};
+
+impl<const N: u8, const ES: u8> Bound<N, ES> {
+    fn key(self) -> i64 {
+        match self {
+            NegInf => i64::MIN,
+            Fin(p) => rank(p) as i64,
+            PosInf => i64::MAX,
+        }
+    }
+
+    fn is_infinite(self) -> bool {
+        self != Fin(self.posit())
+    }
+
+    // infinities back to NaR
+    fn posit(self) -> Posit<N, ES> {
+        match self {
+            Fin(p) => p,
+            _ => Posit::NAR,
+        }
+    }
+
+    fn sign(self) -> Ordering {
+        self.key().cmp(&0)
+    }
+}
+
+type Piece<const N: u8, const ES: u8> = (Bound<N, ES>, Bound<N, ES>);
+
+// order-preserving key of a posit, NaR lowest
+fn rank<const N: u8, const ES: u8>(p: Posit<N, ES>) -> i32 {
+    (p.0 << (32 - N)) as i32
+}
+
+// the adjacent posit above or below
+fn step<const N: u8, const ES: u8>(p: Posit<N, ES>, up: bool) -> Posit<N, ES> {
+    Posit(if up {p.0.wrapping_add(1)} else {p.0.wrapping_sub(1)} & Posit::<N, ES>::MASK)
+}
+
+// r moved one step outward when it lies on the wrong side of the exact value,
+// given r.cmp(exact); stepping past maxpos reaches infinity
+fn directed<const N: u8, const ES: u8>(r: Posit<N, ES>, ord: Ordering, up: bool) -> Bound<N, ES> {
+    let p = match (ord, up) {
+        (Ordering::Less, true) => step(r, true),
+        (Ordering::Greater, false) => step(r, false),
+        _ => r,
+    };
+
+    match p.is_nar() {
+        true => if up {PosInf} else {NegInf},
+        false => Fin(p),
+    }
+}
+
+// an exact value rounded in the given direction
+fn round<const N: u8, const ES: u8>(q: ExactSum<N, ES>, up: bool) -> Bound<N, ES> {
+    let r = Posit::from(q);
+    directed(r, ExactSum::from(r).cmp(&q), up)
+}
+
+// a / c for a non-zero c
+fn divide<const N: u8, const ES: u8>(a: Posit<N, ES>, c: Posit<N, ES>, up: bool) -> Bound<N, ES> {
+    let num = ExactSum::from(a);
+    let r = num.ratio(ExactSum::from(c));
+
+    // r c against a, flipped when c is negative
+    let ord = ExactSum::product(r, c).cmp(&num);
+    directed(r, if rank(c) < 0 {ord.reverse()} else {ord}, up)
+}
+
+fn sqrt_bound<const N: u8, const ES: u8>(b: Bound<N, ES>, up: bool) -> Bound<N, ES> {
+    match b {
+        Fin(p) => {
+            let r = p.sqrt();
+            directed(r, ExactSum::product(r, r).cmp(&ExactSum::from(p)), up)
+        },
+        _ => b,
+    }
+}
+
+fn contains_zero<const N: u8, const ES: u8>((l, h): Piece<N, ES>) -> bool {
+    l.key() <= 0 && h.key() >= 0
+}
+
+fn sum<const N: u8, const ES: u8>(a: Bound<N, ES>, b: Bound<N, ES>, up: bool) -> Bound<N, ES> {
+    match (a, b) {
+        (Fin(x), Fin(y)) => round(ExactSum::from(x) + ExactSum::from(y), up),
+        (Fin(_), inf) | (inf, _) => inf,
+    }
+}
+
+// (rounded down, rounded up) for one endpoint pair
+fn product<const N: u8, const ES: u8>(a: Bound<N, ES>, b: Bound<N, ES>) -> (Bound<N, ES>, Bound<N, ES>) {
+    match (a, b) {
+        (Fin(x), Fin(y)) => {
+            let q = ExactSum::product(x, y);
+            (round(q, false), round(q, true))
+        },
+        _ => {
+            let inf = if a.sign() == b.sign() {PosInf} else {NegInf};
+            (inf, inf)
+        },
+    }
+}
+
+impl<const N: u8, const ES: u8> Interval<N, ES> {
+    pub const NAR: Self = Self::Arc{lo: Posit::NAR, hi: Posit::NAR};
+
+    // the arc from lo up to hi
+    pub fn new(lo: Posit<N, ES>, hi: Posit<N, ES>) -> Self {
+        Self::Arc{lo, hi}
+    }
+
+    pub fn point(p: Posit<N, ES>) -> Self {
+        Self::new(p, p)
+    }
+
+    // the tightest enclosure of [lo, hi]
+    pub fn from_f64(lo: f64, hi: f64) -> Self {
+        assert!(lo <= hi, "interval: lo above hi");
+
+        let out = |v: f64, up: bool| {
+            let r = Posit::from(v);
+            directed(r, f64::from(r).partial_cmp(&v).unwrap_or(Ordering::Equal), up).posit()
+        };
+
+        Self::new(out(lo, false), out(hi, true))
+    }
+
+    pub fn is_empty(self) -> bool {
+        self == Self::Empty
+    }
+
+    pub fn is_everything(self) -> bool {
+        self == Self::Everything
+    }
+
+    pub fn contains(self, p: Posit<N, ES>) -> bool {
+        match self {
+            Self::Empty => false,
+            Self::Arc{lo, hi} => p.0.wrapping_sub(lo.0) & Posit::<N, ES>::MASK <= hi.0.wrapping_sub(lo.0) & Posit::<N, ES>::MASK,
+            Self::Everything => true,
+        }
+    }
+
+    // whether the real v, or infinity for an infinite v, lies in the set
+    pub fn contains_f64(self, v: f64) -> bool {
+        match self {
+            _ if self == Self::NAR => v.is_infinite(),
+            Self::Arc{lo, hi} if !v.is_infinite() => {
+                let above = lo.is_nar() || f64::from(lo) <= v;
+                let below = hi.is_nar() || v <= f64::from(hi);
+                if rank(lo) <= rank(hi) || lo.is_nar() || hi.is_nar() {above && below} else {above || below}
+            },
+            _ => self.contains(Posit::NAR),
+        }
+    }
+
+    // hi - lo, infinite for unbounded sets
+    pub fn width(self) -> f64 {
+        match self {
+            Self::Empty => 0.0,
+            Self::Arc{lo, hi} if !lo.is_nar() && !hi.is_nar() && rank(lo) <= rank(hi) => f64::from(hi) - f64::from(lo),
+            _ => f64::INFINITY,
+        }
+    }
+
+    fn len(lo: Posit<N, ES>, hi: Posit<N, ES>) -> u32 {
+        hi.0.wrapping_sub(lo.0) & Posit::<N, ES>::MASK
+    }
+
+    // whether the arc a holds the arc b
+    fn covers((alo, ahi): (Posit<N, ES>, Posit<N, ES>), (blo, bhi): (Posit<N, ES>, Posit<N, ES>)) -> bool {
+        Self::len(alo, blo) + Self::len(blo, bhi) <= Self::len(alo, ahi)
+    }
+
+    // the set holding both that spans the fewest posits
+    pub fn hull(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, x) | (x, Self::Empty) => x,
+            (Self::Arc{lo: alo, hi: ahi}, Self::Arc{lo: blo, hi: bhi}) => {
+                let (a, b) = ((alo, ahi), (blo, bhi));
+                [a, b, (alo, bhi), (blo, ahi)].iter().cloned()
+                    .filter(|&c| Self::covers(c, a) && Self::covers(c, b))
+                    .min_by_key(|&(lo, hi)| Self::len(lo, hi))
+                    .map_or(Self::Everything, |(lo, hi)| Self::new(lo, hi))
+            },
+            _ => Self::Everything,
+        }
+    }
+
+    // the affine pieces of an arc away from the point at infinity
+    fn pieces(lo: Posit<N, ES>, hi: Posit<N, ES>) -> Vec<Piece<N, ES>> {
+        match (lo.is_nar(), hi.is_nar()) {
+            (true, true) => vec![],
+            (true, false) => vec![(NegInf, Fin(hi))],
+            (false, true) => vec![(Fin(lo), PosInf)],
+            _ if rank(lo) <= rank(hi) => vec![(Fin(lo), Fin(hi))],
+            _ => vec![(Fin(lo), PosInf), (NegInf, Fin(hi))],
+        }
+    }
+
+    fn from_piece((l, h): Piece<N, ES>) -> Self {
+        if l == NegInf && h == PosInf {Self::Everything} else {Self::new(l.posit(), h.posit())}
+    }
+
+    // op over every pair of pieces; infinity alone in either operand gives
+    // infinity, or everything when the other operand holds the indeterminate value
+    fn combine<F>(self, other: Self, indeterminate: Posit<N, ES>, op: F) -> Self
+        where F: Fn(Piece<N, ES>, Piece<N, ES>) -> Self
+    {
+        match (self, other) {
+            (Self::Empty, _) | (_, Self::Empty) => Self::Empty,
+            (Self::Everything, _) | (_, Self::Everything) => Self::Everything,
+            (x, y) if x == Self::NAR || y == Self::NAR => {
+                let rest = if x == Self::NAR {y} else {x};
+                if rest.contains(indeterminate) {Self::Everything} else {Self::NAR}
+            },
+            (Self::Arc{lo: alo, hi: ahi}, Self::Arc{lo: blo, hi: bhi}) => {
+                let mut out = Self::Empty;
+                for &a in &Self::pieces(alo, ahi) {
+                    for &b in &Self::pieces(blo, bhi) {
+                        out = out.hull(op(a, b));
+                    }
+                }
+                out
+            },
+        }
+    }
+
+    // 1 / x reverses the circle: [lo, hi] goes to [1 / hi, 1 / lo], with 1 / 0 = NaR and 1 / NaR = 0
+    pub fn recip(self) -> Self {
+        let inv = |p: Posit<N, ES>, up: bool| match (p.is_zero(), p.is_nar()) {
+            (true, _) => Posit::NAR,
+            (_, true) => Posit::zero(),
+            _ => divide(Posit::one(), p, up).posit(),
+        };
+
+        match self {
+            Self::Arc{lo, hi} => Self::new(inv(hi, false), inv(lo, true)),
+            x => x,
+        }
+    }
+
+    // outward-rounded square root of the non-negative part, Empty if there is none
+    pub fn sqrt(self) -> Self {
+        match self {
+            Self::Empty => Self::Empty,
+            Self::Everything => Self::new(Posit::zero(), Posit::NAR),
+            Self::Arc{lo, hi} => {
+                let mut out = if self.contains(Posit::NAR) {Self::NAR} else {Self::Empty};
+
+                for (l, h) in Self::pieces(lo, hi) {
+                    if h.key() >= 0 {
+                        let l = if l.key() < 0 {Fin(Posit::zero())} else {l};
+                        out = out.hull(Self::from_piece((sqrt_bound(l, false), sqrt_bound(h, true))));
+                    }
+                }
+                out
+            },
+        }
+    }
+
+    fn finite(self) -> Option<(Posit<N, ES>, Posit<N, ES>)> {
+        match self {
+            Self::Arc{lo, hi} if !lo.is_nar() && !hi.is_nar() && rank(lo) <= rank(hi) => Some((lo, hi)),
+            _ => None,
+        }
+    }
+}
+
+impl<const N: u8, const ES: u8> fmt::Display for Interval<N, ES> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Empty => write!(f, "empty"),
+            Self::Everything => write!(f, "everything"),
+            x if x == Self::NAR => write!(f, "[NaR]"),
+            Self::Arc{lo, hi} => {
+                let lo = if lo.is_nar() {f32::NEG_INFINITY} else {f32::from(lo)};
+                let hi = if hi.is_nar() {f32::INFINITY} else {f32::from(hi)};
+                write!(f, "[{}, {}]", lo, hi)
+            },
+        }
+    }
+}
+
+impl<const N: u8, const ES: u8> From<Posit<N, ES>> for Interval<N, ES> {
+    fn from(p: Posit<N, ES>) -> Self {
+        Self::point(p)
+    }
+}
+
+impl<const N: u8, const ES: u8> Neg for Interval<N, ES> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        match self {
+            Self::Arc{lo, hi} => Self::new(-hi, -lo),
+            x => x,
+        }
+    }
+}
+
+// endpoint sums held exactly and rounded outward; -inf + inf covers everything
+impl<const N: u8, const ES: u8> Add for Interval<N, ES> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.combine(other, Posit::NAR, |(al, ah), (bl, bh)| Self::from_piece((sum(al, bl, false), sum(ah, bh, true))))
+    }
+}
+
+impl<const N: u8, const ES: u8> Sub for Interval<N, ES> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+// the extreme endpoint products, each exact before rounding; infinity times zero covers everything
+impl<const N: u8, const ES: u8> Mul for Interval<N, ES> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.combine(other, Posit::zero(), |a, b| {
+            if (a.0.is_infinite() || a.1.is_infinite()) && contains_zero(b) || (b.0.is_infinite() || b.1.is_infinite()) && contains_zero(a) {
+                return Self::Everything;
+            }
+
+            let products = [product(a.0, b.0), product(a.0, b.1), product(a.1, b.0), product(a.1, b.1)];
+            let lo = products.iter().map(|p| p.0).min_by_key(|b| b.key()).unwrap();
+            let hi = products.iter().map(|p| p.1).max_by_key(|b| b.key()).unwrap();
+
+            Self::from_piece((lo, hi))
+        })
+    }
+}
+
+// endpoint quotients rounded outward for bounded operands away from zero,
+// otherwise x (1 / y) around the circle
+impl<const N: u8, const ES: u8> Div for Interval<N, ES> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        match (self.finite(), other.finite()) {
+            (Some((a, b)), Some((c, d))) if rank(c) > 0 || rank(d) < 0 => {
+                let q = [(a, c), (a, d), (b, c), (b, d)];
+                let lo = q.iter().map(|&(x, y)| divide(x, y, false)).min_by_key(|b| b.key()).unwrap();
+                let hi = q.iter().map(|&(x, y)| divide(x, y, true)).max_by_key(|b| b.key()).unwrap();
+
+                Self::from_piece((lo, hi))
+            },
+            _ => self * other.recip(),
+        }
+    }
+}
+
+// the tightest enclosure of sum(a b) for bounded operands: the lowest and the
+// highest endpoint product of every term summed exactly, each bound rounded once
+pub fn dot<const N: u8, const ES: u8>(a: ArrayView1<Interval<N, ES>>, b: ArrayView1<Interval<N, ES>>) -> Interval<N, ES> {
+    assert_eq!(a.len(), b.len(), "dot: lengths differ");
+
+    let mut lo = ExactSum::zero();
+    let mut hi = ExactSum::zero();
+
+    for (&x, &y) in a.iter().zip(b) {
+        match (x.finite(), y.finite()) {
+            (Some((p, q)), Some((r, s))) => {
+                let products = [ExactSum::product(p, r), ExactSum::product(p, s), ExactSum::product(q, r), ExactSum::product(q, s)];
+                lo += *products.iter().min().unwrap();
+                hi += *products.iter().max().unwrap();
+            },
+            _ => return a.iter().zip(b).fold(Interval::point(Posit::zero()), |acc, (&x, &y)| acc + x * y),
+        }
+    }
+
+    Interval::from_piece((round(lo, false), round(hi, true)))
+}
@@ -0,0 +1,437 @@
+use crate::accumulator::{self, Accumulator};
+use crate::linalg::LinalgError;
+use crate::posit::Posit;
+use crate::exact::ExactSum;
+use ndarray::{Array1, ArrayBase, ArrayView1, Data, Ix2};
+use num_traits::identities::{One, Zero};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// element types the solvers run over, with the accumulator used for dot products by default
+pub trait Scalar: Copy + PartialOrd + Zero + One + Neg<Output = Self> + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> {
+    type Acc: Accumulator<Self>;
+
+    // NaR for posits, NaN for floats
+    fn nan() -> Self;
+    fn sqrt(self) -> Self;
+    // non-finite values map to infinity or NaN
+    fn to_f64(self) -> f64;
+}
+
+impl<const N: u8, const ES: u8> Scalar for Posit<N, ES> {
+    type Acc = ExactSum<N, ES>;
+
+    fn nan() -> Self {
+        Posit::NAR
+    }
+
+    fn sqrt(self) -> Self {
+        Posit::sqrt(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+}
+
+impl Scalar for f32 {
+    type Acc = f32;
+
+    fn nan() -> Self {
+        f32::NAN
+    }
+
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Scalar for f64 {
+    type Acc = f64;
+
+    fn nan() -> Self {
+        f64::NAN
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+// f16 has no wider type of its own to accumulate in, so its dot products round
+// after every step
+#[cfg(feature = "half")]
+impl Scalar for half::f16 {
+    type Acc = accumulator::Naive<half::f16>;
+
+    fn nan() -> Self {
+        half::f16::NAN
+    }
+
+    fn sqrt(self) -> Self {
+        half::f16::from_f32(self.to_f32().sqrt())
+    }
+
+    fn to_f64(self) -> f64 {
+        half::f16::to_f64(self)
+    }
+}
+
+pub trait LinearOperator<P> {
+    fn nrows(&self) -> usize;
+    fn ncols(&self) -> usize;
+    fn apply(&self, x: ArrayView1<P>) -> Array1<P>;
+}
+
+impl<P: Scalar, S: Data<Elem = P>> LinearOperator<P> for ArrayBase<S, Ix2> {
+    fn nrows(&self) -> usize {
+        self.nrows()
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols()
+    }
+
+    fn apply(&self, x: ArrayView1<P>) -> Array1<P> {
+        // the quire has no NaR state, so non-finite entries are propagated here
+        let finite = |v: &P| v.to_f64().is_finite();
+        let x_finite = x.iter().all(finite);
+
+        self.rows().into_iter()
+            .map(|row| if x_finite && row.iter().all(finite) {accumulator::dot::<P, P::Acc>(row, x)} else {P::nan()})
+            .collect()
+    }
+}
+
+// matrix-free operator from a closure computing A x
+pub struct FnOperator<F> {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub f: F,
+}
+
+impl<P, F: Fn(ArrayView1<P>) -> Array1<P>> LinearOperator<P> for FnOperator<F> {
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    fn apply(&self, x: ArrayView1<P>) -> Array1<P> {
+        (self.f)(x)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolverConfig {
+    // target for ||r|| / ||b||
+    pub tol: f64,
+    // operator applications for CG and GMRES, iterations for BiCGSTAB
+    pub max_iter: usize,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self{tol: 1e-6, max_iter: 1000}
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution<P> {
+    pub x: Array1<P>,
+    // relative residual norm after every iteration, starting with the initial guess
+    pub residuals: Vec<f64>,
+    pub converged: bool,
+}
+
+fn dot<P: Scalar, A: Accumulator<P>>(a: &Array1<P>, b: &Array1<P>) -> P {
+    accumulator::dot::<P, A>(a.view(), b.view())
+}
+
+fn norm<P: Scalar, A: Accumulator<P>>(a: &Array1<P>) -> f64 {
+    dot::<P, A>(a, a).to_f64().sqrt()
+}
+
+// base + sum(c * v) per element, rounded once when A is exact
+fn combine<P: Scalar, A: Accumulator<P>>(base: &Array1<P>, terms: &[(P, &Array1<P>)]) -> Array1<P> {
+    Array1::from_shape_fn(base.len(), |i| {
+        let mut acc = A::init();
+        acc.add(base[i]);
+        terms.iter().for_each(|&(c, v)| acc.fma(c, v[i]));
+        acc.finish()
+    })
+}
+
+fn check<P: Scalar>(v: &Array1<P>, step: usize) -> Result<(), LinalgError> {
+    if v.iter().all(|x| x.to_f64().is_finite()) {Ok(())} else {Err(LinalgError::NaR(step))}
+}
+
+// validates the system and returns ||b||, or None when b = 0 and x = 0 solves it exactly
+fn setup<P: Scalar, A: Accumulator<P>, Op: LinearOperator<P>>(op: &Op, b: &Array1<P>) -> Result<Option<f64>, LinalgError> {
+    if op.nrows() != op.ncols() || op.nrows() != b.len() {
+        return Err(LinalgError::Shape);
+    }
+    check(b, 0)?;
+
+    let bnorm = norm::<P, A>(b);
+    if bnorm.is_finite() {Ok(if bnorm == 0.0 {None} else {Some(bnorm)})} else {Err(LinalgError::NaR(0))}
+}
+
+fn trivial<P: Scalar>(n: usize) -> Solution<P> {
+    Solution{x: Array1::zeros(n), residuals: vec![0.0], converged: true}
+}
+
+// conjugate gradient for symmetric positive definite operators
+pub fn cg_with<P: Scalar, A: Accumulator<P>, Op: LinearOperator<P>>(op: &Op, b: ArrayView1<P>, cfg: SolverConfig) -> Result<Solution<P>, LinalgError> {
+    let b = b.to_owned();
+    let bnorm = match setup::<P, A, Op>(op, &b)? {
+        Some(bnorm) => bnorm,
+        None => return Ok(trivial(b.len())),
+    };
+
+    let mut x = Array1::zeros(b.len());
+    let mut r = b.clone();
+    let mut p = b;
+    let mut rr = dot::<P, A>(&r, &r);
+    let mut residuals = vec![1.0];
+
+    for k in 1..=cfg.max_iter {
+        let q = op.apply(p.view());
+        check(&q, k)?;
+
+        let pq = dot::<P, A>(&p, &q);
+        if pq <= P::zero() {
+            break;
+        }
+
+        let alpha = rr / pq;
+        x = combine::<P, A>(&x, &[(alpha, &p)]);
+        r = combine::<P, A>(&r, &[(-alpha, &q)]);
+
+        let rr_next = dot::<P, A>(&r, &r);
+        let rel = rr_next.to_f64().sqrt() / bnorm;
+        if !rel.is_finite() {
+            return Err(LinalgError::NaR(k));
+        }
+        residuals.push(rel);
+
+        if rel <= cfg.tol {
+            return Ok(Solution{x, residuals, converged: true});
+        }
+
+        p = combine::<P, A>(&r, &[(rr_next / rr, &p)]);
+        rr = rr_next;
+    }
+
+    Ok(Solution{x, residuals, converged: false})
+}
+
+pub fn bicgstab_with<P: Scalar, A: Accumulator<P>, Op: LinearOperator<P>>(op: &Op, b: ArrayView1<P>, cfg: SolverConfig) -> Result<Solution<P>, LinalgError> {
+    let b = b.to_owned();
+    let bnorm = match setup::<P, A, Op>(op, &b)? {
+        Some(bnorm) => bnorm,
+        None => return Ok(trivial(b.len())),
+    };
+
+    let n = b.len();
+    let mut x = Array1::zeros(n);
+    let mut r = b.clone();
+    let rhat = b;
+    let mut p = Array1::zeros(n);
+    let mut v = Array1::zeros(n);
+    let (mut rho, mut alpha, mut omega) = (P::one(), P::one(), P::one());
+    let mut residuals = vec![1.0];
+
+    for k in 1..=cfg.max_iter {
+        let rho_next = dot::<P, A>(&rhat, &r);
+        if rho_next.is_zero() {
+            break;
+        }
+
+        let beta = (rho_next / rho) * (alpha / omega);
+        p = combine::<P, A>(&r, &[(beta, &p), (-(beta * omega), &v)]);
+        rho = rho_next;
+
+        v = op.apply(p.view());
+        check(&v, k)?;
+
+        let rv = dot::<P, A>(&rhat, &v);
+        if rv.is_zero() {
+            break;
+        }
+        alpha = rho / rv;
+
+        let s = combine::<P, A>(&r, &[(-alpha, &v)]);
+        let rel = norm::<P, A>(&s) / bnorm;
+        if !rel.is_finite() {
+            return Err(LinalgError::NaR(k));
+        }
+
+        if rel <= cfg.tol {
+            x = combine::<P, A>(&x, &[(alpha, &p)]);
+            residuals.push(rel);
+            return Ok(Solution{x, residuals, converged: true});
+        }
+
+        let t = op.apply(s.view());
+        check(&t, k)?;
+
+        let tt = dot::<P, A>(&t, &t);
+        if tt.is_zero() {
+            break;
+        }
+        omega = dot::<P, A>(&t, &s) / tt;
+
+        x = combine::<P, A>(&x, &[(alpha, &p), (omega, &s)]);
+        r = combine::<P, A>(&s, &[(-omega, &t)]);
+
+        let rel = norm::<P, A>(&r) / bnorm;
+        if !rel.is_finite() {
+            return Err(LinalgError::NaR(k));
+        }
+        residuals.push(rel);
+
+        if rel <= cfg.tol {
+            return Ok(Solution{x, residuals, converged: true});
+        }
+        if omega.is_zero() {
+            break;
+        }
+    }
+
+    Ok(Solution{x, residuals, converged: false})
+}
+
+// plane rotation zeroing b in (a, b)
+fn givens<P: Scalar, A: Accumulator<P>>(a: P, b: P) -> (P, P, P) {
+    let mut acc = A::init();
+    acc.fma(a, a);
+    acc.fma(b, b);
+    let d = acc.finish().sqrt();
+
+    (a / d, b / d, d)
+}
+
+// c x + s y with one rounding when A is exact
+pub(crate) fn rotate<P: Scalar, A: Accumulator<P>>(c: P, x: P, s: P, y: P) -> P {
+    let mut acc = A::init();
+    acc.fma(c, x);
+    acc.fma(s, y);
+    acc.finish()
+}
+
+// restarted GMRES(m) with modified Gram-Schmidt Arnoldi and Givens rotations
+pub fn gmres_with<P: Scalar, A: Accumulator<P>, Op: LinearOperator<P>>(op: &Op, b: ArrayView1<P>, restart: usize, cfg: SolverConfig) -> Result<Solution<P>, LinalgError> {
+    let b = b.to_owned();
+    let bnorm = match setup::<P, A, Op>(op, &b)? {
+        Some(bnorm) => bnorm,
+        None => return Ok(trivial(b.len())),
+    };
+    let m = restart.max(1);
+
+    let mut x = Array1::zeros(b.len());
+    let mut residuals = vec![1.0];
+    let mut k = 0;
+
+    while k < cfg.max_iter {
+        // true residual at every restart
+        let ax = op.apply(x.view());
+        check(&ax, k)?;
+        let r = combine::<P, A>(&b, &[(-P::one(), &ax)]);
+        let beta = dot::<P, A>(&r, &r).sqrt();
+
+        if beta.is_zero() {
+            return Ok(Solution{x, residuals, converged: true});
+        }
+
+        let mut basis = vec![r.mapv(|t| t / beta)];
+        let mut h: Vec<Vec<P>> = Vec::with_capacity(m);
+        let mut rotations: Vec<(P, P)> = Vec::with_capacity(m);
+        let mut g = vec![beta];
+        let mut rel = beta.to_f64() / bnorm;
+
+        while h.len() < m && k < cfg.max_iter {
+            k += 1;
+            let j = h.len();
+
+            let mut w = op.apply(basis[j].view());
+            check(&w, k)?;
+
+            let mut col = Vec::with_capacity(j + 2);
+            for v in &basis {
+                let hij = dot::<P, A>(&w, v);
+                w = combine::<P, A>(&w, &[(-hij, v)]);
+                col.push(hij);
+            }
+            let next = dot::<P, A>(&w, &w).sqrt();
+            col.push(next);
+
+            for (i, &(c, s)) in rotations.iter().enumerate() {
+                let (a, b) = (col[i], col[i + 1]);
+                col[i] = rotate::<P, A>(c, a, s, b);
+                col[i + 1] = rotate::<P, A>(-s, a, c, b);
+            }
+
+            let (c, s, d) = givens::<P, A>(col[j], col[j + 1]);
+            col[j] = d;
+            col.pop();
+            rotations.push((c, s));
+
+            g.push(-s * g[j]);
+            g[j] = c * g[j];
+            h.push(col);
+
+            rel = g[j + 1].to_f64().abs() / bnorm;
+            if !rel.is_finite() {
+                return Err(LinalgError::NaR(k));
+            }
+            residuals.push(rel);
+
+            if rel <= cfg.tol || next.is_zero() {
+                break;
+            }
+            basis.push(w.mapv(|t| t / next));
+        }
+
+        // back substitution on the rotated Hessenberg matrix
+        let n = h.len();
+        let mut y = vec![P::zero(); n];
+        for i in (0..n).rev() {
+            let mut acc = A::init();
+            acc.add(g[i]);
+            (i + 1..n).for_each(|l| acc.fma(-h[l][i], y[l]));
+            y[i] = acc.finish() / h[i][i];
+        }
+
+        let terms: Vec<(P, &Array1<P>)> = y.into_iter().zip(&basis).collect();
+        x = combine::<P, A>(&x, &terms);
+        check(&x, k)?;
+
+        if rel <= cfg.tol {
+            return Ok(Solution{x, residuals, converged: true});
+        }
+    }
+
+    Ok(Solution{x, residuals, converged: false})
+}
+
+pub fn cg<P: Scalar, Op: LinearOperator<P>>(op: &Op, b: ArrayView1<P>, cfg: SolverConfig) -> Result<Solution<P>, LinalgError> {
+    cg_with::<P, P::Acc, Op>(op, b, cfg)
+}
+
+pub fn bicgstab<P: Scalar, Op: LinearOperator<P>>(op: &Op, b: ArrayView1<P>, cfg: SolverConfig) -> Result<Solution<P>, LinalgError> {
+    bicgstab_with::<P, P::Acc, Op>(op, b, cfg)
+}
+
+pub fn gmres<P: Scalar, Op: LinearOperator<P>>(op: &Op, b: ArrayView1<P>, restart: usize, cfg: SolverConfig) -> Result<Solution<P>, LinalgError> {
+    gmres_with::<P, P::Acc, Op>(op, b, restart, cfg)
+}
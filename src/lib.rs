@@ -1,55 +1,185 @@
 pub mod posit;
 pub mod quire;
 pub mod flquire;
+pub mod window;
+pub mod exact;
+pub mod accumulator;
+pub mod gemm;
+pub mod tensor;
+pub mod linalg;
+pub mod refine;
+pub mod krylov;
+pub mod sparse;
+pub mod eigen;
+pub mod complex;
+pub mod fft;
+pub mod filter;
+pub mod cnn;
+pub mod transformer;
+pub mod train;
+pub mod dual;
+pub mod interval;
+pub mod double;
+pub mod stats;
+pub mod poly;
+pub mod rng;
+pub mod sizing;
 
 use posit::Posit;
-use quire::Quire;
 use flquire::FLQuire;
+use accumulator::{Naive, Policy};
+use gemm::Trans;
 use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
 use pyo3::wrap_pyfunction;
-use ndarray::{ArrayD, Array2};
-use numpy::{IntoPyArray, PyArrayDyn, PyReadonlyArrayDyn, PyReadonlyArray2, PyArray2};
+use ndarray::{ArrayD, Array2, Array4};
+use numpy::{IntoPyArray, PyArrayDyn, PyReadonlyArrayDyn, PyReadonlyArray2, PyArray2, PyReadonlyArray4, PyArray4};
 
 #[pyfunction]
 fn matmul<'p>(py: Python<'p>, a: PyReadonlyArray2<u32>,  b: PyReadonlyArray2<u32>, _n: u8, es: u8) -> PyResult<&'p PyArray2<u32>> {
     if es == 1 {
-        let ap: Array2<Quire<8, 1>> = a.as_array().mapv(|i| Posit::<8, 1>(i).into());
-        let bp: Array2<Quire<8, 1>> = b.as_array().mapv(|i| Posit::<8, 1>(i).into());
+        let ap: Array2<Posit<8, 1>> = a.as_array().mapv(Posit::<8, 1>);
+        let bp: Array2<Posit<8, 1>> = b.as_array().mapv(Posit::<8, 1>);
 
-        let cp = ap.dot(&bp).mapv(|i| Posit::<8, 1>::from(i).0);
+        let cp = gemm::gemm(Trans::No, ap.view(), Trans::No, bp.view()).mapv(|i| i.0);
         Ok(cp.into_pyarray(py))
     }
     else {
-        let ap: Array2<Quire<8, 0>> = a.as_array().mapv(|i| Posit::<8, 0>(i).into());
-        let bp: Array2<Quire<8, 0>> = b.as_array().mapv(|i| Posit::<8, 0>(i).into());
+        let ap: Array2<Posit<8, 0>> = a.as_array().mapv(Posit::<8, 0>);
+        let bp: Array2<Posit<8, 0>> = b.as_array().mapv(Posit::<8, 0>);
 
-        let cp = ap.dot(&bp).mapv(|i| Posit::<8, 0>::from(i).0);
+        let cp = gemm::gemm(Trans::No, ap.view(), Trans::No, bp.view()).mapv(|i| i.0);
         Ok(cp.into_pyarray(py))
     }
 }
 
 #[pyfunction]
 fn matmul_fl<'p>(py: Python<'p>, a: PyReadonlyArray2<u32>,  b: PyReadonlyArray2<u32>, _n: u8, _es: u8) -> PyResult<&'p PyArray2<u32>> {
-    let ap: Array2<FLQuire<8, 1, 20>> = a.as_array().mapv(|i| Posit::<8, 1>(i).into());
-    let bp: Array2<FLQuire<8, 1, 20>> = b.as_array().mapv(|i| Posit::<8, 1>(i).into());
+    let ap: Array2<Posit<8, 1>> = a.as_array().mapv(Posit::<8, 1>);
+    let bp: Array2<Posit<8, 1>> = b.as_array().mapv(Posit::<8, 1>);
 
-    let cp = ap.dot(&bp).mapv(|i| Posit::<8, 1>::from(i).0);
+    let cp = accumulator::matmul::<_, FLQuire<8, 1, 20>>(ap.view(), bp.view()).mapv(|i| i.0);
     Ok(cp.into_pyarray(py))
 }
 
 #[pyfunction]
 fn matmul_p<'p>(py: Python<'p>, a: PyReadonlyArray2<u32>,  b: PyReadonlyArray2<u32>, _n: u8, _es: u8) -> PyResult<&'p PyArray2<u32>> {
-    let ap: Array2<Posit<8, 1>> = a.as_array().mapv(|i| Posit::<8, 1>(i));
-    let bp: Array2<Posit<8, 1>> = b.as_array().mapv(|i| Posit::<8, 1>(i));
+    let ap: Array2<Posit<8, 1>> = a.as_array().mapv(Posit::<8, 1>);
+    let bp: Array2<Posit<8, 1>> = b.as_array().mapv(Posit::<8, 1>);
 
-    let cp = ap.dot(&bp).mapv(|i| i.0);
+    let cp = accumulator::matmul::<_, Naive<Posit<8, 1>>>(ap.view(), bp.view()).mapv(|i| i.0);
     Ok(cp.into_pyarray(py))
 }
 
 #[pyfunction]
-fn matmul2<'p>(py: Python<'p>, a: PyReadonlyArray2<f32>,  b: PyReadonlyArray2<f32>, _n: u8, _es: u8) -> PyResult<&'p PyArray2<f32>> {
+fn matmul_acc<'p>(py: Python<'p>, a: PyReadonlyArray2<u32>,  b: PyReadonlyArray2<u32>, _n: u8, es: u8, policy: &str) -> PyResult<&'p PyArray2<u32>> {
+    let policy: Policy = policy.parse().map_err(PyValueError::new_err)?;
+
+    if es == 1 {
+        let ap: Array2<Posit<8, 1>> = a.as_array().mapv(Posit::<8, 1>);
+        let bp: Array2<Posit<8, 1>> = b.as_array().mapv(Posit::<8, 1>);
+
+        let cp = policy.matmul(ap.view(), bp.view()).mapv(|i| i.0);
+        Ok(cp.into_pyarray(py))
+    }
+    else {
+        let ap: Array2<Posit<8, 0>> = a.as_array().mapv(Posit::<8, 0>);
+        let bp: Array2<Posit<8, 0>> = b.as_array().mapv(Posit::<8, 0>);
+
+        let cp = policy.matmul(ap.view(), bp.view()).mapv(|i| i.0);
+        Ok(cp.into_pyarray(py))
+    }
+}
+
+#[pyfunction]
+fn matmul_batched<'p>(py: Python<'p>, a: PyReadonlyArrayDyn<'p, u32>, b: PyReadonlyArrayDyn<'p, u32>, _n: u8, es: u8) -> PyResult<&'p PyArrayDyn<u32>> {
+    if es == 1 {
+        let ap: ArrayD<Posit<8, 1>> = a.as_array().mapv(Posit::<8, 1>);
+        let bp: ArrayD<Posit<8, 1>> = b.as_array().mapv(Posit::<8, 1>);
+
+        let cp = tensor::matmul_batched(ap.view(), bp.view()).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0);
+        Ok(cp.into_pyarray(py))
+    }
+    else {
+        let ap: ArrayD<Posit<8, 0>> = a.as_array().mapv(Posit::<8, 0>);
+        let bp: ArrayD<Posit<8, 0>> = b.as_array().mapv(Posit::<8, 0>);
+
+        let cp = tensor::matmul_batched(ap.view(), bp.view()).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0);
+        Ok(cp.into_pyarray(py))
+    }
+}
+
+#[pyfunction]
+fn einsum<'p>(py: Python<'p>, spec: &str, a: PyReadonlyArrayDyn<'p, u32>, b: PyReadonlyArrayDyn<'p, u32>, _n: u8, es: u8) -> PyResult<&'p PyArrayDyn<u32>> {
+    if es == 1 {
+        let ap: ArrayD<Posit<8, 1>> = a.as_array().mapv(Posit::<8, 1>);
+        let bp: ArrayD<Posit<8, 1>> = b.as_array().mapv(Posit::<8, 1>);
+
+        let cp = tensor::einsum(spec, &[ap.view(), bp.view()]).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0);
+        Ok(cp.into_pyarray(py))
+    }
+    else {
+        let ap: ArrayD<Posit<8, 0>> = a.as_array().mapv(Posit::<8, 0>);
+        let bp: ArrayD<Posit<8, 0>> = b.as_array().mapv(Posit::<8, 0>);
+
+        let cp = tensor::einsum(spec, &[ap.view(), bp.view()]).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0);
+        Ok(cp.into_pyarray(py))
+    }
+}
 
-    let cp = a.as_array().dot(&b.as_array());
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn conv2d<'p>(py: Python<'p>, x: PyReadonlyArray4<u32>, w: PyReadonlyArray4<u32>, stride: (usize, usize), padding: (usize, usize), dilation: (usize, usize), groups: usize, _n: u8, es: u8) -> PyResult<&'p PyArray4<u32>> {
+    let cfg = cnn::Conv2dConfig{stride, padding, dilation, groups};
+
+    if es == 1 {
+        let xp: Array4<Posit<8, 1>> = x.as_array().mapv(Posit::<8, 1>);
+        let wp: Array4<Posit<8, 1>> = w.as_array().mapv(Posit::<8, 1>);
+
+        let cp = cnn::conv2d(xp.view(), wp.view(), None, cfg).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0);
+        Ok(cp.into_pyarray(py))
+    }
+    else {
+        let xp: Array4<Posit<8, 0>> = x.as_array().mapv(Posit::<8, 0>);
+        let wp: Array4<Posit<8, 0>> = w.as_array().mapv(Posit::<8, 0>);
+
+        let cp = cnn::conv2d(xp.view(), wp.view(), None, cfg).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0);
+        Ok(cp.into_pyarray(py))
+    }
+}
+
+#[pyfunction]
+fn max_pool2d<'p>(py: Python<'p>, x: PyReadonlyArray4<u32>, kernel: (usize, usize), stride: (usize, usize), padding: (usize, usize), _n: u8, es: u8) -> PyResult<&'p PyArray4<u32>> {
+    let cfg = cnn::Pool2dConfig{kernel, stride, padding};
+
+    let cp = if es == 1 {
+        cnn::max_pool2d(x.as_array().mapv(Posit::<8, 1>).view(), cfg).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0)
+    }
+    else {
+        cnn::max_pool2d(x.as_array().mapv(Posit::<8, 0>).view(), cfg).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0)
+    };
+
+    Ok(cp.into_pyarray(py))
+}
+
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn avg_pool2d<'p>(py: Python<'p>, x: PyReadonlyArray4<u32>, kernel: (usize, usize), stride: (usize, usize), padding: (usize, usize), count_include_pad: bool, _n: u8, es: u8) -> PyResult<&'p PyArray4<u32>> {
+    let cfg = cnn::Pool2dConfig{kernel, stride, padding};
+
+    let cp = if es == 1 {
+        cnn::avg_pool2d(x.as_array().mapv(Posit::<8, 1>).view(), cfg, count_include_pad).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0)
+    }
+    else {
+        cnn::avg_pool2d(x.as_array().mapv(Posit::<8, 0>).view(), cfg, count_include_pad).map_err(|e| PyValueError::new_err(e.to_string()))?.mapv(|i| i.0)
+    };
+
+    Ok(cp.into_pyarray(py))
+}
+
+#[pyfunction]
+fn matmul2<'p>(py: Python<'p>, a: PyReadonlyArray2<f32>,  b: PyReadonlyArray2<f32>, _n: u8, _es: u8) -> PyResult<&'p PyArray2<f32>> {
+    let cp = accumulator::matmul::<_, f32>(a.as_array(), b.as_array());
     Ok(cp.into_pyarray(py))
 }
 
@@ -128,6 +258,12 @@ fn namagiri(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(matmul, m)?)?;
     m.add_function(wrap_pyfunction!(matmul_fl, m)?)?;
     m.add_function(wrap_pyfunction!(matmul_p, m)?)?;
+    m.add_function(wrap_pyfunction!(matmul_acc, m)?)?;
+    m.add_function(wrap_pyfunction!(matmul_batched, m)?)?;
+    m.add_function(wrap_pyfunction!(einsum, m)?)?;
+    m.add_function(wrap_pyfunction!(conv2d, m)?)?;
+    m.add_function(wrap_pyfunction!(max_pool2d, m)?)?;
+    m.add_function(wrap_pyfunction!(avg_pool2d, m)?)?;
     m.add_function(wrap_pyfunction!(matmul2, m)?)?;
 
     m.add_function(wrap_pyfunction!(add, m)?)?;
@@ -0,0 +1,314 @@
+use crate::posit::Posit;
+use crate::gemm::{fused_dot, fused_sum};
+use ndarray::{s, Array1, Array2, ArrayView1, ArrayView2};
+use num_traits::identities::{One, Zero};
+use num_traits::sign::Signed;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinalgError {
+    // operand shapes do not fit the operation
+    Shape,
+    // exact zero pivot at the given step
+    Singular(usize),
+    // NaR met at the given step
+    NaR(usize),
+    NotPositiveDefinite(usize),
+}
+
+impl fmt::Display for LinalgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinalgError::Shape => write!(f, "operand shapes do not match"),
+            LinalgError::Singular(k) => write!(f, "zero pivot at step {}", k),
+            LinalgError::NaR(k) => write!(f, "NaR at step {}", k),
+            LinalgError::NotPositiveDefinite(k) => write!(f, "matrix is not positive definite (step {})", k),
+        }
+    }
+}
+
+impl std::error::Error for LinalgError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Triangle {
+    Lower,
+    Upper,
+}
+
+// (c + sum(a * b)) / d with a single rounding
+fn fused_div<const N: u8, const ES: u8, I>(c: Posit<N, ES>, terms: I, d: Posit<N, ES>) -> Posit<N, ES>
+    where I: IntoIterator<Item = (Posit<N, ES>, Posit<N, ES>)>
+{
+    fused_sum(c, terms).div_posit(d)
+}
+
+fn checked<const N: u8, const ES: u8>(p: Posit<N, ES>, step: usize) -> Result<Posit<N, ES>, LinalgError> {
+    if p.is_nar() {Err(LinalgError::NaR(step))} else {Ok(p)}
+}
+
+fn check_finite<const N: u8, const ES: u8>(a: ArrayView2<Posit<N, ES>>) -> Result<(), LinalgError> {
+    if a.iter().any(|p| p.is_nar()) {Err(LinalgError::NaR(0))} else {Ok(())}
+}
+
+// solves T x = b reading only the given triangle of t
+pub fn solve_triangular<const N: u8, const ES: u8>(t: ArrayView2<Posit<N, ES>>, b: ArrayView1<Posit<N, ES>>, uplo: Triangle, unit_diagonal: bool) -> Result<Array1<Posit<N, ES>>, LinalgError> {
+    let n = b.len();
+    if t.dim() != (n, n) {
+        return Err(LinalgError::Shape);
+    }
+
+    let mut x = Array1::<Posit<N, ES>>::zeros(n);
+
+    let order: Vec<usize> = match uplo {
+        Triangle::Lower => (0..n).collect(),
+        Triangle::Upper => (0..n).rev().collect(),
+    };
+
+    for &i in &order {
+        let known: Vec<usize> = match uplo {
+            Triangle::Lower => (0..i).collect(),
+            Triangle::Upper => (i + 1..n).collect(),
+        };
+        let terms = known.iter().map(|&k| (-t[[i, k]], x[k]));
+
+        x[i] = if unit_diagonal {
+            fused_dot(b[i], terms)
+        }
+        else if t[[i, i]].is_zero() {
+            return Err(LinalgError::Singular(i));
+        }
+        else {
+            fused_div(b[i], terms, t[[i, i]])
+        };
+
+        checked(x[i], i)?;
+    }
+
+    Ok(x)
+}
+
+// P A = L U with unit L below the diagonal of lu and U on and above it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lu<const N: u8, const ES: u8> {
+    pub lu: Array2<Posit<N, ES>>,
+    // row i of P A is row perm[i] of A
+    pub perm: Vec<usize>,
+    pub swaps: usize,
+}
+
+pub fn lu<const N: u8, const ES: u8>(a: ArrayView2<Posit<N, ES>>) -> Result<Lu<N, ES>, LinalgError> {
+    let n = a.nrows();
+    if a.ncols() != n {
+        return Err(LinalgError::Shape);
+    }
+    check_finite(a)?;
+
+    let mut w = a.to_owned();
+    let mut perm: Vec<usize> = (0..n).collect();
+    let mut swaps = 0;
+
+    // Crout ordering: U is a single fused dot product per entry, and the exact sums
+    // below the diagonal stay in the quire, so the pivot is chosen from them and
+    // each entry of L is rounded once by the division
+    for j in 0..n {
+        let mut sums = Vec::with_capacity(n - j);
+        for i in 0..n {
+            let terms: Vec<_> = (0..i.min(j)).map(|k| (-w[[i, k]], w[[k, j]])).collect();
+            let q = fused_sum(w[[i, j]], terms);
+            if q.is_nar() {
+                return Err(LinalgError::NaR(j));
+            }
+            if i < j {w[[i, j]] = Posit::from(q)} else {sums.push(q)}
+        }
+
+        let p = (0..n - j).fold(0, |p, i| if sums[i].abs() > sums[p].abs() {i} else {p}) + j;
+
+        if p != j {
+            for c in 0..n {
+                w.swap([p, c], [j, c]);
+            }
+            sums.swap(p - j, 0);
+            perm.swap(p, j);
+            swaps += 1;
+        }
+
+        let d = Posit::from(sums[0]);
+        if d.is_zero() {
+            return Err(LinalgError::Singular(j));
+        }
+
+        w[[j, j]] = d;
+        for i in j + 1..n {
+            w[[i, j]] = checked(sums[i - j].div_posit(d), j)?;
+        }
+    }
+
+    Ok(Lu{lu: w, perm, swaps})
+}
+
+impl<const N: u8, const ES: u8> Lu<N, ES> {
+    pub fn l(&self) -> Array2<Posit<N, ES>> {
+        Array2::from_shape_fn(self.lu.dim(), |(i, j)| if i == j {Posit::one()} else if i > j {self.lu[[i, j]]} else {Posit::zero()})
+    }
+
+    pub fn u(&self) -> Array2<Posit<N, ES>> {
+        Array2::from_shape_fn(self.lu.dim(), |(i, j)| if i <= j {self.lu[[i, j]]} else {Posit::zero()})
+    }
+
+    pub fn solve(&self, b: ArrayView1<Posit<N, ES>>) -> Result<Array1<Posit<N, ES>>, LinalgError> {
+        if b.len() != self.perm.len() {
+            return Err(LinalgError::Shape);
+        }
+
+        let pb: Array1<Posit<N, ES>> = self.perm.iter().map(|&i| b[i]).collect();
+        let y = solve_triangular(self.lu.view(), pb.view(), Triangle::Lower, true)?;
+
+        solve_triangular(self.lu.view(), y.view(), Triangle::Upper, false)
+    }
+
+    pub fn solve_matrix(&self, b: ArrayView2<Posit<N, ES>>) -> Result<Array2<Posit<N, ES>>, LinalgError> {
+        let mut x = Array2::<Posit<N, ES>>::zeros(b.dim());
+
+        for (j, col) in b.columns().into_iter().enumerate() {
+            x.column_mut(j).assign(&self.solve(col)?);
+        }
+
+        Ok(x)
+    }
+
+    pub fn det(&self) -> Posit<N, ES> {
+        let d = self.lu.diag().iter().fold(Posit::one(), |acc, &x| acc * x);
+
+        if self.swaps & 1 == 0 {d} else {-d}
+    }
+
+    pub fn inv(&self) -> Result<Array2<Posit<N, ES>>, LinalgError> {
+        self.solve_matrix(Array2::eye(self.perm.len()).view())
+    }
+}
+
+pub fn solve<const N: u8, const ES: u8>(a: ArrayView2<Posit<N, ES>>, b: ArrayView1<Posit<N, ES>>) -> Result<Array1<Posit<N, ES>>, LinalgError> {
+    lu(a)?.solve(b)
+}
+
+pub fn det<const N: u8, const ES: u8>(a: ArrayView2<Posit<N, ES>>) -> Result<Posit<N, ES>, LinalgError> {
+    match lu(a) {
+        Ok(f) => Ok(f.det()),
+        Err(LinalgError::Singular(_)) => Ok(Posit::zero()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn inv<const N: u8, const ES: u8>(a: ArrayView2<Posit<N, ES>>) -> Result<Array2<Posit<N, ES>>, LinalgError> {
+    lu(a)?.inv()
+}
+
+// A = L L^T for symmetric positive definite A, only the lower triangle is read
+pub fn cholesky<const N: u8, const ES: u8>(a: ArrayView2<Posit<N, ES>>) -> Result<Array2<Posit<N, ES>>, LinalgError> {
+    let n = a.nrows();
+    if a.ncols() != n {
+        return Err(LinalgError::Shape);
+    }
+
+    let mut l = Array2::<Posit<N, ES>>::zeros((n, n));
+
+    for j in 0..n {
+        let d = checked(fused_dot(a[[j, j]], (0..j).map(|k| (-l[[j, k]], l[[j, k]]))), j)?;
+
+        if d <= Posit::zero() {
+            return Err(LinalgError::NotPositiveDefinite(j));
+        }
+
+        let d = d.sqrt();
+        l[[j, j]] = d;
+
+        for i in j + 1..n {
+            l[[i, j]] = checked(fused_div(a[[i, j]], (0..j).map(|k| (-l[[i, k]], l[[j, k]])), d), j)?;
+        }
+    }
+
+    Ok(l)
+}
+
+// thin factorization A = Q R with Q m x n and R n x n upper triangular
+#[derive(Clone, Debug, PartialEq)]
+pub struct Qr<const N: u8, const ES: u8> {
+    pub q: Array2<Posit<N, ES>>,
+    pub r: Array2<Posit<N, ES>>,
+}
+
+// applies I - 2 v v^T / (v^T v) to rows k.. of w
+fn reflect<const N: u8, const ES: u8>(w: &mut Array2<Posit<N, ES>>, k: usize, v: &Array1<Posit<N, ES>>, vv: Posit<N, ES>) {
+    for j in 0..w.ncols() {
+        let dot = fused_sum(Posit::zero(), v.iter().zip(w.slice(s![k.., j])).map(|(&x, &y)| (x, y)));
+        let tau = (dot + dot).div_posit(vv);
+
+        for (i, &vi) in v.iter().enumerate() {
+            w[[k + i, j]] = fused_dot(w[[k + i, j]], [(-tau, vi)]);
+        }
+    }
+}
+
+pub fn qr<const N: u8, const ES: u8>(a: ArrayView2<Posit<N, ES>>) -> Result<Qr<N, ES>, LinalgError> {
+    let (m, n) = a.dim();
+    if m < n {
+        return Err(LinalgError::Shape);
+    }
+    check_finite(a)?;
+
+    let mut r = a.to_owned();
+    let mut reflectors = Vec::with_capacity(n);
+
+    for k in 0..n {
+        let x = r.slice(s![k.., k]).to_owned();
+        let norm = fused_dot(Posit::zero(), x.iter().map(|&t| (t, t))).sqrt();
+
+        if norm.is_zero() {
+            continue;
+        }
+
+        // reflect onto -sign(x0) |x| e0 so the first entry never cancels
+        let alpha = if x[0].is_negative() {norm} else {-norm};
+        let mut v = x;
+        v[0] = v[0] - alpha;
+
+        let vv = fused_dot(Posit::zero(), v.iter().map(|&t| (t, t)));
+        if vv.is_zero() {
+            continue;
+        }
+
+        reflect(&mut r, k, &v, vv);
+        checked(r[[k, k]], k)?;
+
+        r[[k, k]] = alpha;
+        r.slice_mut(s![k + 1.., k]).fill(Posit::zero());
+
+        reflectors.push((k, v, vv));
+    }
+
+    let mut q = Array2::from_shape_fn((m, n), |(i, j)| if i == j {Posit::one()} else {Posit::zero()});
+    for (k, v, vv) in reflectors.iter().rev() {
+        reflect(&mut q, *k, v, *vv);
+    }
+
+    if q.iter().any(|p| p.is_nar()) {
+        return Err(LinalgError::NaR(n));
+    }
+
+    Ok(Qr{q, r: r.slice(s![..n, ..]).to_owned()})
+}
+
+impl<const N: u8, const ES: u8> Qr<N, ES> {
+    // least squares solution of A x = b
+    pub fn solve(&self, b: ArrayView1<Posit<N, ES>>) -> Result<Array1<Posit<N, ES>>, LinalgError> {
+        if b.len() != self.q.nrows() {
+            return Err(LinalgError::Shape);
+        }
+
+        let qtb: Array1<Posit<N, ES>> = self.q.columns().into_iter()
+            .map(|c| fused_dot(Posit::zero(), c.iter().zip(b).map(|(&x, &y)| (x, y))))
+            .collect();
+
+        solve_triangular(self.r.view(), qtb.view(), Triangle::Upper, false)
+    }
+}
@@ -0,0 +1,199 @@
+use crate::posit::Posit;
+use crate::double::Exact;
+use crate::gemm::fused_dot;
+use ndarray::{Array1, Array2, ArrayView1};
+use num_traits::identities::Zero;
+use std::f64::consts::PI;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PolyError {
+    // the fitting bounds are not a finite interval with a < b
+    Interval(f64, f64),
+    // the Remez system had a zero pivot at the given step
+    Singular(usize),
+}
+
+impl fmt::Display for PolyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolyError::Interval(a, b) => write!(f, "minimax: [{}, {}] is not an interval", a, b),
+            PolyError::Singular(k) => write!(f, "minimax: singular system at step {}", k),
+        }
+    }
+}
+
+impl std::error::Error for PolyError {}
+
+// Polynomials take their coefficients in ascending powers, c[0] + c[1] x + ...
+
+// sum c_k x^k held exactly through Horner's rule, None once it outgrows 128 bits
+fn exact_horner<const N: u8, const ES: u8>(coeffs: ArrayView1<Posit<N, ES>>, x: Posit<N, ES>) -> Option<Exact> {
+    let x = Exact::of(x);
+    coeffs.iter().rev().try_fold(Exact::of(Posit::<N, ES>::zero()), |acc, &c| acc.mul(x)?.add(Exact::of(c)))
+}
+
+// Estrin's scheme: c_2k + c_2k+1 x, then pairs of those joined by x^2, x^4, ...,
+// every step one fused multiply-add, so the roundings grow with log2(degree)
+fn estrin<const N: u8, const ES: u8>(coeffs: ArrayView1<Posit<N, ES>>, x: Posit<N, ES>) -> Posit<N, ES> {
+    let mut level = coeffs.to_vec();
+    let mut power = x;
+
+    while level.len() > 1 {
+        level = level.chunks(2).map(|c| if c.len() == 2 {fused_dot(c[0], [(c[1], power)])} else {c[0]}).collect();
+        power = fused_dot(Posit::zero(), [(power, power)]);
+    }
+
+    level.first().cloned().unwrap_or(Posit::zero())
+}
+
+fn has_nar<const N: u8, const ES: u8>(coeffs: ArrayView1<Posit<N, ES>>, x: Posit<N, ES>) -> bool {
+    x.is_nar() || coeffs.iter().any(|c| c.is_nar())
+}
+
+// the polynomial at x rounded once, or through Estrin's scheme when the exact
+// value needs more than 128 bits (high degrees, or x with a long fraction)
+pub fn poly_eval<const N: u8, const ES: u8>(coeffs: ArrayView1<Posit<N, ES>>, x: Posit<N, ES>) -> Posit<N, ES> {
+    if has_nar(coeffs, x) {
+        return Posit::NAR;
+    }
+
+    exact_horner(coeffs, x).map_or_else(|| estrin(coeffs, x), Exact::round)
+}
+
+// p(x) / q(x) as a single rounding of the exact ratio where both fit, NaR where q(x) = 0
+pub fn rational_eval<const N: u8, const ES: u8>(p: ArrayView1<Posit<N, ES>>, q: ArrayView1<Posit<N, ES>>, x: Posit<N, ES>) -> Posit<N, ES> {
+    if has_nar(p, x) || has_nar(q, x) {
+        return Posit::NAR;
+    }
+
+    match (exact_horner(p, x), exact_horner(q, x)) {
+        (Some(num), Some(den)) => num.ratio(den),
+        _ => Exact::of(estrin(p, x)).ratio(Exact::of(estrin(q, x))),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Minimax<const N: u8, const ES: u8> {
+    pub coeffs: Array1<Posit<N, ES>>,
+    // max |p(x) - f(x)| on the fitting grid, for p with the rounded coefficients
+    pub error: f64,
+}
+
+const GRID: usize = 4096;
+const ITERATIONS: usize = 50;
+
+fn horner(c: &[f64], x: f64) -> f64 {
+    c.iter().rev().fold(0.0, |acc, &c| acc * x + c)
+}
+
+// Gaussian elimination with partial pivoting
+fn gauss(mut a: Array2<f64>, mut b: Vec<f64>) -> Result<Vec<f64>, PolyError> {
+    let n = b.len();
+
+    for j in 0..n {
+        let p = (j..n).fold(j, |p, i| if a[[i, j]].abs() > a[[p, j]].abs() {i} else {p});
+        if a[[p, j]] == 0.0 {
+            return Err(PolyError::Singular(j));
+        }
+
+        for c in 0..n {
+            a.swap([p, c], [j, c]);
+        }
+        b.swap(p, j);
+
+        for i in j + 1..n {
+            let l = a[[i, j]] / a[[j, j]];
+            for c in j..n {
+                a[[i, c]] -= l * a[[j, c]];
+            }
+            b[i] -= l * b[j];
+        }
+    }
+
+    for i in (0..n).rev() {
+        b[i] = (b[i] - (i + 1..n).map(|k| a[[i, k]] * b[k]).sum::<f64>()) / a[[i, i]];
+    }
+
+    Ok(b)
+}
+
+// the largest error in each run of one sign, trimmed at the ends to count points
+fn alternants(grid: &[f64], err: &[f64], count: usize) -> Vec<f64> {
+    let mut peaks: Vec<(f64, f64)> = vec![];
+
+    for (&x, &e) in grid.iter().zip(err) {
+        match peaks.last_mut() {
+            Some(last) if last.1 * e >= 0.0 => if e.abs() > last.1.abs() {*last = (x, e)},
+            _ => peaks.push((x, e)),
+        }
+    }
+
+    while peaks.len() > count {
+        let end = if peaks[0].1.abs() < peaks[peaks.len() - 1].1.abs() {0} else {peaks.len() - 1};
+        peaks.remove(end);
+    }
+
+    peaks.into_iter().map(|(x, _)| x).collect()
+}
+
+// Remez exchange for the degree n polynomial closest to g in the max norm
+fn remez<G: Fn(f64) -> f64>(g: G, n: usize, grid: &[f64]) -> Result<Vec<f64>, PolyError> {
+    let (a, b) = (grid[0], grid[grid.len() - 1]);
+
+    // Chebyshev nodes as the first reference; the extrema would put an even g's
+    // error at one level on a symmetric interval with degree zero
+    let mut nodes: Vec<f64> = (0..n + 2).map(|i| (a + b) / 2.0 - (b - a) / 2.0 * (PI * (2 * i + 1) as f64 / (2 * n + 4) as f64).cos()).collect();
+    let mut c = vec![0.0; n + 1];
+
+    for _ in 0..ITERATIONS {
+        // sum c_j x_i^j + (-1)^i level = g(x_i)
+        let m = Array2::from_shape_fn((n + 2, n + 2), |(i, j)| {
+            if j <= n {nodes[i].powi(j as i32)} else if i % 2 == 0 {1.0} else {-1.0}
+        });
+        let solution = gauss(m, nodes.iter().map(|&x| g(x)).collect())?;
+        let level = solution[n + 1].abs();
+        c = solution[..=n].to_vec();
+
+        let err: Vec<f64> = grid.iter().map(|&x| horner(&c, x) - g(x)).collect();
+        let peak = err.iter().fold(0.0f64, |m, e| m.max(e.abs()));
+        let next = alternants(grid, &err, n + 2);
+
+        // equioscillating, or no better reference to move to
+        if peak - level <= 1e-9 * peak || next.len() < n + 2 {
+            break;
+        }
+        nodes = next;
+    }
+
+    Ok(c)
+}
+
+// coefficients of a degree polynomial approximating f on [a, b] in the max norm,
+// rounded to Posit<N, ES>. Rounding every coefficient of the real minimax fit
+// can cost far more than half an ulp each, so the coefficients are also fixed
+// from the highest power down, refitting the lower powers to what the rounded
+// ones leave over, and whichever set does better on the grid is kept.
+pub fn minimax<const N: u8, const ES: u8, F: Fn(f64) -> f64>(f: F, degree: usize, (a, b): (f64, f64)) -> Result<Minimax<N, ES>, PolyError> {
+    if a >= b || !a.is_finite() || !b.is_finite() {
+        return Err(PolyError::Interval(a, b));
+    }
+
+    let grid: Vec<f64> = (0..=GRID).map(|i| a + (b - a) * i as f64 / GRID as f64).collect();
+    let round = |c: f64| f64::from(Posit::<N, ES>::from(c));
+
+    let rounded: Vec<f64> = remez(&f, degree, &grid)?.into_iter().map(round).collect();
+
+    let mut fixed = vec![0.0; degree + 1];
+    for k in (0..=degree).rev() {
+        let rest = |x: f64| f(x) - horner(&fixed, x);
+        let c = remez(rest, k, &grid)?[k];
+        fixed[k] = round(c);
+    }
+
+    let error = |c: &[f64]| grid.iter().fold(0.0f64, |m, &x| m.max((horner(c, x) - f(x)).abs()));
+    let (e1, e2) = (error(&fixed), error(&rounded));
+    let (best, error) = if e1 <= e2 {(fixed, e1)} else {(rounded, e2)};
+
+    Ok(Minimax{coeffs: best.iter().map(|&c| Posit::from(c)).collect(), error})
+}
@@ -1,6 +1,6 @@
 use std::fmt;
 use std::mem;
-use std::cmp::min;
+use std::cmp::{max, min};
 
 #[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
 pub struct Posit<const N: u8, const ES: u8>(pub u32);
@@ -100,6 +100,24 @@ fn decode(e:u32, f:u32, n:u8, es:u8, rs:u8) -> u32 {
     if r_o < (n - 1).into() {p + ulp} else {p}
 }
 
+pub(crate) fn div_wide(num: u128, den: u128, shift: u32) -> (u128, bool) {
+    let mut q = num / den;
+    let mut r = num % den;
+
+    for _ in 0..shift {
+        let carry = r >> 127 != 0;
+        r <<= 1;
+        q <<= 1;
+
+        if carry || r >= den {
+            r = r.wrapping_sub(den);
+            q |= 1;
+        }
+    }
+
+    (q, r != 0)
+}
+
 use std::num::FpCategory;
 impl<const N: u8, const ES: u8> std::convert::From<f32> for Posit<N, ES> {
     fn from(item: f32) -> Self {
@@ -133,6 +151,41 @@ impl<const N: u8, const ES: u8> std::convert::From<f32> for Posit<N, ES> {
     }
 }
 
+impl<const N: u8, const ES: u8> std::convert::From<f64> for Posit<N, ES> {
+    fn from(item: f64) -> Self {
+        match item.classify() {
+            FpCategory::Zero => Self::zero(),
+            FpCategory::Infinite | FpCategory::Nan => Self::NAR,
+            _ => {
+                let bits = item.to_bits();
+                let e = ((bits >> 52) & 0x7ff) as i32;
+                let m = bits & ((1 << 52) - 1);
+
+                let (m, e) = if e == 0 {(m, -1074)} else {(m | 1 << 52, e - 1075)};
+
+                Self::from_scaled(bits >> 63 != 0, m as u128, e)
+            },
+        }
+    }
+}
+
+impl<const N: u8, const ES: u8> std::convert::From<Posit<N, ES>> for f64 {
+    fn from(item: Posit<N, ES>) -> Self {
+        if item.is_zero() {
+            return 0f64;
+        }
+
+        if item.is_nar() {
+            return f64::INFINITY;
+        }
+
+        let (s, f, e) = item.to_scaled();
+        let v = f as f64 * 2f64.powi(e);
+
+        if s {-v} else {v}
+    }
+}
+
 impl<const N: u8, const ES: u8> Posit<N, ES> {
     #[inline]
     pub const fn new(i: u32) -> Self {
@@ -176,11 +229,105 @@ impl<const N: u8, const ES: u8> Posit<N, ES> {
         }
     }
 
+    pub(crate) fn to_scaled(self) -> (bool, u32, i32) {
+        let (_, s, rc, r, e, f) = self.encode();
+
+        let rg = if rc {(!r).wrapping_add(1)} else {r};
+
+        (s, f, (rg as i32) * 2i32.pow(ES as u32) + (e as i32) - 31)
+    }
+
+    pub(crate) fn from_scaled(s: bool, frac: u128, exp: i32) -> Self {
+        if frac == 0 {
+            return Self::zero();
+        }
+
+        let lz = frac.leading_zeros();
+        let m = frac << lz;
+
+        let f = ((m >> 96) as u32) | ((m << 32 != 0) as u32);
+
+        let max_scale = (N as i32 - 2) * 2i32.pow(ES as u32);
+        let e = min(max(exp + 127 - lz as i32, -max_scale - 1), max_scale + 1);
+
+        let mut p = decode(e as u32, f, N, ES, Self::RS);
+
+        if p == 0 {
+            return if s {
+                - Self::MINPOS
+            } else {
+                Self::MINPOS
+            };
+        }
+
+        p = if s {((!p).wrapping_add(1) & Self::BODY_MASK) | 0x1u32 << (N - 1)} else {p};
+
+        Self(p)
+    }
+
+    pub(crate) fn from_ratio(s: bool, num: u128, den: u128, exp: i32) -> Self {
+        if den == 0 {
+            return Self::NAR;
+        }
+
+        if num == 0 {
+            return Self::zero();
+        }
+
+        let lz = num.leading_zeros();
+        let k = 127 - den.leading_zeros();
+
+        let (q, st) = div_wide(num << lz, den, k);
+
+        Self::from_scaled(s, q | st as u128, exp - lz as i32 - k as i32)
+    }
 
     #[inline]
     pub fn is_nar(self) -> bool {
         self == Self::NAR
     }
+
+    // correctly rounded, NaR for negative inputs
+    pub fn sqrt(self) -> Self {
+        if self.is_zero() || self.is_nar() {
+            return self;
+        }
+
+        if self.is_negative() {
+            return Self::NAR;
+        }
+
+        let (_, f, e) = self.to_scaled();
+        let (f, e) = if e % 2 != 0 {((f as u128) << 1, e - 1)} else {(f as u128, e)};
+
+        let m = f << 94;
+        let r = isqrt(m);
+
+        Self::from_scaled(false, (r << 1) | (r * r != m) as u128, (e - 94) / 2 - 1)
+    }
+}
+
+pub(crate) fn isqrt(n: u128) -> u128 {
+    let mut r = 0u128;
+    let mut bit = 1u128 << 126;
+    let mut n = n;
+
+    while bit > n {
+        bit >>= 2;
+    }
+
+    while bit != 0 {
+        if n >= r + bit {
+            n -= r + bit;
+            r = (r >> 1) + bit;
+        }
+        else {
+            r >>= 1;
+        }
+        bit >>= 2;
+    }
+
+    r
 }
 
 use std::ops::{Neg, Add, Sub, Mul, Div, Rem, AddAssign};
@@ -331,38 +478,11 @@ impl<const N: u8, const ES: u8> Mul for Posit<N, ES> {
             self
         }
         else {
-            let (_, s1, rc1, r1, e1, frac1) = self.encode();
-            let (_, s2, rc2, r2, e2, frac2) = other.encode();
-
-            let s = s1 ^ s2;
-
-            let d = N - ES - 2;
-
-            let f:u32 = ((frac1 >> (32 -  d)) * (frac2 >> (32 - d))) << (32 - 2 * d);
-
-            let movf = (f & 0x80000000u32) >> 31;
-
-            let f = if movf == 0 {f << 1} else {f};
-
-            let rg1 = if rc1 {(!r1).wrapping_add(1)} else {r1};
-            let rg2 = if rc2 {(!r2).wrapping_add(1)} else {r2};
-
-            let e = ((rg1 << ES) | e1) + ((rg2 << ES) | e2) + movf;
-
-            let mut p = decode(e, f, N, ES, Self::RS);
-
-            if p == 0 {
-                return if s {
-                    - Self::MINPOS
-                } else {
-                    Self::MINPOS
-                };
-            }
-
-            p = if s {((!p).wrapping_add(1) & Self::BODY_MASK) | 0x1u32 << N - 1} else {p};
-
-            Self(p)
+            let (s1, f1, e1) = self.to_scaled();
+            let (s2, f2, e2) = other.to_scaled();
 
+            // the significands multiply exactly in 64 bits, for posit32 too
+            Self::from_scaled(s1 ^ s2, f1 as u128 * f2 as u128, e1 + e2)
         }
     }
 }
@@ -384,9 +504,10 @@ impl<const N: u8, const ES: u8> Div for Posit<N, ES> {
             self
         }
         else {
-            // TODO
-            let recip:Self = (1.0 / f32::from(other)).into();
-            self * recip
+            let (s1, f1, e1) = self.to_scaled();
+            let (s2, f2, e2) = other.to_scaled();
+
+            Self::from_ratio(s1 ^ s2, f1 as u128, f2 as u128, e1 - e2)
         }
     }
 }
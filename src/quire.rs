@@ -1,18 +1,152 @@
-use crate::posit::Posit;
+use crate::posit::{Posit, div_wide, isqrt};
 use std::fmt;
 use std::cmp::{max, min};
 
+/// The exact accumulator of a posit format in 128 bits.
+///
+/// Only formats whose products fit with room for carries are accepted, wider ones use `ExactSum`:
+///
+/// ```compile_fail
+/// use namagiri::{posit::Posit, quire::Quire};
+/// let _ = Quire::from(Posit::<32, 2>(0x4000_0000));
+/// ```
 #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct Quire<const N: u8, const ES: u8>(pub u128);
 
 impl<const N: u8, const ES: u8> Quire<N, ES> {
     #[inline]
     pub const fn new(i: u128) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID;
         Self(i)
     }
 
+    const VALID: () = assert!(Self::FITS, "Quire: the format's products must fit 128 bits, use ExactSum for wider ones");
+
     pub const SIGN_MASK:u128 = 1 << N - 1;
     pub const BIAS: i32 = 2i32.pow(ES as u32 + 1) * (N  as i32 - 2);
+
+    // products of two posits still leave 8 carry bits in the 128
+    pub const FITS: bool = 2 * (Self::BIAS + 1) <= 120;
+
+    // the sign bit alone, as in the posit standard
+    pub const NAR: Self = Self(1 << 127);
+
+    #[inline]
+    pub fn is_nar(self) -> bool {
+        self == Self::NAR
+    }
+
+    // times 2^k, NaR when the result no longer fits
+    pub fn scale(self, k: i32) -> Self {
+        if self.is_nar() || self.0 == 0 {
+            return self;
+        }
+
+        if k >= 0 {
+            let v = self.0 as i128;
+            if k > 126 || (v << k) >> k != v {Self::NAR} else {Self((v << k) as u128)}
+        }
+        else {
+            // shifted-out bits stick to the last bit, so rounding to a posit still sees them
+            let k = min(-k, 127);
+            let v = self.0 as i128;
+            Self(((v >> k) | (v & !(!0i128 << k) != 0) as i128) as u128)
+        }
+    }
+
+    pub fn div_posit(self, other: Posit<N, ES>) -> Posit<N, ES> {
+        if self.is_nar() || other.is_nar() || other.is_zero() {
+            return Posit::NAR;
+        }
+
+        let s = (1 << 127) & self.0 != 0;
+        let quire = if s {(!self.0).wrapping_add(1)} else {self.0};
+
+        let (so, f, e) = other.to_scaled();
+
+        Posit::from_ratio(s ^ so, quire, f as u128, -(Self::BIAS + 1) - e)
+    }
+
+    pub fn div_int(self, n: i64) -> Posit<N, ES> {
+        if self.is_nar() {
+            return Posit::NAR;
+        }
+
+        let s = (1 << 127) & self.0 != 0;
+        let quire = if s {(!self.0).wrapping_add(1)} else {self.0};
+
+        Posit::from_ratio(s ^ (n < 0), quire, n.unsigned_abs() as u128, -(Self::BIAS + 1))
+    }
+
+    // correctly rounded square root of the exact value, NaR when negative
+    pub fn sqrt(self) -> Posit<N, ES> {
+        if (1 << 127) & self.0 != 0 {
+            return Posit::NAR;
+        }
+        if self.0 == 0 {
+            return Posit::zero();
+        }
+
+        // normalise with an even total exponent so it halves exactly
+        let frac = Self::BIAS + 1;
+        let lz = self.0.leading_zeros() as i32;
+        let sh = lz - ((lz + frac) & 1);
+
+        let m = self.0 << sh;
+        let r = isqrt(m);
+
+        Posit::from_scaled(false, (r << 1) | (r * r != m) as u128, -(frac + sh) / 2 - 1)
+    }
+
+    pub fn to_rational(self) -> (i128, i32) {
+        if self.0 == 0 {
+            return (0, 0);
+        }
+
+        let tz = self.0.trailing_zeros();
+
+        ((self.0 as i128) >> tz, tz as i32 - (Self::BIAS + 1))
+    }
+
+    pub fn to_f64(self) -> f64 {
+        if self.is_nar() {
+            return f64::INFINITY;
+        }
+
+        let scale = f64::from_bits(((1023 - (Self::BIAS + 1)) as u64) << 52);
+
+        (self.0 as i128) as f64 * scale
+    }
+
+    pub fn to_decimal(self) -> String {
+        if self.is_nar() {
+            return "NaR".to_string();
+        }
+
+        let s = (1 << 127) & self.0 != 0;
+        let quire = if s {(!self.0).wrapping_add(1)} else {self.0};
+
+        let mut k = (Self::BIAS + 1) as u32;
+        let int = quire >> k;
+        let mut frac = quire & !(!0u128 << k);
+
+        let mut out = if s {format!("-{}", int)} else {format!("{}", int)};
+
+        if frac != 0 {
+            out.push('.');
+        }
+
+        // x * 10 / 2^k == x * 5 / 2^(k - 1), so the denominator shrinks by one bit per digit
+        while frac != 0 {
+            frac *= 5;
+            k -= 1;
+            out.push(char::from(b'0' + (frac >> k) as u8));
+            frac &= !(!0u128 << k);
+        }
+
+        out
+    }
 }
 
 impl<const N: u8, const ES: u8> fmt::Binary for Quire<N, ES> {
@@ -32,7 +166,7 @@ impl<const N: u8, const ES: u8> std::convert::From<Posit<N, ES>> for Quire<N, ES
     fn from(item: Posit<N, ES>) -> Self {
 
         if item.is_zero() {
-            return Self(0x0);
+            return Self::new(0x0);
         }
 
         let (_, s, rc, r, e, f) = item.encode();
@@ -49,7 +183,13 @@ impl<const N: u8, const ES: u8> std::convert::From<Posit<N, ES>> for Quire<N, ES
 
         quire = ((quire as i128) >> (N - ES - 4)) as u128;
 
-        Self(quire)
+        Self::new(quire)
+    }
+}
+
+impl<const N: u8, const ES: u8> std::convert::From<Quire<N, ES>> for f64 {
+    fn from(item: Quire<N, ES>) -> Self {
+        item.to_f64()
     }
 }
 
@@ -60,53 +200,14 @@ impl<const N: u8, const ES: u8> std::convert::From<Quire<N, ES>> for Posit<N, ES
             return Self::zero();
         }
 
-        let s = (1 << 127) & item.0 != 0;
-        let quire = if s {(!item.0).wrapping_add(1)} else {item.0};
-        
-        let lod = quire.leading_zeros() as i32 - (128 - 51 - 1);
-        let f = quire << lod + (128 - 51 - 1);
-
-        let quire_e = (51 - 1 - lod - Quire::<N, ES>::BIAS) as u32;
-
-        let e_o = quire_e & (0xffffffffu32 >> (32 - ES));
-        let r_o = min(if quire_e & (1 << (Self::RS + ES + 1)) == 0 {(quire_e >> ES) + 1} else {(!quire_e >> ES).wrapping_add(1)}, (N - 1).into());
-
-        let e_msb = (quire_e << (31 - Self::RS - ES - 1)) & 0x80000000u32;
-
-        let mut rem = ((f << 1) >> 128 - 32) as u32;
-
-        rem = (e_o << 32 - ES) | (rem >> ES);
-
-        rem = e_msb | (rem >> 1);
-
-        rem = rem >> N;
-
-        rem = if e_msb == 0 {(0xffffffffu32 << (32 - N)) | rem} else {rem};
-
-        rem = rem >> r_o;
-
-        let mut p: u32 = (rem << N) >> (32 - N + 1);
-
-        let l = (0x80000000u32 >> (2 * N - 2)) & rem != 0;
-        let g = (0x80000000u32 >> (2 * N - 1)) & rem != 0;
-        let r = (0x80000000u32 >> (2 * N)) & rem != 0;
-        let st = (rem << (2 * N)) != 0;
-
-        let ulp = if (g & (r | st)) | (l & g & !(r | st)) {1} else {0};
-
-        p = if r_o < (N - 1).into() {p + ulp} else {p};
-
-        if p == 0 {
-            return if s {
-                - Self::MINPOS
-            } else {
-                Self::MINPOS
-            };
+        if item.is_nar() {
+            return Self::NAR;
         }
 
-        p = if s {((!p).wrapping_add(1) & Self::BODY_MASK) | 0x1u32 << N - 1} else {p};
+        let s = (1 << 127) & item.0 != 0;
+        let quire = if s {(!item.0).wrapping_add(1)} else {item.0};
 
-        Self(p)
+        Self::from_scaled(s, quire, -(Quire::<N, ES>::BIAS + 1))
     }
 }
 
@@ -176,16 +277,38 @@ impl<const N: u8, const ES: u8> Mul for Quire<N, ES> {
 impl<const N: u8, const ES: u8> Div for Quire<N, ES> {
     type Output = Self;
 
-    // TODO
-    fn div(self, _other: Self) -> Self::Output {
-        Self(0x0)
+    fn div(self, other: Self) -> Self::Output {
+        if self.is_nar() || other.is_nar() || other.is_zero() {
+            return Self::NAR;
+        }
+
+        if self.is_zero() {
+            return Self::zero();
+        }
+
+        let s1 = (1 << 127) & self.0 != 0;
+        let s2 = (1 << 127) & other.0 != 0;
+        let a = if s1 {(!self.0).wrapping_add(1)} else {self.0};
+        let b = if s2 {(!other.0).wrapping_add(1)} else {other.0};
+
+        let (c, _) = div_wide(a, b, (Self::BIAS + 1) as u32);
+
+        Self(if s1 ^ s2 {(!c).wrapping_add(1)} else {c})
+    }
+}
+
+impl<const N: u8, const ES: u8> Div<Posit<N, ES>> for Quire<N, ES> {
+    type Output = Posit<N, ES>;
+
+    fn div(self, other: Posit<N, ES>) -> Self::Output {
+        self.div_posit(other)
     }
 }
 
 impl<const N: u8, const ES: u8> Zero for Quire<N, ES> {
 
     fn zero() -> Self {
-        Self(0x0)
+        Self::new(0x0)
     }
 
     fn is_zero(self:&Self) -> bool {
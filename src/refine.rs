@@ -0,0 +1,170 @@
+use crate::accumulator::Accumulator;
+use crate::linalg::{self, LinalgError};
+use crate::posit::Posit;
+use crate::exact::ExactSum;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Zip};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RefineConfig {
+    // target normwise backward error
+    pub tol: f64,
+    pub max_iter: usize,
+}
+
+impl Default for RefineConfig {
+    fn default() -> Self {
+        Self{tol: 1e-6, max_iter: 20}
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Refinement<T> {
+    pub x: Array1<T>,
+    // backward error of every iterate, starting with the unrefined solve
+    pub history: Vec<f64>,
+    pub converged: bool,
+}
+
+// ||r|| / (||A|| ||x|| + ||b||) in the infinity norm
+fn backward_error(a: ArrayView2<f64>, x: ArrayView1<f64>, b: ArrayView1<f64>, r: ArrayView1<f64>) -> f64 {
+    let inf = |v: ArrayView1<f64>| v.iter().fold(0f64, |m, x| m.max(x.abs()));
+    let norm_a = a.rows().into_iter().map(|row| row.iter().map(|x| x.abs()).sum::<f64>()).fold(0f64, f64::max);
+    let den = norm_a * inf(x) + inf(b);
+
+    if den == 0.0 {inf(r)} else {inf(r) / den}
+}
+
+// stop once the target is met, the error stalls or the iteration budget runs out
+fn done(history: &[f64], cfg: &RefineConfig) -> Option<bool> {
+    let err = *history.last()?;
+
+    if err <= cfg.tol {
+        Some(true)
+    }
+    else if history.len() > cfg.max_iter || err.is_nan() || (history.len() > 1 && err > 0.5 * history[history.len() - 2]) {
+        Some(false)
+    }
+    else {
+        None
+    }
+}
+
+// b - A x with one rounding per element
+pub fn residual<const N: u8, const ES: u8, A>(a: ArrayView2<Posit<N, ES>>, x: ArrayView1<Posit<N, ES>>, b: ArrayView1<Posit<N, ES>>) -> Array1<Posit<N, ES>>
+    where A: Accumulator<Posit<N, ES>>
+{
+    Zip::from(a.rows()).and(b).map_collect(|row, &bi| {
+        let mut acc = A::init();
+        acc.add(bi);
+        row.iter().zip(x).for_each(|(&aij, &xj)| acc.fma(-aij, xj));
+        acc.finish()
+    })
+}
+
+// f64 holds every posit up to 32 bits exactly, so the change of format rounds
+// once where the f32 path of into_p would round twice for wide fractions
+fn convert<const N: u8, const ES: u8, const TN: u8, const TES: u8>(p: Posit<N, ES>) -> Posit<TN, TES> {
+    f64::from(p).into()
+}
+
+// factor in Posit<NF, ESF>, iterate in Posit<NW, ESW> with residuals accumulated by A
+pub fn refine_with<const NF: u8, const ESF: u8, const NW: u8, const ESW: u8, A>(a: ArrayView2<Posit<NW, ESW>>, b: ArrayView1<Posit<NW, ESW>>, cfg: RefineConfig) -> Result<Refinement<Posit<NW, ESW>>, LinalgError>
+    where A: Accumulator<Posit<NW, ESW>>
+{
+    let factor = linalg::lu(a.mapv(convert::<NW, ESW, NF, ESF>).view())?;
+    let solve = |r: &Array1<Posit<NW, ESW>>| factor.solve(r.mapv(convert::<NW, ESW, NF, ESF>).view()).map(|d| d.mapv(convert::<NF, ESF, NW, ESW>));
+
+    let a64 = a.mapv(f64::from);
+    let b64 = b.mapv(f64::from);
+
+    let mut x = solve(&b.to_owned())?;
+    let mut history = Vec::new();
+
+    loop {
+        let r = residual::<NW, ESW, A>(a, x.view(), b);
+        history.push(backward_error(a64.view(), x.mapv(f64::from).view(), b64.view(), r.mapv(f64::from).view()));
+
+        if let Some(converged) = done(&history, &cfg) {
+            return Ok(Refinement{x, history, converged});
+        }
+
+        x = x + solve(&r)?;
+    }
+}
+
+pub fn refine<const NF: u8, const ESF: u8, const NW: u8, const ESW: u8>(a: ArrayView2<Posit<NW, ESW>>, b: ArrayView1<Posit<NW, ESW>>, cfg: RefineConfig) -> Result<Refinement<Posit<NW, ESW>>, LinalgError> {
+    refine_with::<NF, ESF, NW, ESW, ExactSum<NW, ESW>>(a, b, cfg)
+}
+
+// partial pivoting LU in f32, returning the packed factors and the row order
+fn lu_f32(a: ArrayView2<f64>) -> Result<(Array2<f32>, Vec<usize>), LinalgError> {
+    let n = a.nrows();
+    if a.ncols() != n {
+        return Err(LinalgError::Shape);
+    }
+
+    let mut w = a.mapv(|x| x as f32);
+    let mut perm: Vec<usize> = (0..n).collect();
+
+    for j in 0..n {
+        let p = (j..n).fold(j, |p, i| if w[[i, j]].abs() > w[[p, j]].abs() {i} else {p});
+
+        if p != j {
+            for c in 0..n {
+                w.swap([p, c], [j, c]);
+            }
+            perm.swap(p, j);
+        }
+
+        let d = w[[j, j]];
+        if d == 0.0 {
+            return Err(LinalgError::Singular(j));
+        }
+
+        for i in j + 1..n {
+            w[[i, j]] /= d;
+            for c in j + 1..n {
+                w[[i, c]] -= w[[i, j]] * w[[j, c]];
+            }
+        }
+    }
+
+    Ok((w, perm))
+}
+
+fn solve_f32(lu: &Array2<f32>, perm: &[usize], b: ArrayView1<f64>) -> Array1<f64> {
+    let n = perm.len();
+    let mut x: Array1<f32> = perm.iter().map(|&i| b[i] as f32).collect();
+
+    for i in 0..n {
+        x[i] -= (0..i).map(|k| lu[[i, k]] * x[k]).sum::<f32>();
+    }
+    for i in (0..n).rev() {
+        x[i] = (x[i] - (i + 1..n).map(|k| lu[[i, k]] * x[k]).sum::<f32>()) / lu[[i, i]];
+    }
+
+    x.mapv(|v| v as f64)
+}
+
+// the classic f32 factorization with f64 residuals, as a baseline for the posit solvers
+pub fn refine_float(a: ArrayView2<f64>, b: ArrayView1<f64>, cfg: RefineConfig) -> Result<Refinement<f64>, LinalgError> {
+    if b.len() != a.nrows() {
+        return Err(LinalgError::Shape);
+    }
+
+    let (lu, perm) = lu_f32(a)?;
+
+    let mut x = solve_f32(&lu, &perm, b);
+    let mut history = Vec::new();
+
+    loop {
+        let r = &b - &a.dot(&x);
+        history.push(backward_error(a, x.view(), b, r.view()));
+
+        if let Some(converged) = done(&history, &cfg) {
+            return Ok(Refinement{x, history, converged});
+        }
+
+        x = x + solve_f32(&lu, &perm, r.view());
+    }
+}
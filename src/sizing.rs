@@ -0,0 +1,188 @@
+use crate::posit::Posit;
+use crate::quire::Quire;
+use crate::flquire::FLQuire;
+use crate::rng::XorShift;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    // every bit pattern except NaR with equal probability
+    Bits,
+    Uniform(f32, f32),
+    Normal(f32, f32),
+}
+
+impl Distribution {
+    pub fn sample<const N: u8, const ES: u8>(&self, rng: &mut XorShift) -> Posit<N, ES> {
+        match *self {
+            Distribution::Bits => loop {
+                let p = Posit::<N, ES>(rng.next_u32() & Posit::<N, ES>::MASK);
+                if !p.is_nar() {
+                    return p;
+                }
+            },
+            Distribution::Uniform(lo, hi) => Posit::from(lo + (hi - lo) * rng.next_f64() as f32),
+            Distribution::Normal(mean, std) => Posit::from(mean + std * rng.next_normal() as f32),
+        }
+    }
+}
+
+impl FromStr for Distribution {
+    type Err = String;
+
+    // "bits", "uniform:LO,HI" or "normal:MEAN,STD"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, args) = match s.split_once(':') {
+            Some((kind, args)) => (kind, args),
+            None => (s, ""),
+        };
+
+        let params = || -> Result<(f32, f32), String> {
+            let v: Vec<f32> = args.split(',').map(|x| x.trim().parse::<f32>()).collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+            match v[..] {
+                [a, b] => Ok((a, b)),
+                _ => Err(format!("expected two parameters, got `{}`", args)),
+            }
+        };
+
+        match kind {
+            "bits" => Ok(Distribution::Bits),
+            "uniform" => params().map(|(a, b)| Distribution::Uniform(a, b)),
+            "normal" => params().map(|(a, b)| Distribution::Normal(a, b)),
+            _ => Err(format!("unknown distribution `{}`", kind)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepConfig {
+    pub len: usize,
+    pub trials: usize,
+    pub dist: Distribution,
+    pub seed: u64,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self{len: 256, trials: 1000, dist: Distribution::Normal(0.0, 1.0), seed: 1}
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizeReport {
+    pub size: u16,
+    pub trials: usize,
+    pub mismatches: usize,
+    pub max_ulps: u32,
+    pub mean_ulps: f64,
+}
+
+impl SizeReport {
+    pub fn mismatch_rate(&self) -> f64 {
+        self.mismatches as f64 / self.trials as f64
+    }
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>5} {:>10} {:>12.6} {:>9} {:>10.4}", self.size, self.mismatches, self.mismatch_rate(), self.max_ulps, self.mean_ulps)
+    }
+}
+
+// number of posits between a and b in encoding order
+pub fn ulp_distance<const N: u8, const ES: u8>(a: Posit<N, ES>, b: Posit<N, ES>) -> u32 {
+    let sa = ((a.0 << (32 - N)) as i32) >> (32 - N);
+    let sb = ((b.0 << (32 - N)) as i32) >> (32 - N);
+
+    (sa - sb).unsigned_abs()
+}
+
+pub fn measure<const N: u8, const ES: u8, const SIZE: u16>(cfg: &SweepConfig) -> SizeReport {
+    let mut rng = XorShift::new(cfg.seed);
+    let mut mismatches = 0;
+    let mut max_ulps = 0;
+    let mut total_ulps = 0u64;
+
+    for _ in 0..cfg.trials {
+        let mut q = Quire::<N, ES>::default();
+        let mut fl = FLQuire::<N, ES, SIZE>::default();
+
+        for _ in 0..cfg.len {
+            let a: Posit<N, ES> = cfg.dist.sample(&mut rng);
+            let b: Posit<N, ES> = cfg.dist.sample(&mut rng);
+
+            q += Quire::from(a) * Quire::from(b);
+            fl += FLQuire::from(a) * FLQuire::from(b);
+        }
+
+        let d = ulp_distance(Posit::from(q), Posit::from(fl));
+
+        if d != 0 {
+            mismatches += 1;
+            max_ulps = max_ulps.max(d);
+            total_ulps += d as u64;
+        }
+    }
+
+    SizeReport {
+        size: SIZE,
+        trials: cfg.trials,
+        mismatches,
+        max_ulps,
+        mean_ulps: total_ulps as f64 / cfg.trials as f64,
+    }
+}
+
+// only sizes that hold the fraction of each format are instantiated
+macro_rules! measure_size {
+    ($n:literal, $es:literal, $size:expr, $cfg:expr, $($s:literal)*) => {
+        measure_size!(@arms $n, $es, $size, $cfg, $($s)*
+            16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39 40
+            41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61 62 63 64)
+    };
+    (@arms $n:literal, $es:literal, $size:expr, $cfg:expr, $($s:literal)*) => {
+        match $size {
+            $($s => Some(measure::<$n, $es, $s>($cfg)),)*
+            _ => None,
+        }
+    };
+}
+
+pub const MIN_SIZE: u16 = 4;
+pub const MAX_SIZE: u16 = 64;
+
+// None for formats without an exact quire; sizes narrower than the posit fraction
+// or outside MIN_SIZE..=MAX_SIZE are skipped
+pub fn sweep_format(n: u8, es: u8, cfg: &SweepConfig, sizes: &[u16]) -> Option<Vec<SizeReport>> {
+    let measure_one = |size: u16| match (n, es) {
+        (8, 0) => measure_size!(8, 0, size, cfg, 8 9 10 11 12 13 14 15),
+        (8, 1) => measure_size!(8, 1, size, cfg, 7 8 9 10 11 12 13 14 15),
+        (8, 2) => measure_size!(8, 2, size, cfg, 6 7 8 9 10 11 12 13 14 15),
+        (10, 1) => measure_size!(10, 1, size, cfg, 9 10 11 12 13 14 15),
+        (12, 1) => measure_size!(12, 1, size, cfg, 11 12 13 14 15),
+        (16, 1) => measure_size!(16, 1, size, cfg, 15),
+        _ => None,
+    };
+
+    if !matches!((n, es), (8, 0) | (8, 1) | (8, 2) | (10, 1) | (12, 1) | (16, 1)) {
+        return None;
+    }
+
+    Some(sizes.iter().filter_map(|&size| measure_one(size)).collect())
+}
+
+pub fn sweep<const N: u8, const ES: u8>(cfg: &SweepConfig, sizes: &[u16]) -> Vec<SizeReport> {
+    sweep_format(N, ES, cfg, sizes).unwrap_or_default()
+}
+
+// smallest size from which every wider swept size also meets the target
+pub fn recommend(reports: &[SizeReport], target_rate: f64) -> Option<u16> {
+    let mut sorted: Vec<&SizeReport> = reports.iter().collect();
+    sorted.sort_by_key(|r| r.size);
+
+    sorted.iter().rev()
+        .take_while(|r| r.mismatch_rate() <= target_rate)
+        .map(|r| r.size)
+        .last()
+}
@@ -0,0 +1,347 @@
+use crate::posit::Posit;
+use crate::exact::ExactSum;
+use crate::gemm::fused_dot;
+use crate::krylov::LinearOperator;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2};
+use ndarray::parallel::prelude::*;
+use num_traits::identities::{One, Zero};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SparseError {
+    // index arrays disagree with each other or with the matrix shape
+    Shape,
+    // entry k has a row or column index outside the matrix
+    OutOfBounds(usize),
+    // pointer array does not start at zero, decreases or misses nnz
+    Indptr,
+    // columns of the matrix, and rows of the operand it multiplies
+    Dimensions(usize, usize),
+}
+
+impl fmt::Display for SparseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SparseError::Shape => write!(f, "index arrays do not match"),
+            SparseError::OutOfBounds(k) => write!(f, "entry {} is out of bounds", k),
+            SparseError::Indptr => write!(f, "invalid index pointer array"),
+            SparseError::Dimensions(n, m) => write!(f, "matrix with {} columns applied to an operand with {} rows", n, m),
+        }
+    }
+}
+
+impl std::error::Error for SparseError {}
+
+// rows per parallel task in the kernels
+const MIN_ROWS: usize = 64;
+
+// entries in any order, duplicates are summed on conversion
+#[derive(Clone, Debug, PartialEq)]
+pub struct CooMatrix<const N: u8, const ES: u8> {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub rows: Vec<usize>,
+    pub cols: Vec<usize>,
+    pub values: Vec<Posit<N, ES>>,
+}
+
+// compressed rows, the entries of row i are indptr[i]..indptr[i + 1]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsrMatrix<const N: u8, const ES: u8> {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub indptr: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub values: Vec<Posit<N, ES>>,
+}
+
+// compressed columns, the entries of column j are indptr[j]..indptr[j + 1]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CscMatrix<const N: u8, const ES: u8> {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub indptr: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub values: Vec<Posit<N, ES>>,
+}
+
+fn check_compressed(major: usize, minor: usize, indptr: &[usize], indices: &[usize], nnz: usize) -> Result<(), SparseError> {
+    if indices.len() != nnz || indptr.len() != major + 1 {
+        return Err(SparseError::Shape);
+    }
+    if indptr[0] != 0 || indptr[major] != nnz || indptr.windows(2).any(|w| w[0] > w[1]) {
+        return Err(SparseError::Indptr);
+    }
+
+    match indices.iter().position(|&i| i >= minor) {
+        Some(k) => Err(SparseError::OutOfBounds(k)),
+        None => Ok(()),
+    }
+}
+
+// groups (major, minor, value) entries by major index, sorted by minor and with duplicates summed exactly
+fn compress<const N: u8, const ES: u8>(major: usize, mut entries: Vec<(usize, usize, Posit<N, ES>)>) -> (Vec<usize>, Vec<usize>, Vec<Posit<N, ES>>) {
+    entries.sort_by_key(|&(i, j, _)| (i, j));
+
+    let mut indptr = vec![0; major + 1];
+    let mut indices = Vec::with_capacity(entries.len());
+    let mut values = Vec::with_capacity(entries.len());
+
+    let mut start = 0;
+    while start < entries.len() {
+        let (i, j, v) = entries[start];
+        let end = start + entries[start..].iter().take_while(|e| (e.0, e.1) == (i, j)).count();
+
+        indptr[i + 1] += 1;
+        indices.push(j);
+        values.push(if end - start == 1 {v} else {fused_dot(Posit::zero(), entries[start..end].iter().map(|&(_, _, v)| (v, Posit::one())))});
+        start = end;
+    }
+
+    for i in 0..major {
+        indptr[i + 1] += indptr[i];
+    }
+
+    (indptr, indices, values)
+}
+
+fn expand(indptr: &[usize]) -> Vec<usize> {
+    indptr.windows(2).enumerate().flat_map(|(i, w)| (w[0]..w[1]).map(move |_| i)).collect()
+}
+
+impl<const N: u8, const ES: u8> CooMatrix<N, ES> {
+    pub fn new(nrows: usize, ncols: usize) -> Self {
+        Self{nrows, ncols, rows: Vec::new(), cols: Vec::new(), values: Vec::new()}
+    }
+
+    pub fn from_triplets(nrows: usize, ncols: usize, rows: Vec<usize>, cols: Vec<usize>, values: Vec<Posit<N, ES>>) -> Result<Self, SparseError> {
+        if rows.len() != values.len() || cols.len() != values.len() {
+            return Err(SparseError::Shape);
+        }
+
+        match rows.iter().zip(&cols).position(|(&i, &j)| i >= nrows || j >= ncols) {
+            Some(k) => Err(SparseError::OutOfBounds(k)),
+            None => Ok(Self{nrows, ncols, rows, cols, values}),
+        }
+    }
+
+    // keeps the nonzero entries of a
+    pub fn from_dense(a: ArrayView2<Posit<N, ES>>) -> Self {
+        let mut coo = Self::new(a.nrows(), a.ncols());
+
+        for ((i, j), &v) in a.indexed_iter().filter(|(_, v)| !v.is_zero()) {
+            coo.rows.push(i);
+            coo.cols.push(j);
+            coo.values.push(v);
+        }
+
+        coo
+    }
+
+    pub fn push(&mut self, i: usize, j: usize, v: Posit<N, ES>) -> Result<(), SparseError> {
+        if i >= self.nrows || j >= self.ncols {
+            return Err(SparseError::OutOfBounds(self.values.len()));
+        }
+
+        self.rows.push(i);
+        self.cols.push(j);
+        self.values.push(v);
+        Ok(())
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    fn triplets(&self) -> impl Iterator<Item = (usize, usize, Posit<N, ES>)> + '_ {
+        self.rows.iter().zip(&self.cols).zip(&self.values).map(|((&i, &j), &v)| (i, j, v))
+    }
+
+    pub fn to_csr(&self) -> CsrMatrix<N, ES> {
+        let (indptr, indices, values) = compress(self.nrows, self.triplets().collect());
+        CsrMatrix{nrows: self.nrows, ncols: self.ncols, indptr, indices, values}
+    }
+
+    pub fn to_csc(&self) -> CscMatrix<N, ES> {
+        let (indptr, indices, values) = compress(self.ncols, self.triplets().map(|(i, j, v)| (j, i, v)).collect());
+        CscMatrix{nrows: self.nrows, ncols: self.ncols, indptr, indices, values}
+    }
+
+    pub fn to_dense(&self) -> Array2<Posit<N, ES>> {
+        self.to_csr().to_dense()
+    }
+}
+
+impl<const N: u8, const ES: u8> CsrMatrix<N, ES> {
+    pub fn new(nrows: usize, ncols: usize, indptr: Vec<usize>, indices: Vec<usize>, values: Vec<Posit<N, ES>>) -> Result<Self, SparseError> {
+        check_compressed(nrows, ncols, &indptr, &indices, values.len())?;
+        Ok(Self{nrows, ncols, indptr, indices, values})
+    }
+
+    pub fn from_dense(a: ArrayView2<Posit<N, ES>>) -> Self {
+        CooMatrix::from_dense(a).to_csr()
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn to_coo(&self) -> CooMatrix<N, ES> {
+        CooMatrix{nrows: self.nrows, ncols: self.ncols, rows: expand(&self.indptr), cols: self.indices.clone(), values: self.values.clone()}
+    }
+
+    pub fn to_csc(&self) -> CscMatrix<N, ES> {
+        self.to_coo().to_csc()
+    }
+
+    // duplicate entries are summed with a single rounding
+    pub fn to_dense(&self) -> Array2<Posit<N, ES>> {
+        self.to_coo().to_csr().scatter()
+    }
+
+    fn scatter(&self) -> Array2<Posit<N, ES>> {
+        let mut a = Array2::zeros((self.nrows, self.ncols));
+
+        for i in 0..self.nrows {
+            for k in self.indptr[i]..self.indptr[i + 1] {
+                a[[i, self.indices[k]]] = self.values[k];
+            }
+        }
+
+        a
+    }
+
+    // the transpose shares the arrays, read as compressed columns
+    pub fn transpose(self) -> CscMatrix<N, ES> {
+        CscMatrix{nrows: self.ncols, ncols: self.nrows, indptr: self.indptr, indices: self.indices, values: self.values}
+    }
+
+    fn row(&self, i: usize, x: ArrayView1<Posit<N, ES>>) -> Posit<N, ES> {
+        let mut acc = ExactSum::zero();
+        (self.indptr[i]..self.indptr[i + 1]).for_each(|k| acc.fma(self.values[k], x[self.indices[k]]));
+        acc.into()
+    }
+
+    fn product(&self, x: ArrayView1<Posit<N, ES>>) -> Array1<Posit<N, ES>> {
+        let y: Vec<_> = (0..self.nrows).into_par_iter().with_min_len(MIN_ROWS).map(|i| self.row(i, x)).collect();
+        Array1::from(y)
+    }
+
+    // y = A x, each row accumulated exactly and rounded once
+    pub fn spmv(&self, x: ArrayView1<Posit<N, ES>>) -> Result<Array1<Posit<N, ES>>, SparseError> {
+        if self.ncols != x.len() {
+            return Err(SparseError::Dimensions(self.ncols, x.len()));
+        }
+
+        Ok(self.product(x))
+    }
+
+    // C = A B, one exact accumulator per entry of C
+    pub fn spmm(&self, b: ArrayView2<Posit<N, ES>>) -> Result<Array2<Posit<N, ES>>, SparseError> {
+        if self.ncols != b.nrows() {
+            return Err(SparseError::Dimensions(self.ncols, b.nrows()));
+        }
+        let m = b.ncols();
+
+        let rows: Vec<Vec<Posit<N, ES>>> = (0..self.nrows).into_par_iter().with_min_len(MIN_ROWS).map(|i| {
+            let mut acc = vec![ExactSum::zero(); m];
+
+            for k in self.indptr[i]..self.indptr[i + 1] {
+                let (a, row) = (self.values[k], b.row(self.indices[k]));
+                acc.iter_mut().zip(row).for_each(|(c, &v)| c.fma(a, v));
+            }
+
+            acc.into_iter().map(Posit::from).collect()
+        }).collect();
+
+        Ok(Array2::from_shape_vec((self.nrows, m), rows.concat()).unwrap())
+    }
+}
+
+impl<const N: u8, const ES: u8> CscMatrix<N, ES> {
+    pub fn new(nrows: usize, ncols: usize, indptr: Vec<usize>, indices: Vec<usize>, values: Vec<Posit<N, ES>>) -> Result<Self, SparseError> {
+        check_compressed(ncols, nrows, &indptr, &indices, values.len())?;
+        Ok(Self{nrows, ncols, indptr, indices, values})
+    }
+
+    pub fn from_dense(a: ArrayView2<Posit<N, ES>>) -> Self {
+        CooMatrix::from_dense(a).to_csc()
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn to_coo(&self) -> CooMatrix<N, ES> {
+        CooMatrix{nrows: self.nrows, ncols: self.ncols, rows: self.indices.clone(), cols: expand(&self.indptr), values: self.values.clone()}
+    }
+
+    pub fn to_csr(&self) -> CsrMatrix<N, ES> {
+        self.to_coo().to_csr()
+    }
+
+    pub fn to_dense(&self) -> Array2<Posit<N, ES>> {
+        self.to_csr().to_dense()
+    }
+
+    pub fn transpose(self) -> CsrMatrix<N, ES> {
+        CsrMatrix{nrows: self.ncols, ncols: self.nrows, indptr: self.indptr, indices: self.indices, values: self.values}
+    }
+
+    // scattered by column; exact sums make the reduction order irrelevant
+    fn product(&self, x: ArrayView1<Posit<N, ES>>) -> Array1<Posit<N, ES>> {
+        let n = self.nrows;
+
+        let acc = (0..self.ncols).into_par_iter().with_min_len(MIN_ROWS)
+            .fold(|| vec![ExactSum::zero(); n], |mut acc, j| {
+                (self.indptr[j]..self.indptr[j + 1]).for_each(|k| acc[self.indices[k]].fma(self.values[k], x[j]));
+                acc
+            })
+            .reduce(|| vec![ExactSum::zero(); n], |a, b| a.into_iter().zip(b).map(|(a, b)| a + b).collect());
+
+        acc.into_iter().map(Posit::from).collect()
+    }
+
+    // y = A x, each entry accumulated exactly and rounded once
+    pub fn spmv(&self, x: ArrayView1<Posit<N, ES>>) -> Result<Array1<Posit<N, ES>>, SparseError> {
+        if self.ncols != x.len() {
+            return Err(SparseError::Dimensions(self.ncols, x.len()));
+        }
+
+        Ok(self.product(x))
+    }
+
+    pub fn spmm(&self, b: ArrayView2<Posit<N, ES>>) -> Result<Array2<Posit<N, ES>>, SparseError> {
+        self.to_csr().spmm(b)
+    }
+}
+
+impl<const N: u8, const ES: u8> LinearOperator<Posit<N, ES>> for CsrMatrix<N, ES> {
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    // the solvers check the shapes before applying
+    fn apply(&self, x: ArrayView1<Posit<N, ES>>) -> Array1<Posit<N, ES>> {
+        self.product(x)
+    }
+}
+
+impl<const N: u8, const ES: u8> LinearOperator<Posit<N, ES>> for CscMatrix<N, ES> {
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    // the solvers check the shapes before applying
+    fn apply(&self, x: ArrayView1<Posit<N, ES>>) -> Array1<Posit<N, ES>> {
+        self.product(x)
+    }
+}
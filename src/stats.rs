@@ -0,0 +1,282 @@
+use crate::posit::Posit;
+use crate::quire::Quire;
+use crate::exact::ExactSum;
+use crate::double::DoublePosit;
+use crate::gemm::fused_sum;
+use crate::window::{Wide, Window};
+use ndarray::{Array, Array2, ArrayView, ArrayView1, ArrayView2, Axis, Dimension, RemoveAxis};
+use num_traits::identities::{One, Zero};
+use std::cmp::Ordering;
+
+// Reductions along an axis of a posit array. Sums and centred sums are held
+// exactly in an ExactSum and rounded once; formats too wide for it do not compile.
+
+// what a reduction does with NaR entries
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NarPolicy {
+    // any NaR makes the lane's result NaR
+    Propagate,
+    // NaR entries are skipped; a lane with nothing left gives NaR
+    Omit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variance {
+    // exact sum of squares about the rounded mean, corrected for its rounding
+    TwoPass,
+    // one streaming pass, the running moments kept as double posits
+    Welford,
+}
+
+fn ordered<const N: u8, const ES: u8>(a: &Posit<N, ES>, b: &Posit<N, ES>) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+// the lane's entries under policy, None when a NaR propagates
+fn kept<const N: u8, const ES: u8>(x: ArrayView1<Posit<N, ES>>, policy: NarPolicy) -> Option<Vec<(usize, Posit<N, ES>)>> {
+    match policy {
+        NarPolicy::Propagate if x.iter().any(|p| p.is_nar()) => None,
+        _ => Some(x.iter().cloned().enumerate().filter(|(_, p)| !p.is_nar()).collect()),
+    }
+}
+
+fn lane_mean<const N: u8, const ES: u8>(x: ArrayView1<Posit<N, ES>>) -> Posit<N, ES> {
+    if x.is_empty() {
+        return Posit::NAR;
+    }
+
+    fused_sum(Posit::zero(), x.iter().map(|&v| (v, Posit::one()))).div_int(x.len() as i64)
+}
+
+// wide enough for n sum (x - mx)(y - my) on the square of the exact sum's scale,
+// for sums in 128 bits and in 512 bits
+type Moment = Wide<6>;
+type WideMoment = Wide<17>;
+
+const _: () = assert!(<Moment as Window>::BITS >= 2 * 128 + 64 && <WideMoment as Window>::BITS >= 2 * 512 + 64);
+
+// n sum (x - mx)(y - my) - sum (x - mx) sum (y - my), which is n times the
+// centred sum about the true means, held exactly on the square of the exact sum's scale
+fn comoment<const N: u8, const ES: u8, W: Window>(x: ArrayView1<Posit<N, ES>>, mx: Posit<N, ES>, y: ArrayView1<Posit<N, ES>>, my: Posit<N, ES>) -> Option<W> {
+    let one = Posit::one();
+    let offset = |v: ArrayView1<Posit<N, ES>>, m: Posit<N, ES>| fused_sum(Posit::zero(), v.iter().flat_map(|&v| [(v, one), (-m, one)]));
+
+    let s = fused_sum(Posit::zero(), x.iter().zip(y).flat_map(|(&a, &b)| [(a, b), (a, -my), (-mx, b), (mx, my)]));
+    let (dx, dy) = (offset(x, mx), offset(y, my));
+
+    if s.is_nar() || dx.is_nar() || dy.is_nar() {
+        return None;
+    }
+
+    let ns = s.widen::<W>().mul_sar(W::from_i128(x.len() as i128), 0).0.shl(ExactSum::<N, ES>::FRAC as u32);
+    Some(ns.wrapping_add(dx.widen::<W>().mul_sar(dy.widen(), 0).0.wrapping_neg()))
+}
+
+// a comoment over d rounded once
+fn round_moment<const N: u8, const ES: u8, W: Window>(t: W, d: usize) -> Posit<N, ES> {
+    if t.is_zero() {
+        return Posit::zero();
+    }
+
+    let a = t.abs();
+    let k = a.leading_zeros() - 2;
+    let (q, inexact) = a.div_shl(W::from_i128(d as i128), k);
+    let (top, shift) = q.to_u128_sticky();
+
+    Posit::from_scaled(t.is_negative(), top | inexact as u128, shift as i32 - k as i32 - 2 * ExactSum::<N, ES>::FRAC)
+}
+
+fn moment_f64<const N: u8, const ES: u8, W: Window>(t: W) -> f64 {
+    let (top, shift) = t.abs().to_u128_sticky();
+    let v = top as f64 * 2f64.powi(shift as i32 - 2 * ExactSum::<N, ES>::FRAC);
+
+    if t.is_negative() {-v} else {v}
+}
+
+// the comoment over d rounded once, in the window the format's exact sum needs
+fn centred_sum<const N: u8, const ES: u8>(x: ArrayView1<Posit<N, ES>>, mx: Posit<N, ES>, y: ArrayView1<Posit<N, ES>>, my: Posit<N, ES>, d: usize) -> Posit<N, ES> {
+    if Quire::<N, ES>::FITS {
+        comoment::<N, ES, Moment>(x, mx, y, my).map_or(Posit::NAR, |t| round_moment(t, d))
+    }
+    else {
+        comoment::<N, ES, WideMoment>(x, mx, y, my).map_or(Posit::NAR, |t| round_moment(t, d))
+    }
+}
+
+fn centred_f64<const N: u8, const ES: u8>(x: ArrayView1<Posit<N, ES>>, mx: Posit<N, ES>, y: ArrayView1<Posit<N, ES>>, my: Posit<N, ES>) -> Option<f64> {
+    if Quire::<N, ES>::FITS {
+        comoment::<N, ES, Moment>(x, mx, y, my).map(moment_f64::<N, ES, _>)
+    }
+    else {
+        comoment::<N, ES, WideMoment>(x, mx, y, my).map(moment_f64::<N, ES, _>)
+    }
+}
+
+fn lane_var<const N: u8, const ES: u8>(x: ArrayView1<Posit<N, ES>>, ddof: usize, method: Variance) -> Posit<N, ES> {
+    if x.len() <= ddof {
+        return Posit::NAR;
+    }
+
+    match method {
+        Variance::TwoPass => {
+            let m = lane_mean(x);
+            centred_sum(x, m, x, m, x.len() * (x.len() - ddof))
+        },
+        Variance::Welford => x.iter().fold(Welford::new(), |mut w, &v| {w.push(v); w}).variance(ddof),
+    }
+}
+
+// running count, mean and sum of squared deviations, updated one value at a
+// time; the moments carry twice the posit precision so the stream's rounding
+// stays well below that of the result
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Welford<const N: u8, const ES: u8> {
+    pub count: usize,
+    pub mean: DoublePosit<N, ES>,
+    pub m2: DoublePosit<N, ES>,
+}
+
+impl<const N: u8, const ES: u8> Welford<N, ES> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, x: Posit<N, ES>) {
+        self.count += 1;
+
+        let x = DoublePosit::from(x);
+        let delta = x - self.mean;
+        self.mean = self.mean + delta / DoublePosit::from(self.count as f64);
+        self.m2 = self.m2 + delta * (x - self.mean);
+    }
+
+    // Chan et al.'s pairwise combination, for moments gathered in parallel
+    pub fn merge(self, other: Self) -> Self {
+        if self.count == 0 || other.count == 0 {
+            return if self.count == 0 {other} else {self};
+        }
+
+        let count = self.count + other.count;
+        let (na, nb, n) = (self.count as f64, other.count as f64, count as f64);
+        let delta = other.mean - self.mean;
+
+        Self{
+            count,
+            mean: self.mean + delta * DoublePosit::from(nb) / DoublePosit::from(n),
+            m2: self.m2 + other.m2 + delta * delta * DoublePosit::from(na) * DoublePosit::from(nb) / DoublePosit::from(n),
+        }
+    }
+
+    pub fn mean(self) -> Posit<N, ES> {
+        if self.count == 0 {Posit::NAR} else {self.mean.hi}
+    }
+
+    // m2 / (count - ddof), NaR without enough values
+    pub fn variance(self, ddof: usize) -> Posit<N, ES> {
+        if self.count <= ddof {Posit::NAR} else {(self.m2 / DoublePosit::from((self.count - ddof) as f64)).hi}
+    }
+}
+
+// exact sum along axis, rounded once
+pub fn sum<const N: u8, const ES: u8, D: Dimension + RemoveAxis>(x: ArrayView<Posit<N, ES>, D>, axis: Axis) -> Array<Posit<N, ES>, D::Smaller> {
+    x.map_axis(axis, |v| fused_sum(Posit::zero(), v.iter().map(|&v| (v, Posit::one()))).into())
+}
+
+// exact sum divided by the count, rounded once; NaR for an empty axis
+pub fn mean<const N: u8, const ES: u8, D: Dimension + RemoveAxis>(x: ArrayView<Posit<N, ES>, D>, axis: Axis) -> Array<Posit<N, ES>, D::Smaller> {
+    x.map_axis(axis, lane_mean)
+}
+
+// sum of squared deviations over (count - ddof)
+pub fn var<const N: u8, const ES: u8, D: Dimension + RemoveAxis>(x: ArrayView<Posit<N, ES>, D>, axis: Axis, ddof: usize, method: Variance) -> Array<Posit<N, ES>, D::Smaller> {
+    x.map_axis(axis, |v| lane_var(v, ddof, method))
+}
+
+// f of every pair of rows i <= j and their means, mirrored below the diagonal;
+// the rows of x are variables and its columns observations
+fn centred<const N: u8, const ES: u8, T: Copy + Default, F>(x: ArrayView2<Posit<N, ES>>, f: F) -> Array2<T>
+    where F: Fn(ArrayView1<Posit<N, ES>>, Posit<N, ES>, ArrayView1<Posit<N, ES>>, Posit<N, ES>) -> T
+{
+    let means: Vec<_> = x.rows().into_iter().map(lane_mean).collect();
+
+    let s = Array2::from_shape_fn((x.nrows(), x.nrows()), |(i, j)| {
+        if i <= j {f(x.row(i), means[i], x.row(j), means[j])} else {T::default()}
+    });
+    Array2::from_shape_fn(s.raw_dim(), |(i, j)| s[(i.min(j), i.max(j))])
+}
+
+// covariance matrix of the rows of x, each entry an exact centred sum rounded once
+pub fn cov<const N: u8, const ES: u8>(x: ArrayView2<Posit<N, ES>>, ddof: usize) -> Array2<Posit<N, ES>> {
+    let n = x.ncols();
+    centred(x, |a, ma, b, mb| if n > ddof {centred_sum(a, ma, b, mb, n * (n - ddof))} else {Posit::NAR})
+}
+
+// Pearson correlation of the rows of x from the exact centred sums, NaR for a constant row
+pub fn corrcoef<const N: u8, const ES: u8>(x: ArrayView2<Posit<N, ES>>) -> Array2<Posit<N, ES>> {
+    let s = centred(x, centred_f64);
+    let at = |i: usize, j: usize| s[(i, j)];
+
+    Array2::from_shape_fn(s.raw_dim(), |(i, j)| {
+        match (at(i, j), at(i, i), at(j, j)) {
+            (Some(sij), Some(sii), Some(sjj)) if sii > 0.0 && sjj > 0.0 => {
+                if i == j {Posit::one()} else {Posit::from((sij / (sii * sjj).sqrt()).clamp(-1.0, 1.0))}
+            },
+            _ => Posit::NAR,
+        }
+    })
+}
+
+// index of the first extreme entry in the lane; NaR wins under Propagate
+fn extreme<const N: u8, const ES: u8>(x: ArrayView1<Posit<N, ES>>, policy: NarPolicy, want: Ordering) -> Option<usize> {
+    if policy == NarPolicy::Propagate {
+        if let Some(k) = x.iter().position(|p| p.is_nar()) {
+            return Some(k);
+        }
+    }
+
+    kept(x, NarPolicy::Omit)?.into_iter()
+        .fold(None, |best: Option<(usize, Posit<N, ES>)>, (k, p)| match best {
+            Some((_, b)) if ordered(&p, &b) != want => best,
+            _ => Some((k, p)),
+        })
+        .map(|(k, _)| k)
+}
+
+pub fn argmin<const N: u8, const ES: u8, D: Dimension + RemoveAxis>(x: ArrayView<Posit<N, ES>, D>, axis: Axis, policy: NarPolicy) -> Array<Option<usize>, D::Smaller> {
+    x.map_axis(axis, |v| extreme(v, policy, Ordering::Less))
+}
+
+pub fn argmax<const N: u8, const ES: u8, D: Dimension + RemoveAxis>(x: ArrayView<Posit<N, ES>, D>, axis: Axis, policy: NarPolicy) -> Array<Option<usize>, D::Smaller> {
+    x.map_axis(axis, |v| extreme(v, policy, Ordering::Greater))
+}
+
+// NaR for an empty lane, or one with nothing left under Omit
+pub fn min<const N: u8, const ES: u8, D: Dimension + RemoveAxis>(x: ArrayView<Posit<N, ES>, D>, axis: Axis, policy: NarPolicy) -> Array<Posit<N, ES>, D::Smaller> {
+    x.map_axis(axis, |v| extreme(v, policy, Ordering::Less).map_or(Posit::NAR, |k| v[k]))
+}
+
+pub fn max<const N: u8, const ES: u8, D: Dimension + RemoveAxis>(x: ArrayView<Posit<N, ES>, D>, axis: Axis, policy: NarPolicy) -> Array<Posit<N, ES>, D::Smaller> {
+    x.map_axis(axis, |v| extreme(v, policy, Ordering::Greater).map_or(Posit::NAR, |k| v[k]))
+}
+
+// the q-th quantile, interpolating linearly between order statistics as numpy
+// does by default; the interpolation is evaluated in f64 and rounded once
+pub fn quantile<const N: u8, const ES: u8, D: Dimension + RemoveAxis>(x: ArrayView<Posit<N, ES>, D>, axis: Axis, q: f64, policy: NarPolicy) -> Array<Posit<N, ES>, D::Smaller> {
+    assert!((0.0..=1.0).contains(&q), "quantile: q must lie in [0, 1]");
+
+    x.map_axis(axis, |v| {
+        let mut sorted: Vec<Posit<N, ES>> = match kept(v, policy) {
+            Some(k) if !k.is_empty() => k.into_iter().map(|(_, p)| p).collect(),
+            _ => return Posit::NAR,
+        };
+        sorted.sort_by(ordered);
+
+        let h = (sorted.len() - 1) as f64 * q;
+        let (lo, hi) = (f64::from(sorted[h.floor() as usize]), f64::from(sorted[h.ceil() as usize]));
+        Posit::from(lo + (h - h.floor()) * (hi - lo))
+    })
+}
+
+pub fn median<const N: u8, const ES: u8, D: Dimension + RemoveAxis>(x: ArrayView<Posit<N, ES>, D>, axis: Axis, policy: NarPolicy) -> Array<Posit<N, ES>, D::Smaller> {
+    quantile(x, axis, 0.5, policy)
+}
@@ -0,0 +1,262 @@
+use crate::posit::Posit;
+use crate::exact::ExactSum;
+use crate::gemm::{self, Trans};
+use ndarray::{Array3, ArrayD, ArrayView2, ArrayViewD, Axis, Dimension, IxDyn};
+use num_traits::identities::{One, Zero};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TensorError {
+    // tensordot axes lists of different lengths, or an axis past the operand's
+    Axes,
+    // subscripts with characters other than letters, commas and "->"
+    Subscripts(String),
+    // more than two operands, or none
+    Operands(usize),
+    // subscript lists for the operands, and operands given
+    LabelCount(usize, usize),
+    // axes of an operand, and subscripts given for it
+    Rank(usize, usize),
+    // a label used for axes of different sizes
+    AxisSize,
+    // output labels repeated or missing from every input
+    Output,
+    // axes of a matmul_batched operand, fewer than two
+    Matrix(usize),
+    // batch axes that do not broadcast
+    Broadcast,
+    // inner dimensions of the two matrices
+    Inner(usize, usize),
+}
+
+impl fmt::Display for TensorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TensorError::Axes => write!(f, "tensordot: axes lists differ in length or are out of range"),
+            TensorError::Subscripts(s) => write!(f, "einsum: invalid subscripts `{}`", s),
+            TensorError::Operands(n) => write!(f, "einsum: only one or two operands can be contracted with a single rounding, got {}", n),
+            TensorError::LabelCount(l, n) => write!(f, "einsum: {} subscripts for {} operands", l, n),
+            TensorError::Rank(d, l) => write!(f, "einsum: operand with {} axes given {} subscripts", d, l),
+            TensorError::AxisSize => write!(f, "einsum: inconsistent axis sizes"),
+            TensorError::Output => write!(f, "einsum: output subscripts must be unique and appear in an input"),
+            TensorError::Matrix(d) => write!(f, "matmul_batched: operands need at least two axes, got {}", d),
+            TensorError::Broadcast => write!(f, "matmul_batched: batch axes do not broadcast"),
+            TensorError::Inner(k, k2) => write!(f, "matmul_batched: inner dimensions differ, {} and {}", k, k2),
+        }
+    }
+}
+
+impl std::error::Error for TensorError {}
+
+// numpy style batched matmul: (..., m, k) x (..., k, n) with broadcast batch axes
+pub fn matmul_batched<const N: u8, const ES: u8>(a: ArrayViewD<Posit<N, ES>>, b: ArrayViewD<Posit<N, ES>>) -> Result<ArrayD<Posit<N, ES>>, TensorError> {
+    if a.ndim().min(b.ndim()) < 2 {
+        return Err(TensorError::Matrix(a.ndim().min(b.ndim())));
+    }
+
+    let (ba, bb) = (&a.shape()[..a.ndim() - 2], &b.shape()[..b.ndim() - 2]);
+    let batch = broadcast_shape(ba, bb).ok_or(TensorError::Broadcast)?;
+
+    let (m, k) = (a.shape()[a.ndim() - 2], a.shape()[a.ndim() - 1]);
+    let (k2, n) = (b.shape()[b.ndim() - 2], b.shape()[b.ndim() - 1]);
+    if k != k2 {
+        return Err(TensorError::Inner(k, k2));
+    }
+
+    let mut shape = batch.clone();
+    shape.extend([m, n]);
+    let mut out = ArrayD::<Posit<N, ES>>::zeros(IxDyn(&shape));
+
+    for idx in ndarray::indices(IxDyn(&batch)) {
+        let c = gemm::gemm(Trans::No, pick(a.view(), &idx), Trans::No, pick(b.view(), &idx));
+
+        let mut o = out.view_mut();
+        for ax in 0..batch.len() {
+            o.index_axis_inplace(Axis(0), idx[ax]);
+        }
+        o.assign(&c);
+    }
+
+    Ok(out)
+}
+
+// the matrix of x at a broadcast batch index
+fn pick<'a, T>(mut x: ArrayViewD<'a, T>, idx: &IxDyn) -> ArrayView2<'a, T> {
+    let offset = idx.ndim() + 2 - x.ndim();
+
+    for ax in 0..x.ndim() - 2 {
+        let i = if x.shape()[0] == 1 {0} else {idx[offset + ax]};
+        x.index_axis_inplace(Axis(0), i);
+    }
+
+    x.into_dimensionality().unwrap()
+}
+
+fn broadcast_shape(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let n = a.len().max(b.len());
+    let at = |s: &[usize], i: usize| if i + s.len() >= n {s[i + s.len() - n]} else {1};
+
+    (0..n).map(|i| match (at(a, i), at(b, i)) {
+        (x, y) if x == y || y == 1 => Some(x),
+        (1, y) => Some(y),
+        _ => None,
+    }).collect()
+}
+
+// contract axes_a of a with axes_b of b; the result has a's free axes then b's
+pub fn tensordot<const N: u8, const ES: u8>(a: ArrayViewD<Posit<N, ES>>, b: ArrayViewD<Posit<N, ES>>, axes_a: &[usize], axes_b: &[usize]) -> Result<ArrayD<Posit<N, ES>>, TensorError> {
+    if axes_a.len() != axes_b.len() {
+        return Err(TensorError::Axes);
+    }
+
+    let la: Vec<usize> = (0..a.ndim()).collect();
+    let mut lb: Vec<usize> = (a.ndim()..a.ndim() + b.ndim()).collect();
+
+    for (&i, &j) in axes_a.iter().zip(axes_b) {
+        if i >= a.ndim() || j >= b.ndim() {
+            return Err(TensorError::Axes);
+        }
+        lb[j] = la[i];
+    }
+
+    let out: Vec<usize> = la.iter().enumerate().filter(|(i, _)| !axes_a.contains(i)).map(|(_, &l)| l)
+        .chain(lb.iter().enumerate().filter(|(j, _)| !axes_b.contains(j)).map(|(_, &l)| l))
+        .collect();
+
+    contract(&[a.view(), b.view()], &[la, lb], &out)
+}
+
+// "ij,jk->ik"; without "->" the output is every label used once, sorted
+pub fn einsum<const N: u8, const ES: u8>(spec: &str, operands: &[ArrayViewD<Posit<N, ES>>]) -> Result<ArrayD<Posit<N, ES>>, TensorError> {
+    let spec: String = spec.chars().filter(|c| !c.is_whitespace()).collect();
+    let (inputs, output) = match spec.split_once("->") {
+        Some((i, o)) => (i, Some(o)),
+        None => (spec.as_str(), None),
+    };
+
+    let labels: Vec<Vec<usize>> = inputs.split(',').map(|s| s.chars().map(|c| c as usize).collect()).collect();
+
+    if inputs.chars().chain(output.unwrap_or("").chars()).any(|c| c != ',' && !c.is_ascii_alphabetic()) {
+        return Err(TensorError::Subscripts(spec));
+    }
+
+    let out: Vec<usize> = match output {
+        Some(o) => o.chars().map(|c| c as usize).collect(),
+        None => {
+            let all: Vec<usize> = labels.concat();
+            let mut once: Vec<usize> = all.iter().copied().filter(|l| all.iter().filter(|&m| m == l).count() == 1).collect();
+            once.sort_unstable();
+            once
+        },
+    };
+
+    contract(operands, &labels, &out)
+}
+
+fn contract<const N: u8, const ES: u8>(operands: &[ArrayViewD<Posit<N, ES>>], labels: &[Vec<usize>], out: &[usize]) -> Result<ArrayD<Posit<N, ES>>, TensorError> {
+    if operands.is_empty() || operands.len() > 2 {
+        return Err(TensorError::Operands(operands.len()));
+    }
+
+    if operands.len() != labels.len() {
+        return Err(TensorError::LabelCount(labels.len(), operands.len()));
+    }
+
+    // size of every label, checked across operands
+    let mut sizes: Vec<(usize, usize)> = Vec::new();
+    for (x, l) in operands.iter().zip(labels) {
+        if x.ndim() != l.len() {
+            return Err(TensorError::Rank(x.ndim(), l.len()));
+        }
+        for (&label, &d) in l.iter().zip(x.shape()) {
+            match sizes.iter().find(|(m, _)| *m == label) {
+                Some(&(_, e)) if e != d => return Err(TensorError::AxisSize),
+                Some(_) => (),
+                None => sizes.push((label, d)),
+            }
+        }
+    }
+
+    for (i, l) in out.iter().enumerate() {
+        if out[..i].contains(l) || !sizes.iter().any(|(m, _)| m == l) {
+            return Err(TensorError::Output);
+        }
+    }
+
+    let size = |l: usize| sizes.iter().find(|(m, _)| *m == l).unwrap().1;
+    let out_shape: Vec<usize> = out.iter().map(|&l| size(l)).collect();
+
+    if operands.len() == 2 {
+        if let Some(r) = contract_gemm(operands[0].view(), &labels[0], operands[1].view(), &labels[1], out, size) {
+            return Ok(r);
+        }
+    }
+
+    // everything else sums directly over the reduced labels
+    let reduced: Vec<usize> = sizes.iter().map(|&(l, _)| l).filter(|l| !out.contains(l)).collect();
+    let reduced_shape: Vec<usize> = reduced.iter().map(|&l| size(l)).collect();
+
+    let value = |l: usize, o: &IxDyn, r: &IxDyn| match out.iter().position(|&m| m == l) {
+        Some(i) => o[i],
+        None => r[reduced.iter().position(|&m| m == l).unwrap()],
+    };
+
+    let result = ArrayD::from_shape_fn(IxDyn(&out_shape), |o| {
+        let mut acc = ExactSum::zero();
+
+        for r in ndarray::indices(IxDyn(&reduced_shape)) {
+            let at = |k: usize| {
+                let idx: Vec<usize> = labels[k].iter().map(|&l| value(l, &o, &r)).collect();
+                operands[k][IxDyn(&idx)]
+            };
+
+            let x = at(0);
+            let (p, q) = if operands.len() == 2 {(x, at(1))} else {(x, Posit::one())};
+
+            acc.fma(p, q);
+        }
+
+        acc.into()
+    });
+
+    Ok(result)
+}
+
+// batch, free and contracted labels map onto one gemm per batch entry
+fn contract_gemm<const N: u8, const ES: u8, F: Fn(usize) -> usize>(a: ArrayViewD<Posit<N, ES>>, la: &[usize], b: ArrayViewD<Posit<N, ES>>, lb: &[usize], out: &[usize], size: F) -> Option<ArrayD<Posit<N, ES>>> {
+    let unique = |l: &[usize]| l.iter().enumerate().all(|(i, x)| !l[..i].contains(x));
+    if !unique(la) || !unique(lb) {
+        return None;
+    }
+
+    // a label summed on one side only would need its own reduction first
+    if la.iter().chain(lb).any(|l| !out.contains(l) && la.contains(l) != lb.contains(l)) {
+        return None;
+    }
+
+    let batch: Vec<usize> = out.iter().copied().filter(|l| la.contains(l) && lb.contains(l)).collect();
+    let free_a: Vec<usize> = out.iter().copied().filter(|l| la.contains(l) && !lb.contains(l)).collect();
+    let free_b: Vec<usize> = out.iter().copied().filter(|l| lb.contains(l) && !la.contains(l)).collect();
+    let inner: Vec<usize> = la.iter().copied().filter(|l| !out.contains(l)).collect();
+
+    let product_of = |ls: &[usize]| ls.iter().map(|&l| size(l)).product::<usize>();
+    let (nb, m, n, k) = (product_of(&batch), product_of(&free_a), product_of(&free_b), product_of(&inner));
+
+    let axes = |from: &[usize], order: Vec<&[usize]>| -> Vec<usize> {
+        order.concat().iter().map(|l| from.iter().position(|m| m == l).unwrap()).collect()
+    };
+
+    let a3 = a.permuted_axes(axes(la, vec![&batch, &free_a, &inner])).as_standard_layout().into_owned().into_shape((nb, m, k)).unwrap();
+    let b3 = b.permuted_axes(axes(lb, vec![&batch, &inner, &free_b])).as_standard_layout().into_owned().into_shape((nb, k, n)).unwrap();
+
+    let mut c3 = Array3::<Posit<N, ES>>::zeros((nb, m, n));
+    for t in 0..nb {
+        c3.index_axis_mut(Axis(0), t).assign(&gemm::gemm(Trans::No, a3.index_axis(Axis(0), t), Trans::No, b3.index_axis(Axis(0), t)));
+    }
+
+    let order: Vec<usize> = [batch, free_a, free_b].concat();
+    let shape: Vec<usize> = order.iter().map(|&l| size(l)).collect();
+    let c = c3.into_shape(IxDyn(&shape)).unwrap();
+
+    Some(c.permuted_axes(axes(&order, vec![out])).as_standard_layout().into_owned())
+}
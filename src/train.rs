@@ -0,0 +1,331 @@
+use crate::posit::Posit;
+use crate::gemm::{fused_dot, gemm, Trans};
+use crate::transformer::erfc;
+use crate::rng::XorShift;
+use ndarray::{Array, Array1, Array2, ArrayD, ArrayView, ArrayView1, ArrayView2, ArrayViewD, ArrayViewMutD, Axis, Dimension, Zip};
+use num_traits::identities::{One, Zero};
+use std::f64::consts::{FRAC_2_SQRT_PI, SQRT_2};
+
+// C = A B: dA = dC B^T and dB = A^T dC, every element accumulated exactly and rounded once
+pub fn matmul_backward<const N: u8, const ES: u8>(a: ArrayView2<Posit<N, ES>>, b: ArrayView2<Posit<N, ES>>, dc: ArrayView2<Posit<N, ES>>) -> (Array2<Posit<N, ES>>, Array2<Posit<N, ES>>) {
+    (gemm(Trans::No, dc, Trans::Yes, b), gemm(Trans::Yes, a, Trans::No, dc))
+}
+
+// y = x w + b with x (batch, in), w (in, out); each output, bias included, rounded once
+pub fn linear<const N: u8, const ES: u8>(x: ArrayView2<Posit<N, ES>>, w: ArrayView2<Posit<N, ES>>, b: ArrayView1<Posit<N, ES>>) -> Array2<Posit<N, ES>> {
+    assert!(x.ncols() == w.nrows() && w.ncols() == b.len(), "linear: shapes do not match");
+
+    let mut y = Array2::zeros((x.nrows(), w.ncols()));
+    Zip::indexed(&mut y).par_for_each(|(i, j), y| {
+        *y = fused_dot(b[j], x.row(i).iter().cloned().zip(w.column(j).iter().cloned()));
+    });
+
+    y
+}
+
+// (dx, dw, db) of linear given dy, with db the exact column sums of dy
+pub fn linear_backward<const N: u8, const ES: u8>(x: ArrayView2<Posit<N, ES>>, w: ArrayView2<Posit<N, ES>>, dy: ArrayView2<Posit<N, ES>>) -> (Array2<Posit<N, ES>>, Array2<Posit<N, ES>>, Array1<Posit<N, ES>>) {
+    let (dx, dw) = matmul_backward(x, w, dy);
+    let db = dy.columns().into_iter().map(|c| fused_dot(Posit::zero(), c.iter().map(|&g| (g, Posit::one())))).collect();
+
+    (dx, dw, db)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activation {
+    Identity,
+    Relu,
+    Sigmoid,
+    Tanh,
+    // x Phi(x) with the exact error function
+    Gelu,
+}
+
+impl Activation {
+    fn eval(self, x: f64) -> f64 {
+        match self {
+            Activation::Identity => x,
+            Activation::Relu => x.max(0.0),
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Activation::Tanh => x.tanh(),
+            Activation::Gelu => 0.5 * x * erfc(-x / SQRT_2),
+        }
+    }
+
+    fn derivative(self, x: f64) -> f64 {
+        match self {
+            Activation::Identity => 1.0,
+            Activation::Relu => if x > 0.0 {1.0} else {0.0},
+            Activation::Sigmoid => {
+                let s = self.eval(x);
+                s * (1.0 - s)
+            },
+            Activation::Tanh => 1.0 - x.tanh().powi(2),
+            Activation::Gelu => 0.5 * erfc(-x / SQRT_2) + x * FRAC_2_SQRT_PI / SQRT_2 / 2.0 * (-0.5 * x * x).exp(),
+        }
+    }
+
+    // evaluated in f64 and rounded once
+    pub fn forward<const N: u8, const ES: u8, D: Dimension>(self, x: ArrayView<Posit<N, ES>, D>) -> Array<Posit<N, ES>, D> {
+        x.mapv(|p| if p.is_nar() {p} else {Posit::from(self.eval(f64::from(p)))})
+    }
+
+    // dy f'(x), rounded once
+    pub fn backward<const N: u8, const ES: u8, D: Dimension>(self, x: ArrayView<Posit<N, ES>, D>, dy: ArrayView<Posit<N, ES>, D>) -> Array<Posit<N, ES>, D> {
+        Zip::from(&x).and(&dy).map_collect(|&p, &g| {
+            if p.is_nar() || g.is_nar() {Posit::NAR} else {Posit::from(f64::from(g) * self.derivative(f64::from(p)))}
+        })
+    }
+}
+
+// mean cross entropy of softmax(logits) against class labels, and its gradient
+// (softmax - onehot) / batch with each entry rounded once; a row holding NaR
+// gives a NaR gradient row and a NaN loss
+pub fn softmax_cross_entropy<const N: u8, const ES: u8>(logits: ArrayView2<Posit<N, ES>>, labels: &[usize]) -> (f64, Array2<Posit<N, ES>>) {
+    let (batch, classes) = logits.dim();
+    assert!(labels.len() == batch && labels.iter().all(|&l| l < classes), "softmax_cross_entropy: bad labels");
+
+    let mut grad = Array2::zeros((batch, classes));
+    let mut loss = 0.0;
+
+    for ((row, mut g), &label) in logits.outer_iter().zip(grad.outer_iter_mut()).zip(labels) {
+        if row.iter().any(|p| p.is_nar()) {
+            g.fill(Posit::NAR);
+            loss = f64::NAN;
+            continue;
+        }
+
+        let x: Vec<f64> = row.iter().map(|&p| f64::from(p)).collect();
+        let m = x.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let lse = m + x.iter().map(|v| (v - m).exp()).sum::<f64>().ln();
+
+        loss += lse - x[label];
+        for (k, g) in g.iter_mut().enumerate() {
+            let y = if k == label {1.0} else {0.0};
+            *g = Posit::from(((x[k] - lse).exp() - y) / batch as f64);
+        }
+    }
+
+    (loss / batch as f64, grad)
+}
+
+// the posit just below or above v, picked with probability proportional to
+// proximity, so the expected result is v; out-of-range values saturate
+pub fn stochastic_round<const N: u8, const ES: u8>(v: f64, rng: &mut XorShift) -> Posit<N, ES> {
+    if !v.is_finite() {
+        return Posit::NAR;
+    }
+
+    let near = Posit::<N, ES>::from(v);
+    let r = f64::from(near);
+    if r == v || v.abs() > f64::from(Posit::<N, ES>::MAXPOS) {
+        return near;
+    }
+
+    // neighbours are adjacent bit patterns
+    let step = |d: u32| Posit::<N, ES>(near.0.wrapping_add(d) & Posit::<N, ES>::MASK);
+    let (lo, hi) = if r < v {(near, step(1))} else {(step(u32::MAX), near)};
+    let (a, b) = (f64::from(lo), f64::from(hi));
+
+    if rng.next_f64() * (b - a) < v - a {hi} else {lo}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Nearest,
+    // seeded stream for stochastic_round
+    Stochastic(u64),
+}
+
+// writes w + delta back into the posit weight
+fn write_back<const N: u8, const ES: u8>(w: &mut Posit<N, ES>, delta: f64, rng: &mut Option<XorShift>) {
+    let v = f64::from(*w) + delta;
+    *w = match rng {
+        Some(rng) => stochastic_round(v, rng),
+        None => Posit::from(v),
+    };
+}
+
+fn rng_for(rounding: Rounding) -> Option<XorShift> {
+    match rounding {
+        Rounding::Nearest => None,
+        Rounding::Stochastic(seed) => Some(XorShift::new(seed)),
+    }
+}
+
+// optimiser state is kept in f64 per parameter slot; only the weights are posits
+pub trait Optimizer<const N: u8, const ES: u8> {
+    fn step(&mut self, params: &mut [ArrayViewMutD<Posit<N, ES>>], grads: &[ArrayViewD<Posit<N, ES>>]);
+}
+
+fn init_state<const N: u8, const ES: u8>(state: &mut Vec<ArrayD<f64>>, params: &[ArrayViewMutD<Posit<N, ES>>], grads: &[ArrayViewD<Posit<N, ES>>]) {
+    assert_eq!(params.len(), grads.len(), "optimizer: parameter and gradient counts differ");
+    assert!(params.iter().zip(grads).all(|(p, g)| p.shape() == g.shape()), "optimizer: gradient shape differs from its parameter");
+
+    if state.is_empty() {
+        *state = params.iter().map(|p| ArrayD::zeros(p.raw_dim())).collect();
+    }
+    assert!(state.len() == params.len() && state.iter().zip(params).all(|(s, p)| s.shape() == p.shape()), "optimizer: parameters changed between steps");
+}
+
+// w -= lr (v = momentum v + g)
+#[derive(Clone, Debug)]
+pub struct Sgd {
+    pub lr: f64,
+    pub momentum: f64,
+    velocity: Vec<ArrayD<f64>>,
+    rng: Option<XorShift>,
+}
+
+impl Sgd {
+    pub fn new(lr: f64, momentum: f64, rounding: Rounding) -> Self {
+        Self{lr, momentum, velocity: Vec::new(), rng: rng_for(rounding)}
+    }
+}
+
+impl<const N: u8, const ES: u8> Optimizer<N, ES> for Sgd {
+    fn step(&mut self, params: &mut [ArrayViewMutD<Posit<N, ES>>], grads: &[ArrayViewD<Posit<N, ES>>]) {
+        init_state(&mut self.velocity, params, grads);
+
+        for ((p, g), v) in params.iter_mut().zip(grads).zip(&mut self.velocity) {
+            for ((w, &g), v) in p.iter_mut().zip(g).zip(v) {
+                *v = self.momentum * *v + f64::from(g);
+                write_back(w, -self.lr * *v, &mut self.rng);
+            }
+        }
+    }
+}
+
+// Adam with bias-corrected moments
+#[derive(Clone, Debug)]
+pub struct Adam {
+    pub lr: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub eps: f64,
+    m: Vec<ArrayD<f64>>,
+    v: Vec<ArrayD<f64>>,
+    t: i32,
+    rng: Option<XorShift>,
+}
+
+impl Adam {
+    // beta1 0.9, beta2 0.999, eps 1e-8
+    pub fn new(lr: f64, rounding: Rounding) -> Self {
+        Self{lr, beta1: 0.9, beta2: 0.999, eps: 1e-8, m: Vec::new(), v: Vec::new(), t: 0, rng: rng_for(rounding)}
+    }
+}
+
+impl<const N: u8, const ES: u8> Optimizer<N, ES> for Adam {
+    fn step(&mut self, params: &mut [ArrayViewMutD<Posit<N, ES>>], grads: &[ArrayViewD<Posit<N, ES>>]) {
+        init_state(&mut self.m, params, grads);
+        init_state(&mut self.v, params, grads);
+
+        self.t += 1;
+        let (c1, c2) = (1.0 - self.beta1.powi(self.t), 1.0 - self.beta2.powi(self.t));
+
+        for (((p, g), m), v) in params.iter_mut().zip(grads).zip(&mut self.m).zip(&mut self.v) {
+            for (((w, &g), m), v) in p.iter_mut().zip(g).zip(m).zip(v) {
+                let g = f64::from(g);
+                *m = self.beta1 * *m + (1.0 - self.beta1) * g;
+                *v = self.beta2 * *v + (1.0 - self.beta2) * g * g;
+                write_back(w, -self.lr * (*m / c1) / ((*v / c2).sqrt() + self.eps), &mut self.rng);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dense<const N: u8, const ES: u8> {
+    // (in, out)
+    pub w: Array2<Posit<N, ES>>,
+    pub b: Array1<Posit<N, ES>>,
+    pub activation: Activation,
+}
+
+// (dw, db) for every layer
+pub type Gradients<const N: u8, const ES: u8> = Vec<(Array2<Posit<N, ES>>, Array1<Posit<N, ES>>)>;
+
+// (layer input, pre-activation) for every layer
+type Trace<const N: u8, const ES: u8> = Vec<(Array2<Posit<N, ES>>, Array2<Posit<N, ES>>)>;
+
+// a stack of dense layers ending in logits for softmax cross entropy
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mlp<const N: u8, const ES: u8> {
+    pub layers: Vec<Dense<N, ES>>,
+}
+
+impl<const N: u8, const ES: u8> Mlp<N, ES> {
+    // He-initialised weights for sizes [in, hidden.., out], identity on the last layer
+    pub fn new(sizes: &[usize], hidden: Activation, rng: &mut XorShift) -> Self {
+        assert!(sizes.len() >= 2, "mlp: needs at least an input and an output size");
+
+        let layers = sizes.windows(2).enumerate().map(|(k, s)| {
+            let std = (2.0 / s[0] as f64).sqrt();
+            Dense{
+                w: Array2::from_shape_fn((s[0], s[1]), |_| Posit::from(std * rng.next_normal())),
+                b: Array1::zeros(s[1]),
+                activation: if k + 2 == sizes.len() {Activation::Identity} else {hidden},
+            }
+        }).collect();
+
+        Self{layers}
+    }
+
+    // the trace of every layer and the final output
+    fn trace(&self, x: ArrayView2<Posit<N, ES>>) -> (Trace<N, ES>, Array2<Posit<N, ES>>) {
+        let mut h = x.to_owned();
+        let mut cache = Vec::with_capacity(self.layers.len());
+
+        for l in &self.layers {
+            let z = linear(h.view(), l.w.view(), l.b.view());
+            let next = l.activation.forward(z.view());
+            cache.push((h, z));
+            h = next;
+        }
+
+        (cache, h)
+    }
+
+    pub fn forward(&self, x: ArrayView2<Posit<N, ES>>) -> Array2<Posit<N, ES>> {
+        self.trace(x).1
+    }
+
+    // the mean softmax cross entropy and its gradients
+    pub fn gradients(&self, x: ArrayView2<Posit<N, ES>>, labels: &[usize]) -> (f64, Gradients<N, ES>) {
+        let (cache, logits) = self.trace(x);
+        let (loss, mut dy) = softmax_cross_entropy(logits.view(), labels);
+
+        let mut grads = Vec::with_capacity(self.layers.len());
+        for (l, (h, z)) in self.layers.iter().zip(&cache).rev() {
+            let dz = l.activation.backward(z.view(), dy.view());
+            let (dx, dw, db) = linear_backward(h.view(), l.w.view(), dz.view());
+            grads.push((dw, db));
+            dy = dx;
+        }
+        grads.reverse();
+
+        (loss, grads)
+    }
+
+    // one optimiser step on a batch, returning the loss before the update
+    pub fn train_step<O: Optimizer<N, ES>>(&mut self, x: ArrayView2<Posit<N, ES>>, labels: &[usize], opt: &mut O) -> f64 {
+        let (loss, grads) = self.gradients(x, labels);
+
+        let mut params: Vec<_> = self.layers.iter_mut().flat_map(|l| [l.w.view_mut().into_dyn(), l.b.view_mut().into_dyn()]).collect();
+        let grads: Vec<_> = grads.iter().flat_map(|(dw, db)| [dw.view().into_dyn(), db.view().into_dyn()]).collect();
+        opt.step(&mut params, &grads);
+
+        loss
+    }
+
+    // fraction of rows whose largest logit is the label
+    pub fn accuracy(&self, x: ArrayView2<Posit<N, ES>>, labels: &[usize]) -> f64 {
+        let logits = self.forward(x);
+        let hits = logits.axis_iter(Axis(0)).zip(labels).filter(|(row, &label)| {
+            row.iter().all(|&p| !p.is_nar() && p <= row[label])
+        }).count();
+
+        hits as f64 / labels.len() as f64
+    }
+}
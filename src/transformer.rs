@@ -0,0 +1,181 @@
+use crate::posit::Posit;
+use crate::exact::ExactSum;
+use crate::gemm::{self, fused_sum, Trans};
+use ndarray::{Array, Array2, ArrayView, ArrayView1, ArrayView2, Axis, Dimension, Zip};
+use num_traits::identities::{One, Zero};
+use std::f64::consts::{FRAC_2_SQRT_PI, SQRT_2};
+use std::fmt;
+
+// The kernels take activations in Posit<N, ES> and carry their intermediates
+// (exponentials, sums, scores) in a working format Posit<NA, ESA>, which must
+// hold every activation exactly.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransformerError {
+    // gamma or beta length, and the normalised axis length
+    Parameters(usize, usize),
+    // q, k and v do not agree on the head or key dimension
+    Shape,
+    // mask shape, and the (queries, keys) it must have
+    Mask((usize, usize), (usize, usize)),
+}
+
+impl fmt::Display for TransformerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransformerError::Parameters(p, n) => write!(f, "layer_norm: {} parameters for an axis of {}", p, n),
+            TransformerError::Shape => write!(f, "attention: q, k and v shapes do not match"),
+            TransformerError::Mask(m, e) => write!(f, "attention: mask is {:?}, expected {:?}", m, e),
+        }
+    }
+}
+
+impl std::error::Error for TransformerError {}
+
+// exp(scale (x - max)) over the kept entries divided by their exact sum, each
+// probability rounded once into the output format; masked entries give zero
+fn softmax_lane<const NA: u8, const ESA: u8, const NO: u8, const ESO: u8>(x: &[Option<f64>], scale: f64) -> Vec<Posit<NO, ESO>> {
+    let kept = || x.iter().flatten();
+
+    if kept().any(|v| !v.is_finite()) || kept().next().is_none() {
+        return vec![Posit::NAR; x.len()];
+    }
+
+    let m = kept().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let e: Vec<Option<ExactSum<NA, ESA>>> = x.iter().map(|v| v.map(|v| Posit::from(((v - m) * scale).exp()).into())).collect();
+    let sum = e.iter().flatten().fold(ExactSum::zero(), |s, &v| s + v);
+
+    e.iter().map(|v| v.map_or(Posit::zero(), |v| v.ratio(sum))).collect()
+}
+
+// softmax along axis
+pub fn softmax<const N: u8, const ES: u8, const NA: u8, const ESA: u8, D: Dimension>(x: ArrayView<Posit<N, ES>, D>, axis: Axis) -> Array<Posit<N, ES>, D> {
+    let mut out = Array::zeros(x.raw_dim());
+
+    Zip::from(out.lanes_mut(axis)).and(x.lanes(axis)).par_for_each(|mut o, x| {
+        let lane: Vec<Option<f64>> = x.iter().map(|&p| Some(f64::from(p))).collect();
+        o.assign(&ArrayView1::from(&softmax_lane::<NA, ESA, N, ES>(&lane, 1.0)));
+    });
+
+    out
+}
+
+// (x - mean) / sqrt(var + eps) * gamma + beta along axis; gamma and beta index the lane
+pub fn layer_norm<const N: u8, const ES: u8, const NA: u8, const ESA: u8, D: Dimension>(x: ArrayView<Posit<N, ES>, D>, axis: Axis, gamma: Option<ArrayView1<Posit<N, ES>>>, beta: Option<ArrayView1<Posit<N, ES>>>, eps: f64) -> Result<Array<Posit<N, ES>, D>, TransformerError> {
+    let len = x.len_of(axis);
+    if let Some(&p) = [gamma.map(|g| g.len()), beta.map(|b| b.len())].iter().flatten().find(|&&p| p != len) {
+        return Err(TransformerError::Parameters(p, len));
+    }
+
+    let mut out = Array::zeros(x.raw_dim());
+
+    Zip::from(out.lanes_mut(axis)).and(x.lanes(axis)).par_for_each(|mut o, x| {
+        let xa: Vec<Posit<NA, ESA>> = x.iter().map(|&p| p.into_p()).collect();
+        let n = len as i64;
+
+        // the mean rounded once, then sum (x - mean)^2 = sum x^2 - 2 mean x + mean^2 held exactly
+        let m = fused_sum(Posit::zero(), xa.iter().map(|&v| (v, Posit::one()))).div_int(n);
+        let v = fused_sum(Posit::zero(), xa.iter().flat_map(|&v| [(v, v), (v, -m), (v, -m), (m, m)])).div_int(n);
+
+        if v.is_nar() {
+            o.fill(Posit::NAR);
+            return;
+        }
+
+        let (m, rstd) = (f64::from(m), 1.0 / (f64::from(v) + eps).sqrt());
+
+        for (k, (o, &p)) in o.iter_mut().zip(x).enumerate() {
+            let g = gamma.map_or(1.0, |g| f64::from(g[k]));
+            let b = beta.map_or(0.0, |b| f64::from(b[k]));
+            *o = Posit::from((f64::from(p) - m) * rstd * g + b);
+        }
+    });
+
+    Ok(out)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gelu {
+    // x Phi(x) with the exact error function
+    Erf,
+    // 0.5 x (1 + tanh(sqrt(2 / pi) (x + 0.044715 x^3)))
+    Tanh,
+}
+
+// complementary error function, accurate to a relative 1e-15 or so
+pub(crate) fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        return 2.0 - erfc(-x);
+    }
+
+    if x < 2.5 {
+        // Maclaurin series of erf
+        let (mut term, mut sum, x2) = (x, x, x * x);
+        for k in 1..60 {
+            term *= -x2 / k as f64;
+            sum += term / (2 * k + 1) as f64;
+        }
+        1.0 - FRAC_2_SQRT_PI * sum
+    }
+    else {
+        // continued fraction x + (1/2) / (x + 1 / (x + (3/2) / ...))
+        let t = (1..60).rev().fold(x, |t, k| x + k as f64 / 2.0 / t);
+        (-x * x).exp() * FRAC_2_SQRT_PI / 2.0 / t
+    }
+}
+
+// evaluated in f64, each output rounded once
+pub fn gelu<const N: u8, const ES: u8, D: Dimension>(x: ArrayView<Posit<N, ES>, D>, approx: Gelu) -> Array<Posit<N, ES>, D> {
+    x.mapv(|p| {
+        if p.is_nar() {
+            return p;
+        }
+
+        let v = f64::from(p);
+        Posit::from(match approx {
+            Gelu::Erf => 0.5 * v * erfc(-v / SQRT_2),
+            Gelu::Tanh => 0.5 * v * (1.0 + ((2.0 / std::f64::consts::PI).sqrt() * (v + 0.044715 * v * v * v)).tanh()),
+        })
+    })
+}
+
+// mask[[i, j]] = query i may attend to key j when j <= i
+pub fn causal_mask(n: usize) -> Array2<bool> {
+    Array2::from_shape_fn((n, n), |(i, j)| j <= i)
+}
+
+// softmax(q k^T / sqrt(d)) v for one head; scores and probabilities live in the
+// working format, and each weighted sum is held exactly and rounded once to the
+// activation format
+pub fn attention<const N: u8, const ES: u8, const NA: u8, const ESA: u8>(q: ArrayView2<Posit<N, ES>>, k: ArrayView2<Posit<N, ES>>, v: ArrayView2<Posit<N, ES>>, mask: Option<ArrayView2<bool>>) -> Result<Array2<Posit<N, ES>>, TransformerError> {
+    let (lq, d) = q.dim();
+    let lk = k.nrows();
+
+    if k.ncols() != d || v.nrows() != lk {
+        return Err(TransformerError::Shape);
+    }
+
+    if let Some(m) = mask.filter(|m| m.dim() != (lq, lk)) {
+        return Err(TransformerError::Mask(m.dim(), (lq, lk)));
+    }
+
+    let wide = |a: ArrayView2<Posit<N, ES>>| a.mapv(Posit::into_p::<NA, ESA>);
+    let scores = gemm::gemm(Trans::No, wide(q).view(), Trans::Yes, wide(k).view());
+
+    let scale = 1.0 / (d as f64).sqrt();
+    let mut probs = Array2::<Posit<NA, ESA>>::zeros((lq, lk));
+
+    Zip::indexed(probs.rows_mut()).and(scores.rows()).par_for_each(|i, mut p, s| {
+        let lane: Vec<Option<f64>> = s.iter().enumerate().map(|(j, &x)| if matches!(mask, Some(m) if !m[[i, j]]) {None} else {Some(f64::from(x))}).collect();
+        p.assign(&ArrayView1::from(&softmax_lane::<NA, ESA, NA, ESA>(&lane, scale)));
+    });
+
+    let v = wide(v);
+    let mut out = Array2::<Posit<N, ES>>::zeros((lq, v.ncols()));
+
+    Zip::indexed(&mut out).par_for_each(|(i, c), o| {
+        *o = fused_sum(Posit::zero(), probs.row(i).iter().cloned().zip(v.column(c).iter().cloned())).to_posit();
+    });
+
+    Ok(out)
+}
@@ -0,0 +1,336 @@
+use crate::posit::div_wide;
+use std::fmt;
+use std::hash::Hash;
+
+// two's complement storage for the FLQuire window
+pub trait Window: Copy + Default + PartialEq + Eq + Hash + fmt::Debug {
+    const BITS: u32;
+    // widest SIZE whose products and carries still fit the storage
+    const MAX_SIZE: u32;
+
+    fn from_i128(x: i128) -> Self;
+    fn low_u128(self) -> u128;
+
+    fn is_zero(self) -> bool;
+    fn is_negative(self) -> bool;
+    fn leading_zeros(self) -> u32;
+    // any of the lowest k bits set
+    fn low_bits(self, k: u32) -> bool;
+
+    fn not(self) -> Self;
+    fn wrapping_add(self, other: Self) -> Self;
+    fn shl(self, k: u32) -> Self;
+    // arithmetic shift, shifts of BITS or more leave only the sign
+    fn sar(self, k: u32) -> Self;
+
+    // floor(self * other / 2^k) and whether any bits were shifted out
+    fn mul_sar(self, other: Self, k: u32) -> (Self, bool);
+    // floor(self * 2^k / other) on non-negative values and whether it was inexact
+    fn div_shl(self, other: Self, k: u32) -> (Self, bool);
+    // non-negative value as top * 2^shift, folding the dropped bits into the last bit of top
+    fn to_u128_sticky(self) -> (u128, u32);
+
+    #[inline]
+    fn wrapping_neg(self) -> Self {
+        self.not().wrapping_add(Self::from_i128(1))
+    }
+
+    #[inline]
+    fn abs(self) -> Self {
+        if self.is_negative() {self.wrapping_neg()} else {self}
+    }
+}
+
+impl Window for u128 {
+    const BITS: u32 = 128;
+    const MAX_SIZE: u32 = 64;
+
+    #[inline]
+    fn from_i128(x: i128) -> Self {
+        x as u128
+    }
+
+    #[inline]
+    fn low_u128(self) -> u128 {
+        self
+    }
+
+    #[inline]
+    fn is_zero(self) -> bool {
+        self == 0
+    }
+
+    #[inline]
+    fn is_negative(self) -> bool {
+        (1 << 127) & self != 0
+    }
+
+    #[inline]
+    fn leading_zeros(self) -> u32 {
+        u128::leading_zeros(self)
+    }
+
+    #[inline]
+    fn low_bits(self, k: u32) -> bool {
+        if k >= 128 {self != 0} else {self & !(!0u128 << k) != 0}
+    }
+
+    #[inline]
+    fn not(self) -> Self {
+        !self
+    }
+
+    #[inline]
+    fn wrapping_add(self, other: Self) -> Self {
+        u128::wrapping_add(self, other)
+    }
+
+    #[inline]
+    fn shl(self, k: u32) -> Self {
+        if k >= 128 {0} else {self << k}
+    }
+
+    #[inline]
+    fn sar(self, k: u32) -> Self {
+        ((self as i128) >> k.min(127)) as u128
+    }
+
+    #[inline]
+    fn mul_sar(self, other: Self, k: u32) -> (Self, bool) {
+        let prod = (self as i128).wrapping_mul(other as i128) as u128;
+
+        (prod.sar(k), prod.low_bits(k))
+    }
+
+    #[inline]
+    fn div_shl(self, other: Self, k: u32) -> (Self, bool) {
+        div_wide(self, other, k)
+    }
+
+    #[inline]
+    fn to_u128_sticky(self) -> (u128, u32) {
+        (self, 0)
+    }
+}
+
+// little-endian limbs of a 64 * L bit two's complement integer
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Wide<const L: usize>(pub [u64; L]);
+
+impl<const L: usize> Default for Wide<L> {
+    fn default() -> Self {
+        Self([0; L])
+    }
+}
+
+impl<const L: usize> fmt::Debug for Wide<L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Wide(0x")?;
+        for limb in self.0.iter().rev() {
+            write!(f, "{:016x}", limb)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl<const L: usize> Wide<L> {
+    fn bit(&self, i: u32) -> bool {
+        (self.0[(i / 64) as usize] >> (i % 64)) & 1 != 0
+    }
+
+    fn set_bit(&mut self, i: u32) {
+        self.0[(i / 64) as usize] |= 1 << (i % 64);
+    }
+
+    fn ge(&self, other: &Self) -> bool {
+        for i in (0..L).rev() {
+            if self.0[i] != other.0[i] {
+                return self.0[i] > other.0[i];
+            }
+        }
+        true
+    }
+
+    fn wrapping_sub(self, other: Self) -> Self {
+        self.wrapping_add(other.wrapping_neg())
+    }
+
+    // full unsigned product of two non-negative values, low limbs first
+    fn mul_full(self, other: Self) -> Vec<u64> {
+        let mut out = vec![0u64; 2 * L];
+
+        for i in 0..L {
+            let mut carry = 0u128;
+            for j in 0..L {
+                let t = (self.0[i] as u128) * (other.0[j] as u128) + out[i + j] as u128 + carry;
+                out[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            out[i + L] = carry as u64;
+        }
+
+        out
+    }
+}
+
+impl<const L: usize> Window for Wide<L> {
+    const BITS: u32 = 64 * L as u32;
+    const MAX_SIZE: u32 = 64 * L as u32 - 2;
+
+    fn from_i128(x: i128) -> Self {
+        let fill = if x < 0 {!0} else {0};
+        let mut out = [fill; L];
+
+        out[0] = x as u64;
+        if L > 1 {
+            out[1] = (x >> 64) as u64;
+        }
+
+        Self(out)
+    }
+
+    fn low_u128(self) -> u128 {
+        let hi = if L > 1 {self.0[1]} else if self.is_negative() {!0} else {0};
+
+        ((hi as u128) << 64) | self.0[0] as u128
+    }
+
+    fn is_zero(self) -> bool {
+        self.0.iter().all(|&limb| limb == 0)
+    }
+
+    fn is_negative(self) -> bool {
+        self.0[L - 1] >> 63 != 0
+    }
+
+    fn leading_zeros(self) -> u32 {
+        let mut lz = 0;
+        for &limb in self.0.iter().rev() {
+            lz += limb.leading_zeros();
+            if limb != 0 {
+                break;
+            }
+        }
+        lz
+    }
+
+    fn low_bits(self, k: u32) -> bool {
+        let k = k.min(Self::BITS);
+        let full = (k / 64) as usize;
+
+        self.0[..full].iter().any(|&limb| limb != 0)
+            || (k % 64 != 0 && self.0[full] & !(!0u64 << (k % 64)) != 0)
+    }
+
+    fn not(self) -> Self {
+        let mut out = self.0;
+        for limb in out.iter_mut() {
+            *limb = !*limb;
+        }
+        Self(out)
+    }
+
+    fn wrapping_add(self, other: Self) -> Self {
+        let mut out = [0u64; L];
+        let mut carry = false;
+
+        for (i, limb) in out.iter_mut().enumerate() {
+            let (t, c1) = self.0[i].overflowing_add(other.0[i]);
+            let (t, c2) = t.overflowing_add(carry as u64);
+            *limb = t;
+            carry = c1 | c2;
+        }
+
+        Self(out)
+    }
+
+    fn shl(self, k: u32) -> Self {
+        let mut out = [0u64; L];
+        let (limbs, bits) = ((k / 64) as usize, k % 64);
+
+        for (i, limb) in out.iter_mut().enumerate().skip(limbs) {
+            *limb = self.0[i - limbs] << bits;
+            if bits != 0 && i > limbs {
+                *limb |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+
+        Self(out)
+    }
+
+    fn sar(self, k: u32) -> Self {
+        let fill = if self.is_negative() {!0u64} else {0};
+        let k = k.min(Self::BITS);
+        let (limbs, bits) = ((k / 64) as usize, k % 64);
+        let mut out = [fill; L];
+
+        for (i, limb) in out.iter_mut().enumerate().take(L.saturating_sub(limbs)) {
+            let hi = if i + limbs + 1 < L {self.0[i + limbs + 1]} else {fill};
+            *limb = self.0[i + limbs] >> bits;
+            if bits != 0 {
+                *limb |= hi << (64 - bits);
+            }
+        }
+
+        Self(out)
+    }
+
+    fn mul_sar(self, other: Self, k: u32) -> (Self, bool) {
+        let s = self.is_negative() ^ other.is_negative();
+        let prod = self.abs().mul_full(other.abs());
+
+        let (limbs, bits) = ((k / 64) as usize, k % 64);
+
+        let lost = prod.iter().take(limbs).any(|&limb| limb != 0)
+            || (bits != 0 && limbs < 2 * L && prod[limbs] & !(!0u64 << bits) != 0);
+
+        let mut out = Self::default();
+        for (i, limb) in out.0.iter_mut().enumerate() {
+            let lo = prod.get(i + limbs).copied().unwrap_or(0);
+            let hi = prod.get(i + limbs + 1).copied().unwrap_or(0);
+            *limb = if bits == 0 {lo} else {(lo >> bits) | (hi << (64 - bits))};
+        }
+
+        // floor of a negative product rounds the magnitude up
+        if s {
+            out = out.wrapping_neg();
+            if lost {
+                out = out.wrapping_add(Self::from_i128(-1));
+            }
+        }
+
+        (out, lost)
+    }
+
+    fn div_shl(self, other: Self, k: u32) -> (Self, bool) {
+        let mut q = Self::default();
+        let mut r = Self::default();
+
+        for i in (0..Self::BITS + k).rev() {
+            r = r.shl(1);
+            if i >= k && self.bit(i - k) {
+                r.0[0] |= 1;
+            }
+            if r.ge(&other) {
+                r = r.wrapping_sub(other);
+                if i < Self::BITS {
+                    q.set_bit(i);
+                }
+            }
+        }
+
+        (q, !r.is_zero())
+    }
+
+    fn to_u128_sticky(self) -> (u128, u32) {
+        let used = Self::BITS - self.leading_zeros();
+
+        if used <= 127 {
+            (self.low_u128(), 0)
+        }
+        else {
+            let shift = used - 126;
+            (self.sar(shift).low_u128() | self.low_bits(shift) as u128, shift)
+        }
+    }
+}
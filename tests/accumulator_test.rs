@@ -0,0 +1,117 @@
+use namagiri::posit::Posit;
+use namagiri::quire::Quire;
+use namagiri::flquire::FLQuire;
+use namagiri::accumulator::{self, Accumulator, Kahan, Naive, Policy};
+use namagiri::rng::XorShift;
+use ndarray::{Array1, Array2};
+
+type P = Posit<8, 1>;
+
+fn random_matrix(rng: &mut XorShift, rows: usize, cols: usize) -> Array2<P> {
+    Array2::from_shape_fn((rows, cols), |_| P::from(rng.next_normal() as f32))
+}
+
+#[test]
+fn dot_test() {
+    let mut rng = XorShift::new(3);
+    for _ in 0..500 {
+        let a: Array1<P> = (0..32).map(|_| P::from(rng.next_normal() as f32)).collect();
+        let b: Array1<P> = (0..32).map(|_| P::from(rng.next_normal() as f32)).collect();
+
+        let q = a.iter().zip(&b).fold(Quire::<8, 1>::default(), |acc, (&x, &y)| acc + Quire::from(x) * Quire::from(y));
+        let n = a.iter().zip(&b).fold(P::default(), |acc, (&x, &y)| acc + x * y);
+
+        assert_eq!(accumulator::dot::<_, Quire<8, 1>>(a.view(), b.view()), P::from(q));
+        assert_eq!(accumulator::dot::<_, FLQuire<8, 1, 64>>(a.view(), b.view()), P::from(q));
+        assert_eq!(accumulator::dot::<_, Naive<P>>(a.view(), b.view()), n);
+        assert_eq!(Policy::Quire.dot(a.view(), b.view()), P::from(q));
+        assert_eq!(Policy::FLQuire(50).dot(a.view(), b.view()), P::from(q));
+    }
+}
+
+#[test]
+fn kahan_test() {
+    let small = P::from(0.125);
+    let mut naive = Naive::<P>::init();
+    let mut kahan = Kahan::<P>::init();
+    let mut exact = Quire::<8, 1>::init();
+
+    Accumulator::add(&mut naive, P::from(16.0));
+    Accumulator::add(&mut kahan, P::from(16.0));
+    Accumulator::add(&mut exact, P::from(16.0));
+    for _ in 0..64 {
+        Accumulator::add(&mut naive, small);
+        Accumulator::add(&mut kahan, small);
+        Accumulator::add(&mut exact, small);
+    }
+
+    assert_eq!(naive.finish(), P::from(16.0));
+    assert_eq!(exact.finish(), P::from(24.0));
+    assert_eq!(kahan.finish(), exact.finish());
+}
+
+#[test]
+fn matmul_test() {
+    let mut rng = XorShift::new(11);
+    let a = random_matrix(&mut rng, 7, 13);
+    let b = random_matrix(&mut rng, 13, 5);
+
+    let c = accumulator::matmul::<_, Quire<8, 1>>(a.view(), b.view());
+    assert_eq!(c.dim(), (7, 5));
+    for i in 0..7 {
+        for j in 0..5 {
+            assert_eq!(c[[i, j]], accumulator::dot::<_, Quire<8, 1>>(a.row(i), b.column(j)));
+        }
+    }
+
+    assert_eq!(Policy::Quire.matmul(a.view(), b.view()), c);
+    assert_eq!(Policy::Naive.matmul(a.view(), b.view()), a.dot(&b));
+    assert_eq!(Policy::F32.matmul(a.view(), b.view()), accumulator::matmul::<_, f32>(a.view(), b.view()));
+
+    let af = a.mapv(f32::from);
+    let bf = b.mapv(f32::from);
+    assert_eq!(accumulator::matmul::<f32, f32>(af.view(), bf.view()).mapv(P::from), Policy::F32.matmul(a.view(), b.view()));
+}
+
+#[test]
+fn policy_parse_test() {
+    assert_eq!("quire".parse::<Policy>(), Ok(Policy::Quire));
+    assert_eq!("flquire".parse::<Policy>(), Ok(Policy::FLQuire(20)));
+    assert_eq!("flquire:32".parse::<Policy>(), Ok(Policy::FLQuire(32)));
+    assert_eq!("kahan".parse::<Policy>(), Ok(Policy::Kahan));
+    assert!("flquire:wide".parse::<Policy>().is_err());
+    assert!("flquire:65".parse::<Policy>().is_err());
+    assert!("double".parse::<Policy>().is_err());
+}
+
+#[test]
+fn flquire_dispatch_test() {
+    // posit32 has only the windows of 30 bits or more to choose from
+    let a: Array1<Posit<32, 2>> = (1..=8).map(|i| Posit::from(i as f64 / 3.0)).collect();
+    let exact = a.iter().map(|&x| f64::from(x) * f64::from(x)).sum::<f64>();
+    for &size in &[16, 30, 64] {
+        assert!((f64::from(Policy::FLQuire(size).dot(a.view(), a.view())) - exact).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn quire_posit32_test() {
+    // the posit32 quire needs more than 128 bits, small dyadic values keep the f64 reference exact
+    let a: Array1<Posit<32, 2>> = (1..=8).map(|i| Posit::from(i as f64 / 4.0)).collect();
+    let b: Array1<Posit<32, 2>> = (1..=8).map(|i| Posit::from(3.0 - i as f64)).collect();
+    let exact = a.iter().zip(&b).map(|(&x, &y)| f64::from(x) * f64::from(y)).sum::<f64>();
+
+    assert_eq!(Policy::Quire.dot(a.view(), b.view()), Posit::from(exact));
+
+    let m = a.clone().into_shape((2, 4)).unwrap();
+    let n = b.clone().into_shape((4, 2)).unwrap();
+    let c = m.mapv(f64::from).dot(&n.mapv(f64::from)).mapv(Posit::<32, 2>::from);
+    assert_eq!(Policy::Quire.matmul(m.view(), n.view()), c);
+}
+
+#[test]
+#[should_panic(expected = "wider than the largest dispatched size")]
+fn flquire_too_wide_test() {
+    let a = Array1::from(vec![P::from(1.0); 4]);
+    Policy::FLQuire(65).dot(a.view(), a.view());
+}
@@ -0,0 +1,146 @@
+use namagiri::posit::Posit;
+use namagiri::cnn::{self, BatchNorm, CnnError, Conv2dConfig, Pool2dConfig};
+use namagiri::rng::XorShift;
+use ndarray::{Array1, Array4};
+
+type P = Posit<8, 1>;
+
+fn random(rng: &mut XorShift, shape: (usize, usize, usize, usize)) -> Array4<P> {
+    Array4::from_shape_fn(shape, |_| P::from(rng.next_normal()))
+}
+
+// direct convolution with the sum taken exactly in f64 and rounded once
+fn reference(x: &Array4<P>, w: &Array4<P>, bias: Option<&Array1<P>>, cfg: Conv2dConfig) -> Array4<P> {
+    let (n, c, h, wd) = x.dim();
+    let (o, cg, kh, kw) = w.dim();
+    let (s, p, d) = (cfg.stride, cfg.padding, cfg.dilation);
+    let oh = (h + 2 * p.0 - d.0 * (kh - 1) - 1) / s.0 + 1;
+    let ow = (wd + 2 * p.1 - d.1 * (kw - 1) - 1) / s.1 + 1;
+    let per_group = o / cfg.groups;
+    assert_eq!(c / cfg.groups, cg);
+
+    Array4::from_shape_fn((n, o, oh, ow), |(b, oc, i, j)| {
+        let mut acc = bias.map_or(0.0, |b| f64::from(b[oc]));
+        for ci in 0..cg {
+            for ky in 0..kh {
+                for kx in 0..kw {
+                    let iy = (i * s.0 + ky * d.0) as isize - p.0 as isize;
+                    let ix = (j * s.1 + kx * d.1) as isize - p.1 as isize;
+                    if iy >= 0 && ix >= 0 && (iy as usize) < h && (ix as usize) < wd {
+                        acc += f64::from(w[[oc, ci, ky, kx]]) * f64::from(x[[b, oc / per_group * cg + ci, iy as usize, ix as usize]]);
+                    }
+                }
+            }
+        }
+        P::from(acc)
+    })
+}
+
+#[test]
+fn conv2d_test() {
+    let mut rng = XorShift::new(1);
+    let configs = [
+        Conv2dConfig::default(),
+        Conv2dConfig{stride: (2, 1), padding: (1, 2), ..Default::default()},
+        Conv2dConfig{dilation: (2, 2), padding: (2, 2), ..Default::default()},
+        Conv2dConfig{groups: 2, stride: (2, 2), padding: (1, 1), ..Default::default()},
+        Conv2dConfig{groups: 4, ..Default::default()},
+    ];
+
+    for cfg in configs {
+        let x = random(&mut rng, (2, 4, 9, 8));
+        let w = random(&mut rng, (8, 4 / cfg.groups, 3, 3));
+        let bias: Array1<P> = (0..8).map(|_| P::from(rng.next_normal())).collect();
+
+        assert_eq!(cnn::conv2d(x.view(), w.view(), None, cfg).unwrap(), reference(&x, &w, None, cfg));
+        assert_eq!(cnn::conv2d(x.view(), w.view(), Some(bias.view()), cfg).unwrap(), reference(&x, &w, Some(&bias), cfg));
+    }
+}
+
+#[test]
+fn pool_test() {
+    let x = Array4::from_shape_fn((1, 2, 4, 4), |(_, c, i, j)| P::from((i * 4 + j) as f64 * if c == 0 {1.0} else {-0.25}));
+
+    let m = cnn::max_pool2d(x.view(), Pool2dConfig::new((2, 2))).unwrap();
+    assert_eq!(m.dim(), (1, 2, 2, 2));
+    assert_eq!(m[[0, 0, 1, 0]], P::from(13.0));
+    assert_eq!(m[[0, 1, 0, 0]], P::from(0.0));
+
+    let a = cnn::avg_pool2d(x.view(), Pool2dConfig::new((2, 2)), true).unwrap();
+    assert_eq!(a[[0, 0, 0, 1]], P::from(4.5));
+    assert_eq!(a[[0, 1, 1, 1]], P::from(-3.125));
+
+    // a padded corner averages over four of nine positions, or over the four values only
+    let cfg = Pool2dConfig{kernel: (3, 3), stride: (2, 2), padding: (1, 1)};
+    let with = cnn::avg_pool2d(x.view(), cfg, true).unwrap();
+    let without = cnn::avg_pool2d(x.view(), cfg, false).unwrap();
+    assert_eq!(with[[0, 0, 0, 0]], P::from(10.0 / 9.0));
+    assert_eq!(without[[0, 0, 0, 0]], P::from(2.5));
+    assert_eq!(cnn::max_pool2d(x.view(), cfg).unwrap()[[0, 1, 0, 0]], P::from(0.0));
+
+    let mut n = x.clone();
+    n[[0, 0, 0, 0]] = P::NAR;
+    let m = cnn::max_pool2d(n.view(), Pool2dConfig::new((2, 2))).unwrap();
+    assert!(m[[0, 0, 0, 0]].is_nar() && !m[[0, 0, 0, 1]].is_nar());
+    assert!(cnn::avg_pool2d(n.view(), Pool2dConfig::new((2, 2)), true).unwrap()[[0, 0, 0, 0]].is_nar());
+}
+
+#[test]
+fn batch_norm_test() {
+    type Q = Posit<16, 1>;
+    let mut rng = XorShift::new(2);
+    let x = Array4::from_shape_fn((1, 3, 6, 6), |_| Q::from(rng.next_normal()));
+    let w = Array4::from_shape_fn((4, 3, 3, 3), |_| Q::from(rng.next_normal() * 0.5));
+    let bias: Array1<Q> = (0..4).map(|_| Q::from(rng.next_normal())).collect();
+    let param = |rng: &mut XorShift, shift: f64| (0..4).map(|_| Q::from(rng.next_f64() + shift)).collect::<Array1<Q>>();
+    let bn = BatchNorm{gamma: param(&mut rng, 0.5), beta: param(&mut rng, -0.5), mean: param(&mut rng, -0.5), var: param(&mut rng, 0.1), eps: 1e-5};
+
+    let cfg = Conv2dConfig{padding: (1, 1), ..Default::default()};
+    let (wf, bf) = cnn::fold_batch_norm(w.view(), Some(bias.view()), &bn).unwrap();
+    let folded = cnn::conv2d(x.view(), wf.view(), Some(bf.view()), cfg).unwrap();
+
+    let y = cnn::conv2d(x.view(), w.view(), Some(bias.view()), cfg).unwrap();
+    for ((b, c, i, j), &v) in y.indexed_iter() {
+        let s = f64::from(bn.gamma[c]) / (f64::from(bn.var[c]) + bn.eps).sqrt();
+        let expected = (f64::from(v) - f64::from(bn.mean[c])) * s + f64::from(bn.beta[c]);
+        let actual = f64::from(folded[[b, c, i, j]]);
+        assert!((actual - expected).abs() < 2e-2 * expected.abs().max(1.0), "{} vs {}", actual, expected);
+    }
+}
+
+#[test]
+fn error_test() {
+    let x = Array4::<P>::zeros((1, 4, 5, 5));
+    let w = Array4::<P>::zeros((6, 2, 3, 3));
+
+    assert_eq!(cnn::conv2d(x.view(), w.view(), None, Conv2dConfig::default()), Err(CnnError::Channels(2, 4)));
+    assert!(cnn::conv2d(x.view(), w.view(), None, Conv2dConfig{groups: 2, ..Default::default()}).is_ok());
+    assert_eq!(cnn::conv2d(x.view(), w.view(), None, Conv2dConfig{groups: 3, ..Default::default()}), Err(CnnError::Groups(3, 4, 6)));
+    assert_eq!(cnn::conv2d(x.view(), w.view(), None, Conv2dConfig{groups: 2, dilation: (3, 1), ..Default::default()}), Err(CnnError::Window(7, 5, 0)));
+    assert_eq!(cnn::conv2d(x.view(), w.view(), None, Conv2dConfig{groups: 2, stride: (0, 1), ..Default::default()}), Err(CnnError::Zero));
+    assert_eq!(cnn::conv2d(x.view(), w.view(), Some(Array1::zeros(5).view()), Conv2dConfig{groups: 2, ..Default::default()}), Err(CnnError::Parameters(6)));
+
+    assert_eq!(cnn::max_pool2d(x.view(), Pool2dConfig{kernel: (2, 2), stride: (1, 1), padding: (2, 0)}), Err(CnnError::Padding));
+    assert_eq!(cnn::avg_pool2d(x.view(), Pool2dConfig::new((6, 6)), true), Err(CnnError::Window(6, 5, 0)));
+
+    // padding alone fits the kernel, but every window would be empty
+    let cfg = Pool2dConfig{kernel: (2, 2), stride: (1, 1), padding: (1, 1)};
+    assert_eq!(cnn::max_pool2d(Array4::<P>::zeros((1, 1, 0, 3)).view(), cfg), Err(CnnError::Window(2, 0, 1)));
+    assert_eq!(cnn::avg_pool2d(Array4::<P>::zeros((1, 1, 3, 0)).view(), cfg, false), Err(CnnError::Window(2, 0, 1)));
+}
+
+#[test]
+fn posit32_test() {
+    type Q = Posit<32, 2>;
+    let x = Array4::from_shape_fn((1, 1, 3, 3), |(_, _, i, j)| Q::from((i * 3 + j + 1) as f64));
+
+    // a 2x2 box filter sums each window exactly
+    let y = cnn::conv2d(x.view(), Array4::from_elem((1, 1, 2, 2), Q::from(1.0)).view(), None, Conv2dConfig::default()).unwrap();
+    assert_eq!(y.into_raw_vec(), [12.0, 16.0, 24.0, 28.0].map(Q::from).to_vec());
+
+    let cfg = Pool2dConfig{kernel: (3, 3), stride: (1, 1), padding: (1, 1)};
+    let a = cnn::avg_pool2d(x.view(), cfg, true).unwrap();
+    assert_eq!(a[[0, 0, 1, 1]], Q::from(5.0));
+    assert_eq!(a[[0, 0, 0, 0]], Q::from(12.0 / 9.0));
+    assert_eq!(cnn::max_pool2d(x.view(), cfg).unwrap()[[0, 0, 2, 2]], Q::from(9.0));
+}
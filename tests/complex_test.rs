@@ -0,0 +1,128 @@
+use namagiri::posit::Posit;
+use namagiri::complex::{self, ComplexPosit, ComplexQuire};
+use namagiri::accumulator;
+use namagiri::gemm::fused_dot;
+use namagiri::rng::XorShift;
+use ndarray::{array, Array1, Array2};
+use num_traits::identities::Zero;
+
+type P = Posit<16, 1>;
+type C = ComplexPosit<16, 1>;
+
+fn random(rng: &mut XorShift) -> C {
+    C::from((rng.next_normal(), rng.next_normal()))
+}
+
+#[test]
+fn mul_test() {
+    let mut rng = XorShift::new(1);
+    let mut naive_misses = 0;
+
+    for _ in 0..2000 {
+        let (x, y) = (random(&mut rng), random(&mut rng));
+        let ((a, b), (c, d)): ((f64, f64), (f64, f64)) = (x.into(), y.into());
+
+        // products of 13-bit significands and their sums are exact in f64
+        let z = x * y;
+        assert_eq!(z, C::from((a * c - b * d, a * d + b * c)));
+
+        naive_misses += (z != C::new(x.re * y.re - x.im * y.im, x.re * y.im + x.im * y.re)) as usize;
+    }
+
+    assert!(naive_misses > 100, "{}", naive_misses);
+}
+
+#[test]
+fn div_test() {
+    let mut rng = XorShift::new(2);
+
+    for _ in 0..2000 {
+        let (x, y) = (random(&mut rng), random(&mut rng));
+        let ((a, b), (c, d)): ((f64, f64), (f64, f64)) = (x.into(), y.into());
+        let den = c * c + d * d;
+
+        assert_eq!(x / y, C::from(((a * c + b * d) / den, (b * c - a * d) / den)));
+    }
+
+    let z = C::from((3.0, -2.0));
+    assert_eq!(z / C::from((1.0, 0.0)), z);
+    assert_eq!(z / z, C::from((1.0, 0.0)));
+    assert!((z / C::zero()).is_nar());
+}
+
+#[test]
+fn abs_arg_test() {
+    assert_eq!(C::from((3.0, 4.0)).abs(), P::from(5.0));
+    assert_eq!(C::from((-3.0, 4.0)).norm_sqr(), P::from(25.0));
+    assert_eq!(C::from((0.0, 2.0)).arg(), P::from(std::f64::consts::FRAC_PI_2));
+    assert_eq!(C::from((-1.0, 0.0)).arg(), P::from(std::f64::consts::PI));
+    assert_eq!(C::zero().abs(), P::from(0.0));
+
+    let mut rng = XorShift::new(3);
+    for _ in 0..2000 {
+        let z = random(&mut rng);
+        let (a, b): (f64, f64) = z.into();
+        assert_eq!(z.abs(), P::from((a * a + b * b).sqrt()));
+    }
+
+    assert!(C::new(P::NAR, P::from(1.0)).abs().is_nar());
+    assert!(C::NAR.arg().is_nar());
+}
+
+#[test]
+fn dot_test() {
+    let mut rng = XorShift::new(4);
+    let a: Array1<C> = (0..50).map(|_| random(&mut rng)).collect();
+    let b: Array1<C> = (0..50).map(|_| random(&mut rng)).collect();
+
+    let re = fused_dot(P::from(0.0), a.iter().zip(&b).flat_map(|(x, y)| [(x.re, y.re), (x.im, y.im)]));
+    let im = fused_dot(P::from(0.0), a.iter().zip(&b).flat_map(|(x, y)| [(x.re, y.im), (-x.im, y.re)]));
+    assert_eq!(complex::dotc(a.view(), b.view()), C::new(re, im));
+
+    let u = complex::dotu(a.view(), b.view());
+    assert_eq!(accumulator::dot::<C, ComplexQuire<16, 1>>(a.view(), b.view()), u);
+    assert_eq!(complex::dotc(a.mapv(|z| z.conj()).view(), b.view()), u);
+
+    // conj(a) a is real
+    assert_eq!(complex::dotc(a.view(), a.view()).im, P::from(0.0));
+
+    let mut n = a.clone();
+    n[7] = C::NAR;
+    assert!(complex::dotc(n.view(), b.view()).is_nar());
+}
+
+#[test]
+fn ndarray_test() {
+    let re: Array2<P> = array![[1.0, 2.0], [3.0, 4.0]].mapv(P::from);
+    let im: Array2<P> = array![[0.0, -1.0], [0.5, 2.0]].mapv(P::from);
+
+    let z = complex::from_parts(re.view(), im.view());
+    assert_eq!(complex::to_parts(z.view()), (re, im));
+
+    let i = C::i();
+    let w = &z * i;
+    assert_eq!(w[[0, 1]], C::from((1.0, 2.0)));
+    assert_eq!(Array2::<C>::zeros((2, 2)) + &z, z);
+
+    let v: Array1<C> = array![C::from((1.0, 1.0)), C::from((0.0, -1.0))];
+    assert_eq!(z.dot(&v), array![C::from((0.0, -1.0)), C::from((4.5, -0.5))]);
+
+    assert_eq!(format!("{}", C::from((1.5, -2.0))), "1.5-2i");
+    assert_eq!(format!("{}", C::from((0.0, 0.25))), "0+0.25i");
+}
+
+#[test]
+fn posit32_test() {
+    type Z = ComplexPosit<32, 2>;
+    let z = Z::from((1.0, 1.0));
+
+    assert_eq!(z * z, Z::from((0.0, 2.0)));
+    assert_eq!(z.mul_add(z, Z::from((1.0, 0.0))), Z::from((1.0, 2.0)));
+    assert_eq!(Z::from((0.0, 2.0)) / z, z);
+    assert_eq!(Z::from((3.0, 4.0)).abs(), Posit::<32, 2>::from(5.0));
+
+    let a = Array1::from_elem(3, z);
+    assert_eq!(complex::dotu(a.view(), a.view()), Z::from((0.0, 6.0)));
+    assert_eq!(complex::dotc(a.view(), a.view()), Z::from((6.0, 0.0)));
+    assert_eq!(accumulator::dot::<Z, ComplexQuire<32, 2>>(a.view(), a.view()), Z::from((0.0, 6.0)));
+}
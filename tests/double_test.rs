@@ -0,0 +1,165 @@
+use namagiri::posit::Posit;
+use namagiri::quire::Quire;
+use namagiri::gemm::fused_dot;
+use namagiri::double::{self, DoublePosit};
+use namagiri::rng::XorShift;
+use namagiri::sizing::Distribution;
+use ndarray::Array1;
+use num_traits::{One, Zero};
+
+type P = Posit<16, 1>;
+type P32 = Posit<32, 2>;
+type D = DoublePosit<32, 2>;
+
+// the dot product to about 106 bits, from f64 error-free transformations
+fn reference(a: &Array1<P32>, b: &Array1<P32>) -> f64 {
+    let (s, c) = a.iter().zip(b).fold((0.0f64, 0.0f64), |(s, c), (&x, &y)| {
+        let (x, y) = (f64::from(x), f64::from(y));
+        let h = x * y;
+        let r = x.mul_add(y, -h);
+        let t = s + h;
+        let z = t - s;
+        (t, c + (s - (t - z)) + (h - z) + r)
+    });
+    s + c
+}
+
+fn exact_sum(a: P, b: P) -> i128 {
+    (Quire::from(a) + Quire::from(b)).0 as i128
+}
+
+#[test]
+fn two_sum_test() {
+    let mut rng = XorShift::new(1);
+    for _ in 0..20000 {
+        let (a, b) = (Distribution::Bits.sample::<16, 1>(&mut rng), Distribution::Bits.sample::<16, 1>(&mut rng));
+        if a.is_nar() || b.is_nar() {
+            assert_eq!(double::two_sum(a, b), (P::NAR, P::NAR));
+            continue;
+        }
+
+        let (s, e) = double::two_sum(a, b);
+        assert_eq!(s, fused_dot(a, [(b, P::one())]), "{} + {}", a, b);
+
+        // the error of a sum holds the low bits of the smaller term, which always fit
+        assert_eq!(double::two_sum_exact(a, b), Some((s, e)));
+        assert_eq!(exact_sum(s, e), exact_sum(a, b), "{} + {}", a, b);
+    }
+
+    let (s, e) = double::two_sum(P32::one(), P32::from(2f64.powi(-40)));
+    assert_eq!((s, e), (P32::one(), P32::from(2f64.powi(-40))));
+
+    // terms too far apart to align still sum exactly
+    let a = P32::from(1e30);
+    let b = P32::from(1e-30);
+    assert_eq!(double::two_sum_exact(b, a), Some((a, b)));
+}
+
+#[test]
+fn two_product_test() {
+    let mut rng = XorShift::new(2);
+    let mut inexact = 0;
+
+    for _ in 0..20000 {
+        let (a, b) = (Distribution::Bits.sample::<16, 1>(&mut rng), Distribution::Bits.sample::<16, 1>(&mut rng));
+        if a.is_nar() || b.is_nar() {
+            continue;
+        }
+
+        let (p, e) = double::two_product(a, b);
+        assert_eq!(p, fused_dot(P::zero(), [(a, b)]), "{} * {}", a, b);
+        assert_eq!(e, fused_dot(P::zero(), [(a, b), (-p, P::one())]), "{} * {}", a, b);
+
+        match double::two_product_exact(a, b) {
+            Some(pair) => {
+                assert_eq!(pair, (p, e));
+                assert_eq!(f64::from(p) + f64::from(e), f64::from(a) * f64::from(b));
+            },
+            None => inexact += 1,
+        }
+    }
+
+    // tapered precision: the error of a product needs more bits than posits have out there
+    assert!(inexact > 0);
+    let third = P32::from(1.0 / 3.0);
+    let (p, e) = double::two_product(third, third);
+    assert_eq!(double::two_product_exact(third, third), None);
+    assert!((f64::from(p) + f64::from(e) - f64::from(third) * f64::from(third)).abs() < 2f64.powi(-48));
+
+    // exact near 1, where both halves have room
+    let (a, b) = (P32::from(1.0 + 2f64.powi(-20)), P32::from(1.0 - 2f64.powi(-21)));
+    let (p, e) = double::two_product_exact(a, b).unwrap();
+    assert_eq!(f64::from(p) + f64::from(e), f64::from(a) * f64::from(b));
+    assert!(!e.is_zero());
+
+    // a product below minpos rounds up to it, leaving an error no posit can hold
+    let tiny = P::from(2f64.powi(-20));
+    assert_eq!(double::two_product(tiny, tiny).0, P::MINPOS);
+    assert_eq!(double::two_product_exact(tiny, tiny), None);
+
+    // beyond maxpos the error has the sign of the product, below minpos the opposite one
+    type W = Posit<32, 3>;
+    assert_eq!(double::two_product(W::MAXPOS, W::MAXPOS), (W::MAXPOS, W::MAXPOS));
+    assert_eq!(double::two_product(-W::MAXPOS, W::MAXPOS), (-W::MAXPOS, -W::MAXPOS));
+    assert_eq!(double::two_product(W::MINPOS, W::MINPOS), (W::MINPOS, -W::MINPOS));
+    assert_eq!(double::two_product_exact(W::MAXPOS, W::MAXPOS), None);
+}
+
+#[test]
+fn double_posit_test() {
+    let mut rng = XorShift::new(3);
+    let rel = |x: D, v: f64| (f64::from(x) - v).abs() / v.abs();
+
+    for _ in 0..2000 {
+        let (x, y) = (0.5 + rng.next_f64(), 0.5 + rng.next_f64());
+        let (a, b) = (D::from(x), D::from(y));
+        let (x, y) = (f64::from(a), f64::from(b));
+
+        // well beyond the 2^-27 of a single posit32 near 1
+        assert!(rel(a, x) == 0.0 && rel(b, y) == 0.0);
+        assert!(rel(a + b, x + y) < 2f64.powi(-44));
+        assert!(rel(a * b, x * y) < 2f64.powi(-44));
+        assert!(rel(a / b, x / y) < 2f64.powi(-44));
+        assert!(rel(a.sqrt(), x.sqrt()) < 2f64.powi(-44));
+        assert!((f64::from(a - b) - (x - y)).abs() < 2f64.powi(-44));
+    }
+
+    let third = D::one() / D::from(3.0);
+    assert!((f64::from(third) - 1.0 / 3.0).abs() < 2f64.powi(-48));
+    assert!((f64::from(third) - f64::from(P32::from(1.0 / 3.0))).abs() > 2f64.powi(-32));
+    assert!(f64::from(D::from(2.0).sqrt() * D::from(2.0).sqrt() - D::from(2.0)).abs() < 2f64.powi(-44));
+
+    assert!((D::one() / D::zero()).is_nar());
+    assert!(D::from(-1.0).sqrt().is_nar());
+    assert!((D::NAR + D::one()).is_nar());
+    assert_eq!(format!("{}", D::from(0.75)), "0.75");
+}
+
+#[test]
+fn compensated_test() {
+    // 1 is lost beside 1e8 in posit32 but kept by the error terms
+    let x = Array1::from(vec![P32::from(1e8), P32::one(), -P32::from(1e8)]);
+    assert_eq!(x.iter().fold(P32::zero(), |s, &v| s + v), P32::zero());
+    assert_eq!(double::sum2(x.view()), D::one());
+
+    // an ill-conditioned dot product: sum (a_i b_i) cancels to a small value
+    let mut rng = XorShift::new(4);
+    for _ in 0..50 {
+        let n = 40;
+        let a: Array1<P32> = (0..n).map(|_| P32::from(rng.next_normal() * 2f64.powi((rng.next_u32() % 24) as i32))).collect();
+        let mut b: Array1<P32> = (0..n).map(|_| P32::from(rng.next_normal())).collect();
+
+        // pick the last term to nearly cancel the rest
+        let partial: f64 = a.iter().zip(&b).take(n - 1).map(|(&x, &y)| f64::from(x) * f64::from(y)).sum();
+        b[n - 1] = P32::from(-partial / f64::from(a[n - 1]));
+
+        let exact = reference(&a, &b);
+        let naive = a.iter().zip(&b).fold(P32::zero(), |s, (&x, &y)| s + x * y);
+        let dot2 = double::dot2(a.view(), b.view());
+
+        let scale: f64 = a.iter().zip(&b).map(|(&x, &y)| (f64::from(x) * f64::from(y)).abs()).sum();
+        let (e2, en) = ((f64::from(dot2) - exact).abs(), (f64::from(naive) - exact).abs());
+        assert!(e2 <= scale * 2f64.powi(-40), "{} {} {}", dot2, naive, exact);
+        assert!(e2 <= en);
+    }
+}
@@ -0,0 +1,119 @@
+use namagiri::posit::Posit;
+use namagiri::dual::{self, Dual};
+use ndarray::{array, Array1};
+use num_traits::{Num, One, Zero};
+
+type P = Posit<16, 1>;
+type D = Dual<P>;
+
+// f, f(x), f'(x)
+type Case = (fn(D) -> D, f64, f64);
+
+fn p(v: f64) -> P {
+    P::from(v)
+}
+
+// generic code written against num-traits runs unchanged on duals
+fn horner<T: Num + Copy>(coeffs: &[T], x: T) -> T {
+    coeffs.iter().rev().fold(T::zero(), |acc, &c| acc * x + c)
+}
+
+#[test]
+fn arithmetic_test() {
+    // x^3 - 2x at 1.5
+    let (y, d) = dual::derivative(|x| x * x * x - x * D::constant(p(2.0)), p(1.5));
+    assert_eq!((y, d), (p(0.375), p(4.75)));
+
+    let c = [p(-2.0), p(0.0), p(3.0)].map(D::constant);
+    assert_eq!(horner(&c, D::variable(p(0.75))), D::new(p(-0.3125), p(4.5)));
+
+    // quotient rule, each part a single rounding of the exact ratio
+    let (y, d) = dual::derivative(|x| x / (D::one() + x * x), p(2.0));
+    assert_eq!((y, d), (p(0.4), p(-0.12)));
+
+    let x = D::variable(p(3.0));
+    assert_eq!(x.recip(), D::new(p(1.0 / 3.0), p(-1.0 / 9.0)));
+    assert_eq!(x / p(-4.0), D::new(p(-0.75), p(-0.25)));
+    assert_eq!(D::new(p(7.5), p(1.0)) % D::constant(p(2.0)), D::new(p(1.5), p(1.0)));
+    assert_eq!((-x).abs(), x);
+
+    let mut y = x;
+    y *= x;
+    y -= D::constant(p(1.0));
+    y /= x;
+    assert_eq!(y, D::new(p(8.0 / 3.0), p(10.0 / 9.0)));
+
+    assert_eq!(format!("{}", D::new(p(1.5), p(-2.0))), "1.5-2ε");
+    assert_eq!(D::from_str_radix("2.5", 10).unwrap(), D::constant(p(2.5)));
+}
+
+#[test]
+fn elementary_test() {
+    let x: f64 = 0.5;
+    let cases: [Case; 12] = [
+        (D::exp, x.exp(), x.exp()),
+        (D::ln, x.ln(), 1.0 / x),
+        (D::sqrt, x.sqrt(), 0.5 / x.sqrt()),
+        (D::sin, x.sin(), x.cos()),
+        (D::cos, x.cos(), -x.sin()),
+        (D::tan, x.tan(), 1.0 / x.cos().powi(2)),
+        (D::asin, x.asin(), 1.0 / (1.0 - x * x).sqrt()),
+        (D::acos, x.acos(), -1.0 / (1.0 - x * x).sqrt()),
+        (D::atan, x.atan(), 0.8),
+        (D::sinh, x.sinh(), x.cosh()),
+        (D::cosh, x.cosh(), x.sinh()),
+        (D::tanh, x.tanh(), 1.0 - x.tanh().powi(2)),
+    ];
+
+    for (f, y, d) in cases {
+        assert_eq!(f(D::variable(p(x))), D::new(p(y), p(d)));
+    }
+
+    assert_eq!(D::variable(p(2.0)).powi(3), D::new(p(8.0), p(12.0)));
+    assert_eq!(D::variable(p(4.0)).powf(1.5), D::new(p(8.0), p(3.0)));
+
+    // chain rule through a composition: d/dx sin(x^2) = 2x cos(x^2)
+    let (y, d) = dual::derivative(|x| (x * x).sin(), p(1.25));
+    assert!((f64::from(y) - 1.5625f64.sin()).abs() < 1e-3);
+    assert!((f64::from(d) - 2.5 * 1.5625f64.cos()).abs() < 1e-3);
+
+    // NaR in, NaR out; the derivative of sqrt at zero does not exist
+    assert!(D::new(P::NAR, p(1.0)).exp().is_nar());
+    assert!(D::variable(p(-1.0)).ln().is_nar());
+    assert!(D::variable(p(1.0)) / D::zero() == D::NAR);
+    assert!(D::variable(p(0.0)).sqrt().eps.is_nar());
+}
+
+#[test]
+fn ndarray_test() {
+    let f = |v: ndarray::ArrayView1<D>| v[0] * v[1] + v[0].sin() - v[1] * v[1];
+    let x = array![p(0.5), p(-1.5)];
+
+    let (y, g) = dual::gradient(f, x.view());
+    assert_eq!(y, p(-0.75 + 0.5f64.sin() - 2.25));
+    assert_eq!(g, array![p(-1.5 + 0.5f64.cos()), p(3.5)]);
+
+    let (_, d) = dual::jvp(f, x.view(), array![p(2.0), p(1.0)].view());
+    assert!((f64::from(d) - (2.0 * (-1.5 + 0.5f64.cos()) + 3.5)).abs() < 1e-3);
+
+    // the derivative part of a dot product accumulates exactly
+    let a: Array1<D> = (1..=6).map(|k| D::new(p(k as f64), p(1.0 / 8.0))).collect();
+    let b: Array1<D> = (1..=6).map(|k| D::new(p(1.0 / k as f64), p(k as f64))).collect();
+    let exact: f64 = (1..=6).map(|k| k as f64 * k as f64 + f64::from(p(1.0 / k as f64)) / 8.0).sum();
+    assert_eq!(dual::dot(a.view(), b.view()).eps, p(exact));
+
+    // arrays of duals scale and sum like arrays of posits
+    let scaled = &a * D::constant(p(2.0));
+    assert_eq!(scaled.sum(), D::new(p(42.0), p(1.5)));
+}
+
+#[test]
+fn posit32_test() {
+    type Q = Posit<32, 2>;
+    let x = Dual::variable(Q::from(3.0));
+
+    assert_eq!(x * x, Dual::new(Q::from(9.0), Q::from(6.0)));
+    assert_eq!(x.mul_add(x, Dual::constant(Q::from(1.0))), Dual::new(Q::from(10.0), Q::from(6.0)));
+    assert_eq!(Dual::constant(Q::from(1.0)) / x, Dual::new(Q::from(1.0 / 3.0), Q::from(-1.0 / 9.0)));
+    assert_eq!((x * x) / x, x);
+}
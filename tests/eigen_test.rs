@@ -0,0 +1,118 @@
+use namagiri::posit::Posit;
+use namagiri::eigen::{self, JacobiConfig};
+use namagiri::linalg::LinalgError;
+use namagiri::rng::XorShift;
+use ndarray::{array, Array1, Array2};
+
+type P = Posit<16, 1>;
+
+fn max_diff(a: &Array2<f64>, b: &Array2<f64>) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).abs()).fold(0.0, f64::max)
+}
+
+fn random_symmetric(rng: &mut XorShift, n: usize) -> Array2<f64> {
+    let b = Array2::from_shape_fn((n, n), |_| rng.next_normal());
+    (&b + &b.t()) * 0.5
+}
+
+#[test]
+fn eigh_test() {
+    let mut rng = XorShift::new(11);
+    let cfg = JacobiConfig{tol: 1e-4, max_sweeps: 30};
+
+    for n in [1, 2, 6, 15] {
+        let a = random_symmetric(&mut rng, n);
+        let reference = eigen::eigh(a.view(), JacobiConfig{tol: 1e-15, max_sweeps: 30}).unwrap();
+        let e = eigen::eigh(a.mapv(P::from).view(), cfg).unwrap();
+        assert!(e.converged && reference.converged);
+
+        // f64 residual of A V = V diag(values)
+        let (l, v) = (e.values.mapv(f64::from), e.vectors.mapv(f64::from));
+        assert!(max_diff(&a.dot(&v), &(&v * &l)) < 1e-2);
+        assert!(e.values.iter().zip(e.values.iter().skip(1)).all(|(x, y)| x <= y));
+
+        assert!(l.iter().zip(&reference.values).all(|(x, y)| (x - y).abs() < 5e-3), "{} vs {}", l, reference.values);
+        assert!(e.orthogonality_loss < 5e-3 && reference.orthogonality_loss < 1e-13);
+        assert!(e.orthogonality_loss >= reference.orthogonality_loss);
+    }
+}
+
+#[test]
+fn eigh_exact_test() {
+    let a: Array2<P> = array![[2.0, 1.0], [1.0, 2.0]].mapv(|x: f32| P::from(x));
+    let e = eigen::eigh(a.view(), JacobiConfig::default()).unwrap();
+    assert_eq!(e.values, array![1.0, 3.0].mapv(|x: f32| P::from(x)));
+    assert_eq!(e.sweeps, 2);
+
+    // already diagonal, so no rotation is needed
+    let d: Array2<P> = array![[3.0, 0.0], [0.0, -1.0]].mapv(|x: f32| P::from(x));
+    let e = eigen::eigh(d.view(), JacobiConfig::default()).unwrap();
+    assert_eq!((e.values, e.sweeps, e.orthogonality_loss), (array![-1.0, 3.0].mapv(|x: f32| P::from(x)), 1, 0.0));
+}
+
+#[test]
+fn posit32_test() {
+    type P32 = Posit<32, 2>;
+
+    let a: Array2<P32> = array![[2.0, 1.0], [1.0, 2.0]].mapv(P32::from);
+    let e = eigen::eigh(a.view(), JacobiConfig::default()).unwrap();
+    assert_eq!(e.values, array![1.0, 3.0].mapv(P32::from));
+
+    let mut rng = XorShift::new(12);
+    let a = random_symmetric(&mut rng, 8);
+    let reference = eigen::eigh(a.view(), JacobiConfig{tol: 1e-15, max_sweeps: 30}).unwrap();
+    let e = eigen::eigh(a.mapv(P32::from).view(), JacobiConfig{tol: 1e-7, max_sweeps: 30}).unwrap();
+    assert!(e.converged && e.orthogonality_loss < 1e-6);
+    assert!(e.values.iter().zip(&reference.values).all(|(&x, y)| (f64::from(x) - y).abs() < 1e-6), "{} vs {}", e.values, reference.values);
+}
+
+#[test]
+fn svd_test() {
+    let mut rng = XorShift::new(12);
+    let cfg = JacobiConfig{tol: 1e-4, max_sweeps: 30};
+
+    for (m, n) in [(4, 4), (12, 5), (5, 9)] {
+        let a = Array2::from_shape_fn((m, n), |_| rng.next_normal());
+        let reference = eigen::svd(a.view(), JacobiConfig{tol: 1e-15, max_sweeps: 30}).unwrap();
+        let f = eigen::svd(a.mapv(P::from).view(), cfg).unwrap();
+        let k = m.min(n);
+        assert!(f.converged);
+        assert_eq!((f.u.dim(), f.s.len(), f.vt.dim()), ((m, k), k, (k, n)));
+
+        let (u, s, vt) = (f.u.mapv(f64::from), f.s.mapv(f64::from), f.vt.mapv(f64::from));
+        assert!(max_diff(&(&u * &s).dot(&vt), &a) < 2e-2);
+        assert!(s.iter().zip(s.iter().skip(1)).all(|(x, y)| x >= y));
+        assert!(s.iter().zip(&reference.s).all(|(x, y)| (x - y).abs() < 1e-2 * reference.s[0]));
+
+        assert!(f.orthogonality_loss.0 < 5e-3 && f.orthogonality_loss.1 < 5e-3);
+        assert!(reference.orthogonality_loss.0 < 1e-13 && reference.orthogonality_loss.1 < 1e-13);
+    }
+}
+
+#[test]
+fn low_rank_test() {
+    let x: Array1<f64> = array![1.0, 2.0, -1.0, 0.5];
+    let y: Array1<f64> = array![0.5, -1.0, 2.0];
+    let a = Array2::from_shape_fn((4, 3), |(i, j)| P::from(x[i] * y[j]));
+
+    let f = eigen::svd(a.view(), JacobiConfig::default()).unwrap();
+    let s = f.s.mapv(f64::from);
+    assert!((s[0] - x.dot(&x).sqrt() * y.dot(&y).sqrt()).abs() < 1e-2);
+    assert!(s[1] < 1e-2 && s[2] < 1e-2);
+}
+
+#[test]
+fn error_test() {
+    let wide = Array2::from_elem((2, 3), P::from(1.0));
+    assert_eq!(eigen::eigh(wide.view(), JacobiConfig::default()), Err(LinalgError::Shape));
+
+    let mut a = Array2::from_elem((2, 2), P::from(1.0));
+    a[[1, 0]] = P::NAR;
+    assert_eq!(eigen::eigh(a.view(), JacobiConfig::default()), Err(LinalgError::NaR(0)));
+    assert_eq!(eigen::svd(a.view(), JacobiConfig::default()), Err(LinalgError::NaR(0)));
+
+    // the upper triangle is ignored
+    a[[0, 1]] = P::NAR;
+    a[[1, 0]] = P::from(0.0);
+    assert!(eigen::eigh(a.view(), JacobiConfig::default()).is_ok());
+}
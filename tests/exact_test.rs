@@ -0,0 +1,59 @@
+use num_traits::identities::Zero;
+use namagiri::posit::Posit;
+use namagiri::quire::Quire;
+use namagiri::exact::ExactSum;
+
+#[test]
+fn against_quire_test() {
+    // formats whose quire fits 128 bits round exactly as the quire does
+    for i in 0..0b100000000 {
+        let a = Posit::<8, 1>(i);
+        if a.is_nar() {
+            continue;
+        }
+        let e = ExactSum::from(a) + ExactSum::product(a, Posit::from(0.75));
+        let q = Quire::from(a) + Quire::from(a) * Quire::from(Posit::<8, 1>::from(0.75));
+
+        assert_eq!(Posit::from(e), Posit::from(q));
+        assert_eq!(e.sqrt(), q.sqrt());
+        for n in [1, 3, -7, 64] {
+            assert_eq!(e.div_int(n), q.div_int(n));
+        }
+        for j in 0..0b100000000 {
+            let b = Posit::<8, 1>(j);
+            assert_eq!(e.div_posit(b), q.div_posit(b));
+            assert_eq!(e.ratio(ExactSum::from(b)), if b.is_nar() {Posit::NAR} else {Posit::from(q / Quire::from(b))});
+        }
+    }
+}
+
+#[test]
+fn posit32_test() {
+    type P32 = Posit<32, 2>;
+
+    let third = P32::from(1.0 / 3.0);
+    let mut e = ExactSum::zero();
+    for _ in 0..3 {
+        e.fma(third, P32::from(3.0));
+    }
+
+    assert_eq!(P32::from(e), P32::from(3.0 * f64::from(third) * 3.0));
+    assert_eq!(e.div_int(3), P32::from(f64::from(third) * 3.0));
+    assert_eq!(e.div_posit(P32::from(2.0)), P32::from(1.5 * f64::from(third) * 3.0));
+    assert_eq!(ExactSum::product(P32::from(3.0), P32::from(3.0)).sqrt(), P32::from(3.0));
+    assert_eq!(ExactSum::from(P32::from(2.0)).sqrt(), P32::from(2f64.sqrt()));
+    assert_eq!(ExactSum::from(P32::from(7.0)).ratio(ExactSum::from(P32::from(-2.0))), P32::from(-3.5));
+
+    // the extremes of the format still sum exactly
+    let big = ExactSum::product(P32::MAXPOS, P32::MAXPOS);
+    let tiny = ExactSum::product(P32::MINPOS, P32::MINPOS);
+    assert_eq!(P32::from(big + tiny - big), P32::MINPOS);
+    assert_eq!(P32::from(big - big), P32::zero());
+    assert!(tiny > ExactSum::zero() && -tiny < ExactSum::zero() && big > tiny);
+    assert_eq!((-big).abs(), big);
+    assert_eq!((big + tiny).to_posit::<16, 1>(), Posit::<16, 1>(0x7fff));
+
+    let nar = ExactSum::from(P32::NAR) + big;
+    assert!(nar.is_nar() && P32::from(nar).is_nar() && nar.div_int(2).is_nar() && nar.sqrt().is_nar());
+    assert!(ExactSum::from(P32::from(-1.0)).sqrt().is_nar() && big.ratio::<32, 2>(ExactSum::zero()).is_nar());
+}
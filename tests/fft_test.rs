@@ -0,0 +1,176 @@
+use namagiri::posit::Posit;
+use namagiri::complex::ComplexPosit;
+use namagiri::fft::{self, Butterfly, FftPlan};
+use namagiri::rng::XorShift;
+use ndarray::Array1;
+
+type C = ComplexPosit<16, 1>;
+
+fn signal(rng: &mut XorShift, n: usize) -> Vec<(f64, f64)> {
+    (0..n).map(|_| (rng.next_normal(), rng.next_normal())).collect()
+}
+
+fn to_posit(x: &[(f64, f64)]) -> Array1<C> {
+    x.iter().map(|&z| C::from(z)).collect()
+}
+
+fn to_f64(x: &Array1<C>) -> Vec<(f64, f64)> {
+    x.iter().map(|&z| z.into()).collect()
+}
+
+// direct O(n^2) DFT in f64
+fn dft(x: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let n = x.len();
+    (0..n).map(|k| x.iter().enumerate().fold((0.0, 0.0), |(re, im), (j, &(a, b))| {
+        let t = -2.0 * std::f64::consts::PI * (j * k) as f64 / n as f64;
+        (re + a * t.cos() - b * t.sin(), im + a * t.sin() + b * t.cos())
+    })).collect()
+}
+
+#[test]
+fn twiddle_test() {
+    let plan = FftPlan::<16, 1>::new(8, Butterfly::Fused);
+    let h = Posit::<16, 1>::from(std::f64::consts::FRAC_1_SQRT_2);
+    let (one, zero) = (Posit::<16, 1>::from(1.0), Posit::<16, 1>::from(0.0));
+
+    assert_eq!(plan.twiddles()[0], C::new(one, zero));
+    assert_eq!(plan.twiddles()[1], C::new(h, -h));
+    assert_eq!(plan.twiddles()[2], C::new(zero, -one));
+    assert_eq!(plan.twiddles()[4], C::new(-one, zero));
+    assert_eq!(FftPlan::<16, 1>::new(60, Butterfly::Fused).factors(), &[2, 2, 3, 5]);
+}
+
+#[test]
+fn accuracy_test() {
+    let mut rng = XorShift::new(1);
+
+    for n in [1, 2, 7, 12, 64, 90, 256] {
+        let x = signal(&mut rng, n);
+        let reference = dft(&x);
+        let xp = to_posit(&x);
+        let exact: Vec<(f64, f64)> = dft(&to_f64(&xp));
+
+        let fused = FftPlan::<16, 1>::new(n, Butterfly::Fused).forward(xp.view());
+        let rounded = FftPlan::<16, 1>::new(n, Butterfly::Rounded).forward(xp.view());
+        let (snr_fused, snr_rounded) = (fft::snr_db(&exact, &to_f64(&fused)), fft::snr_db(&exact, &to_f64(&rounded)));
+
+        assert!(snr_fused > 60.0, "n = {}: {} dB", n, snr_fused);
+        assert!(snr_fused >= snr_rounded - 1.0, "n = {}: {} vs {} dB", n, snr_fused, snr_rounded);
+
+        let f64_out = fft::fft_float(&x, false);
+        assert!(fft::snr_db(&reference, &f64_out) > 250.0);
+    }
+}
+
+#[test]
+fn rounded_stage_test() {
+    // a rounded stage rounds the four real products of each twiddle product, then every sum
+    let mut rng = XorShift::new(5);
+    for n in [3, 5, 7] {
+        let xp = to_posit(&signal(&mut rng, n));
+        let plan = FftPlan::<16, 1>::new(n, Butterfly::Rounded);
+        let y = plan.forward(xp.view());
+        let w = plan.twiddles();
+
+        for k in 0..n {
+            let expected = (1..n).fold(xp[0], |acc: C, r| {
+                let (a, b) = (w[r * k % n], xp[r]);
+                acc + C::new(a.re * b.re - a.im * b.im, a.re * b.im + a.im * b.re)
+            });
+            assert_eq!(y[k], expected, "n = {}, k = {}", n, k);
+        }
+    }
+}
+
+#[test]
+fn single_stage_test() {
+    // a single fused radix-p stage rounds every output exactly once
+    let mut rng = XorShift::new(2);
+    for n in [2, 3, 5, 7] {
+        let xp = to_posit(&signal(&mut rng, n));
+        let plan = FftPlan::<16, 1>::new(n, Butterfly::Fused);
+        let y = plan.forward(xp.view());
+
+        for k in 0..n {
+            let re = namagiri::gemm::fused_dot(Posit::from(0.0), (0..n).flat_map(|j| {
+                let w = plan.twiddles()[j * k % n];
+                [(w.re, xp[j].re), (-w.im, xp[j].im)]
+            }));
+            assert_eq!(y[k].re, re);
+        }
+    }
+}
+
+#[test]
+fn inverse_test() {
+    let mut rng = XorShift::new(3);
+
+    for n in [1, 8, 30, 128] {
+        let xp = to_posit(&signal(&mut rng, n));
+        let plan = FftPlan::<16, 1>::new(n, Butterfly::Fused);
+        let back = plan.inverse(plan.forward(xp.view()).view());
+
+        assert!(fft::snr_db(&to_f64(&xp), &to_f64(&back)) > 55.0, "n = {}: {}", n, fft::snr_db(&to_f64(&xp), &to_f64(&back)));
+        assert_eq!(fft::ifft(fft::fft(xp.view()).view()), back);
+
+        let x = to_f64(&xp);
+        let back = fft::fft_float(&fft::fft_float(&x, false), true);
+        assert!(fft::snr_db(&x, &back) > 250.0, "{}", fft::snr_db(&x, &back));
+    }
+}
+
+#[test]
+fn posit_vs_float_test() {
+    let mut rng = XorShift::new(4);
+    let x = signal(&mut rng, 512);
+    let reference = dft(&x);
+
+    let xp = to_posit(&x);
+    let p16 = fft::fft(xp.view());
+    let snr_p16 = fft::snr_db(&reference, &to_f64(&p16));
+
+    let x32: Vec<(f32, f32)> = x.iter().map(|&(a, b)| (a as f32, b as f32)).collect();
+    let f32_out: Vec<(f64, f64)> = fft::fft_float(&x32, false).into_iter().map(|(a, b)| (a as f64, b as f64)).collect();
+    let snr_f32 = fft::snr_db(&reference, &f32_out);
+
+    // posit16 carries about 12 significand bits near 1, f32 carries 24
+    assert!(snr_p16 > 60.0 && snr_f32 > snr_p16, "{} vs {}", snr_p16, snr_f32);
+}
+
+#[test]
+fn inverse_rounding_test() {
+    let mut rng = XorShift::new(5);
+    let plan = FftPlan::<16, 1>::new(3, Butterfly::Fused);
+    let mut naive_misses = 0;
+
+    // all twiddles of the first output are one, so its exact value is the plain sum over n
+    for _ in 0..2000 {
+        let x = to_posit(&signal(&mut rng, 3));
+        let (re, im) = to_f64(&x).iter().fold((0.0, 0.0), |(a, b), &(c, d)| (a + c, b + d));
+
+        assert_eq!(plan.inverse(x.view())[0], C::from((re / 3.0, im / 3.0)));
+        naive_misses += (Posit::<16, 1>::from(re) / Posit::from(3.0) != Posit::from(re / 3.0)) as usize;
+    }
+
+    assert!(naive_misses > 0);
+}
+
+#[cfg(feature = "half")]
+#[test]
+fn posit_vs_f16_test() {
+    use half::f16;
+
+    let mut rng = XorShift::new(6);
+    let x = signal(&mut rng, 64);
+    let reference = dft(&x);
+
+    let p16 = to_f64(&fft::fft(to_posit(&x).view()));
+    let x16: Vec<(f16, f16)> = x.iter().map(|&(a, b)| (f16::from_f64(a), f16::from_f64(b))).collect();
+    let f16_out: Vec<(f64, f64)> = fft::fft_float(&x16, false).into_iter().map(|(a, b)| (a.to_f64(), b.to_f64())).collect();
+
+    let (snr_p16, snr_f16) = (fft::snr_db(&reference, &p16), fft::snr_db(&reference, &f16_out));
+    assert!(snr_f16 > 40.0 && snr_p16 > snr_f16, "{} vs {}", snr_p16, snr_f16);
+
+    let back: Vec<(f64, f64)> = fft::fft_float(&fft::fft_float(&x16, false), true).into_iter().map(|(a, b)| (a.to_f64(), b.to_f64())).collect();
+    assert!(fft::snr_db(&x, &back) > 40.0, "{}", fft::snr_db(&x, &back));
+}
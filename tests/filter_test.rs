@@ -0,0 +1,127 @@
+use namagiri::posit::Posit;
+use namagiri::filter::{self, Biquad, ConvMode, Filter, Fir, Sos};
+use namagiri::gemm::fused_dot;
+use namagiri::rng::XorShift;
+use ndarray::{array, s, Array1};
+
+type P = Posit<16, 1>;
+
+fn noise(rng: &mut XorShift, n: usize) -> Array1<P> {
+    (0..n).map(|_| P::from(rng.next_normal())).collect()
+}
+
+#[test]
+fn fir_test() {
+    let mut rng = XorShift::new(1);
+    let taps: Vec<P> = (0..9).map(|_| P::from(rng.next_normal() * 0.3)).collect();
+    let x = noise(&mut rng, 100);
+
+    let y = Fir::new(taps.clone()).process_block(x.view());
+    let full = filter::convolve(x.view(), Array1::from(taps.clone()).view(), ConvMode::Full);
+    assert_eq!(y, full.slice(s![..100]));
+
+    // each output is a single fused dot over the window
+    let expected = fused_dot(P::from(0.0), (0..9).map(|k| (taps[k], x[50 - k])));
+    assert_eq!(y[50], expected);
+
+    // block boundaries do not matter
+    let mut fir = Fir::new(taps);
+    let mut chunked = fir.process_block(x.slice(s![..37])).to_vec();
+    chunked.extend(fir.process_block(x.slice(s![37..])));
+    assert_eq!(Array1::from(chunked), y);
+
+    fir.reset();
+    assert_eq!(fir.process_block(x.view()), y);
+}
+
+#[test]
+fn nar_test() {
+    let mut fir = Fir::new(vec![P::from(1.0); 3]);
+    let x: Array1<P> = array![1.0, 2.0, 3.0, 4.0, 5.0, 6.0].mapv(P::from);
+    let mut xn = x.clone();
+    xn[1] = P::NAR;
+
+    // a NaR sample poisons exactly the outputs whose window holds it
+    let y = fir.process_block(xn.view());
+    assert_eq!(y.iter().map(|p| p.is_nar()).collect::<Vec<_>>(), vec![false, true, true, true, false, false]);
+    assert_eq!(y[5], P::from(15.0));
+
+    // but stays in the feedback of a recursive filter until reset
+    let mut iir = Biquad::new([P::from(1.0), P::from(0.0), P::from(0.0)], [P::from(-0.5), P::from(0.0)]);
+    assert!(iir.process_block(xn.view()).iter().skip(1).all(|p| p.is_nar()));
+    iir.reset();
+    assert!(!iir.process_block(x.view()).iter().any(|p| p.is_nar()));
+}
+
+// same recursion in f64
+fn biquad_f64(b: [f64; 3], a: [f64; 3], x: &[f64]) -> Vec<f64> {
+    let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+    x.iter().map(|&v| {
+        let y = (b[0] * v + b[1] * x1 + b[2] * x2 - a[1] * y1 - a[2] * y2) / a[0];
+        x2 = x1;
+        x1 = v;
+        y2 = y1;
+        y1 = y;
+        y
+    }).collect()
+}
+
+#[test]
+fn biquad_test() {
+    let mut rng = XorShift::new(2);
+    let x = noise(&mut rng, 500);
+    let x64: Vec<f64> = x.iter().map(|&p| p.into()).collect();
+
+    // a lowpass section and a resonant bandpass section
+    let lp = ([0.0675, 0.135, 0.0675], [1.0, -1.143, 0.4128]);
+    let bp = ([0.2, 0.0, -0.2], [2.0, -2.4, 1.6]);
+
+    let mut sos = Sos{sections: vec![Biquad::from_f64(lp.0, lp.1), Biquad::from_f64(bp.0, bp.1)]};
+    let y: Vec<f64> = sos.process_block(x.view()).iter().map(|&p| p.into()).collect();
+    let reference = biquad_f64(bp.0, bp.1, &biquad_f64(lp.0, lp.1, &x64));
+
+    let err = y.iter().zip(&reference).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+    let peak = reference.iter().map(|v| v.abs()).fold(0.0, f64::max);
+    assert!(err < 1e-2 * peak, "{} vs {}", err, peak);
+
+    // streaming and block processing agree
+    sos.reset();
+    let streamed: Vec<P> = x.iter().map(|&v| sos.process(v)).collect();
+    sos.reset();
+    assert_eq!(Array1::from(streamed), sos.process_block(x.view()));
+}
+
+#[test]
+fn convolve_test() {
+    let x: Array1<P> = array![1.0, 2.0, 3.0, 4.0].mapv(P::from);
+    let h: Array1<P> = array![1.0, 0.5, 0.25].mapv(P::from);
+    let f = |v: Vec<f64>| Array1::from(v).mapv(P::from);
+
+    assert_eq!(filter::convolve(x.view(), h.view(), ConvMode::Full), f(vec![1.0, 2.5, 4.25, 6.0, 2.75, 1.0]));
+    assert_eq!(filter::convolve(x.view(), h.view(), ConvMode::Same), f(vec![2.5, 4.25, 6.0, 2.75]));
+    assert_eq!(filter::convolve(x.view(), h.view(), ConvMode::Valid), f(vec![4.25, 6.0]));
+
+    // valid mode is symmetric in its arguments
+    assert_eq!(filter::convolve(h.view(), x.view(), ConvMode::Valid), f(vec![4.25, 6.0]));
+    assert_eq!(filter::convolve(x.view(), Array1::zeros(0).view(), ConvMode::Full).len(), 0);
+}
+
+#[test]
+fn posit32_test() {
+    type Q = Posit<32, 2>;
+
+    let mut fir = Fir::new(vec![Q::from(0.5); 2]);
+    let y = fir.process_block(Array1::from_elem(4, Q::from(2.0)).view());
+    assert_eq!(y, array![1.0, 2.0, 2.0, 2.0].mapv(Q::from));
+
+    // a one-pole smoother settles exactly on a constant input
+    let mut smooth = Biquad::new([Q::from(0.5), Q::from(0.0), Q::from(0.0)], [Q::from(-0.5), Q::from(0.0)]);
+    let y = smooth.process_block(Array1::from_elem(40, Q::from(3.0)).view());
+    assert_eq!(y[0], Q::from(1.5));
+    assert_eq!(y[1], Q::from(2.25));
+    assert_eq!(y[39], Q::from(3.0));
+
+    let x: Array1<Q> = array![1.0, 2.0, 3.0, 4.0].mapv(Q::from);
+    let h: Array1<Q> = array![1.0, 0.5, 0.25].mapv(Q::from);
+    assert_eq!(filter::convolve(x.view(), h.view(), ConvMode::Full), array![1.0, 2.5, 4.25, 6.0, 2.75, 1.0].mapv(Q::from));
+}
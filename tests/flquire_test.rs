@@ -1,5 +1,8 @@
+use num_traits::identities::{One, Zero};
 use namagiri::posit::Posit;
 use namagiri::flquire::FLQuire;
+use namagiri::quire::Quire;
+use namagiri::window::{Window, Wide};
 
 #[test]
 fn to_from_test() {
@@ -43,4 +46,191 @@ fn mul_test() {
 #[test]
 fn add_test() {
     op_test(|x, y| x + y, |x, y| x + y)
-}
\ No newline at end of file
+}
+
+#[test]
+fn div_test() {
+    for i in 0..0b100000000 {
+        let a = Posit::<8, 1>(i);
+        let qa: FLQuire<8, 1, 20> = a.into();
+        for j in 0..0b100000000 {
+            let b = Posit::<8, 1>(j);
+            if a.is_nar() || b.is_nar() || b.is_zero() {
+                continue;
+            }
+            let qb: FLQuire<8, 1, 20> = b.into();
+            let c = Posit::<8, 1>::from(f32::from(a) / f32::from(b));
+
+            assert_eq!(qa / b, c);
+            assert_eq!(Posit::<8, 1>::from(qa / qb), c);
+        }
+        if a.is_nar() {
+            continue;
+        }
+        for n in 1..20 {
+            assert_eq!(qa.div_int(n), Posit::<8, 1>::from(f32::from(a) / n as f32));
+        }
+        assert_eq!(Posit::<8, 1>::from(qa.scale(-2)), Posit::<8, 1>::from(f32::from(a) / 4.0));
+    }
+}
+
+fn xorshift(state: &mut u64) -> u32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state >> 32) as u32
+}
+
+fn random_posits(state: &mut u64, len: usize) -> Vec<Posit<8, 1>> {
+    (0..len)
+        .map(|_| Posit::<8, 1>(xorshift(state) & 0xff))
+        .map(|p| if p.is_nar() {Posit::<8, 1>(0)} else {p})
+        .collect()
+}
+
+fn dot_q(a: &[Posit<8, 1>], b: &[Posit<8, 1>]) -> Posit<8, 1> {
+    a.iter().zip(b).fold(Quire::<8, 1>::default(), |acc, (&x, &y)| acc + Quire::from(x) * Quire::from(y)).into()
+}
+
+fn dot_fl<const SIZE: u16, W: Window>(a: &[Posit<8, 1>], b: &[Posit<8, 1>]) -> Posit<8, 1> {
+    a.iter().zip(b).fold(FLQuire::<8, 1, SIZE, W>::default(), |acc, (&x, &y)| acc + FLQuire::from(x) * FLQuire::from(y)).into()
+}
+
+#[test]
+fn dot_against_quire_test() {
+    let mut state = 0x9e3779b97f4a7c15;
+    for len in [2, 3, 8, 64, 256] {
+        for _ in 0..2000 {
+            let a = random_posits(&mut state, len);
+            let b = random_posits(&mut state, len);
+            assert_eq!(dot_fl::<64, u128>(&a, &b), dot_q(&a, &b));
+        }
+    }
+}
+
+#[test]
+fn fma_against_quire_test() {
+    let mut state = 0x2545f4914f6cdd1d;
+    for _ in 0..200000 {
+        let v = random_posits(&mut state, 3);
+        let (a, b, c) = (v[0], v[1], v[2]);
+
+        let q: Posit<8, 1> = (Quire::from(a) * Quire::from(b) + Quire::from(c)).into();
+        let fl: Posit<8, 1> = (FLQuire::<8, 1, 20>::from(a) * FLQuire::from(b) + FLQuire::from(c)).into();
+        assert_eq!(fl, q);
+
+        let q: Posit<8, 1> = (Quire::from(c) - Quire::from(a) * Quire::from(b)).into();
+        let fl: Posit<8, 1> = (FLQuire::<8, 1, 20>::from(c) - FLQuire::from(a) * FLQuire::from(b)).into();
+        assert_eq!(fl, q);
+    }
+}
+
+#[test]
+fn sticky_test() {
+    let one = FLQuire::<8, 1, 12>::from(Posit::<8, 1>::one());
+    let tiny = FLQuire::<8, 1, 12>::from(Posit::<8, 1>::MINPOS);
+
+    assert!(!(one + one).sticky);
+    assert!((one + tiny).sticky);
+    assert!((one - tiny).sticky);
+    assert!((-(one + tiny)).sticky);
+    assert_eq!(Posit::<8, 1>::from(one + tiny), Posit::<8, 1>::one());
+    assert_eq!(Posit::<8, 1>::from(one - tiny), Posit::<8, 1>::one());
+    assert_eq!(Posit::<8, 1>::from(tiny - one), -Posit::<8, 1>::one());
+}
+
+fn dot_quire(a: &[Posit<8, 1>], b: &[Posit<8, 1>]) -> Quire<8, 1> {
+    a.iter().zip(b).fold(Quire::<8, 1>::default(), |acc, (&x, &y)| acc + Quire::from(x) * Quire::from(y))
+}
+
+#[test]
+fn quire_round_trip_test() {
+    let mut state = 0x853c49e6748fea9b;
+    for len in [1, 2, 16, 128] {
+        for _ in 0..2000 {
+            let a = random_posits(&mut state, len);
+            let b = random_posits(&mut state, len);
+            let q = dot_quire(&a, &b);
+
+            let wide = FLQuire::<8, 1, 64>::from(q);
+            assert_eq!(Quire::from(wide).0, q.0);
+            assert_eq!(FLQuire::<8, 1, 64>::from(Quire::from(wide)), wide);
+
+            let narrow = FLQuire::<8, 1, 12>::from(q);
+            assert_eq!(Posit::<8, 1>::from(narrow), Posit::<8, 1>::from(q));
+            assert_eq!(FLQuire::<8, 1, 12>::from(Quire::from(narrow)).quire, narrow.quire);
+        }
+    }
+
+    for i in 0..0b100000000 {
+        let a = Posit::<8, 1>(i);
+        if a.is_nar() {
+            continue;
+        }
+        let fl = FLQuire::<8, 1, 20>::from(a);
+        assert_eq!(Quire::from(fl).0, Quire::from(a).0);
+        assert_eq!(FLQuire::<8, 1, 20>::from(Quire::from(fl)), fl);
+    }
+}
+
+#[test]
+fn wide_window_test() {
+    let mut state = 0x3c6ef372fe94f82b;
+    for len in [2, 8, 64] {
+        for _ in 0..500 {
+            let a = random_posits(&mut state, len);
+            let b = random_posits(&mut state, len);
+            assert_eq!(dot_fl::<20, Wide<1>>(&a, &b), dot_fl::<20, u128>(&a, &b));
+            assert_eq!(dot_fl::<64, Wide<2>>(&a, &b), dot_q(&a, &b));
+            assert_eq!(dot_fl::<100, Wide<2>>(&a, &b), dot_q(&a, &b));
+        }
+    }
+}
+
+#[test]
+fn wide_posit16_test() {
+    let mut state = 0xa54ff53a5f1d36f1;
+    for _ in 0..500 {
+        // keep products inside the range the u128 quire can hold
+        let v: Vec<Posit<16, 1>> = (0..32)
+            .map(|_| (xorshift(&mut state) as i32 as f32) * 2f32.powi((xorshift(&mut state) % 24) as i32 - 51))
+            .map(Posit::<16, 1>::from)
+            .collect();
+        let q = v.chunks(2).fold(Quire::<16, 1>::default(), |acc, p| acc + Quire::from(p[0]) * Quire::from(p[1]));
+        let fl = v.chunks(2).fold(FLQuire::<16, 1, 150, Wide<3>>::default(), |acc, p| acc + FLQuire::from(p[0]) * FLQuire::from(p[1]));
+        assert_eq!(Posit::<16, 1>::from(fl), Posit::<16, 1>::from(q));
+        assert_eq!(Quire::<16, 1>::from(fl).0, q.0);
+    }
+}
+
+#[test]
+fn wide_posit32_test() {
+    type F = FLQuire<32, 2, 300, Wide<5>>;
+    let big = Posit::<32, 2>::from(2f32.powi(60));
+    let one = Posit::<32, 2>::one();
+    let tiny = Posit::<32, 2>::from(2f32.powi(-60));
+
+    assert_eq!(Posit::<32, 2>::from(F::from(big) + F::from(one) - F::from(big)), one);
+    assert_eq!(Posit::<32, 2>::from(F::from(big) * F::from(tiny) + F::from(one)), Posit::<32, 2>::from(2.0f32));
+    assert_eq!(Posit::<32, 2>::from(F::from(big) + F::from(tiny) - F::from(big)), tiny);
+}
+
+#[test]
+fn nar_test() {
+    let one = FLQuire::<8, 1, 20>::from(Posit::<8, 1>::from(1.0));
+    let nar = FLQuire::<8, 1, 20>::from(Posit::<8, 1>::NAR);
+
+    // dividing by zero gives NaR, as it does for the quire
+    assert!((one / FLQuire::zero()).is_nar());
+    assert!((FLQuire::<8, 1, 20>::zero() / FLQuire::zero()).is_nar());
+    assert!(Quire::from(one / FLQuire::zero()).is_nar());
+
+    // and NaR stays NaR through arithmetic and every way out
+    assert!(nar.is_nar() && !one.is_nar() && !FLQuire::<8, 1, 20>::zero().is_nar());
+    assert!((nar + one).is_nar() && (one - nar).is_nar() && (-nar).is_nar());
+    assert!((nar * FLQuire::zero()).is_nar() && (nar / one).is_nar() && nar.scale(3).is_nar());
+    assert!(Posit::<8, 1>::from(nar).is_nar() && Posit::<8, 1>::from(nar / FLQuire::zero()).is_nar());
+    assert!(nar.div_int(3).is_nar() && nar.div_posit(Posit::from(2.0)).is_nar());
+    assert!(FLQuire::<8, 1, 20>::from(Quire::<8, 1>::NAR).is_nar());
+    assert!(FLQuire::<8, 1, 40, Wide<2>>::from(Posit::<8, 1>::NAR).is_nar());
+}
@@ -0,0 +1,103 @@
+use namagiri::posit::Posit;
+use namagiri::quire::Quire;
+use namagiri::flquire::FLQuire;
+use namagiri::window::Wide;
+use namagiri::accumulator;
+use namagiri::gemm::{self, GemmConfig, Trans};
+use namagiri::rng::XorShift;
+use ndarray::{Array1, Array2};
+
+type P = Posit<8, 1>;
+
+fn random_matrix(rng: &mut XorShift, rows: usize, cols: usize) -> Array2<P> {
+    Array2::from_shape_fn((rows, cols), |_| {
+        let p = P::new(rng.next_u32() & 0xff);
+        if p.is_nar() {P::new(0)} else {p}
+    })
+}
+
+#[test]
+fn gemm_against_quire_test() {
+    let mut rng = XorShift::new(7);
+    for (m, k, n) in [(1, 1, 1), (3, 5, 2), (17, 40, 9), (70, 300, 33)] {
+        let a = random_matrix(&mut rng, m, k);
+        let b = random_matrix(&mut rng, k, n);
+        let q = accumulator::matmul::<_, Quire<8, 1>>(a.view(), b.view());
+
+        assert_eq!(gemm::gemm(Trans::No, a.view(), Trans::No, b.view()), q);
+        assert_eq!(gemm::gemm(Trans::Yes, a.t().to_owned().view(), Trans::No, b.view()), q);
+        assert_eq!(gemm::gemm(Trans::No, a.view(), Trans::Yes, b.t().to_owned().view()), q);
+        assert_eq!(gemm::gemm(Trans::Yes, a.t(), Trans::Yes, b.t()), q);
+    }
+}
+
+#[test]
+fn deterministic_test() {
+    let mut rng = XorShift::new(11);
+    let a = random_matrix(&mut rng, 45, 130);
+    let b = random_matrix(&mut rng, 130, 50);
+    let c = gemm::gemm(Trans::No, a.view(), Trans::No, b.view());
+
+    for (mc, nc, kc) in [(1, 1, 1), (4, 7, 3), (16, 16, 64), (100, 100, 1000)] {
+        for parallel in [false, true] {
+            let cfg = GemmConfig{mc, nc, kc, parallel};
+            assert_eq!(gemm::gemm_with(cfg, Trans::No, a.view(), Trans::No, b.view()), c);
+        }
+    }
+}
+
+#[test]
+fn gemv_test() {
+    let mut rng = XorShift::new(13);
+    let a = random_matrix(&mut rng, 37, 90);
+    let x = random_matrix(&mut rng, 90, 1);
+    let c = gemm::gemm(Trans::No, a.view(), Trans::No, x.view());
+
+    assert_eq!(gemm::gemv(Trans::No, a.view(), x.column(0)), c.column(0));
+    assert_eq!(gemm::gemv(Trans::Yes, a.t(), x.column(0)), c.column(0));
+}
+
+#[test]
+fn nar_test() {
+    let mut a = Array2::from_elem((3, 2), P::from(1.0));
+    let b = Array2::from_elem((2, 4), P::from(2.0));
+    a[[1, 0]] = P::NAR;
+
+    let c = gemm::gemm(Trans::No, a.view(), Trans::No, b.view());
+    for ((i, _), &x) in c.indexed_iter() {
+        assert_eq!(x, if i == 1 {P::NAR} else {P::from(4.0)});
+    }
+
+    let y = gemm::gemv(Trans::Yes, b.view(), Array1::from_elem(2, P::NAR).view());
+    assert!(y.iter().all(|p| p.is_nar()));
+}
+
+#[test]
+fn posit16_test() {
+    let mut rng = XorShift::new(17);
+    let a = Array2::from_shape_fn((20, 60), |_| Posit::<16, 1>::from(rng.next_normal() as f32));
+    let b = Array2::from_shape_fn((60, 10), |_| Posit::<16, 1>::from(rng.next_normal() as f32));
+
+    assert_eq!(gemm::gemm(Trans::No, a.view(), Trans::No, b.view()), accumulator::matmul::<_, Quire<16, 1>>(a.view(), b.view()));
+}
+
+#[test]
+fn posit32_test() {
+    type P32 = Posit<32, 2>;
+
+    // the quire of posit32 outgrows 128 bits, small dyadic entries keep the f64 reference exact
+    let t = Array2::from_shape_fn((6, 6), |(i, j)| match j as i64 - i as i64 {0 => 2.0, -1 => -0.25, 1 => 0.5, _ => 0.0});
+    let tp = t.mapv(P32::from);
+    let tt = gemm::gemm(Trans::No, tp.view(), Trans::No, tp.view());
+    assert_eq!(tt, t.dot(&t).mapv(P32::from));
+    assert_eq!(tt[[2, 2]], P32::from(3.75));
+
+    let mut rng = XorShift::new(19);
+    let a = Array2::from_shape_fn((20, 70), |_| P32::from(rng.next_normal()));
+    let b = Array2::from_shape_fn((70, 12), |_| P32::from(rng.next_normal()));
+    let q = accumulator::matmul::<_, FLQuire<32, 2, 400, Wide<8>>>(a.view(), b.view());
+
+    assert_eq!(gemm::gemm(Trans::No, a.view(), Trans::No, b.view()), q);
+    assert_eq!(gemm::gemm_with(GemmConfig{mc: 3, nc: 5, kc: 16, parallel: false}, Trans::Yes, a.t(), Trans::No, b.view()), q);
+    assert_eq!(gemm::gemv(Trans::No, a.view(), b.column(3)), q.column(3));
+}
@@ -0,0 +1,221 @@
+use namagiri::posit::Posit;
+use namagiri::interval::{self, Interval};
+use namagiri::rng::XorShift;
+use namagiri::sizing::{ulp_distance, Distribution};
+use ndarray::Array1;
+
+type P = Posit<8, 1>;
+type I = Interval<8, 1>;
+
+// the interval operation and its real counterpart
+type Op = (fn(I, I) -> I, fn(f64, f64) -> f64);
+
+const OPS: [Op; 4] = [
+    (|a, b| a + b, |x, y| x + y),
+    (|a, b| a - b, |x, y| x - y),
+    (|a, b| a * b, |x, y| x * y),
+    (|a, b| a / b, |x, y| x / y),
+];
+
+fn p(v: f64) -> P {
+    P::from(v)
+}
+
+fn iv(lo: f64, hi: f64) -> I {
+    I::new(p(lo), p(hi))
+}
+
+fn random(rng: &mut XorShift) -> I {
+    let (a, b) = (Distribution::Bits.sample::<8, 1>(rng), Distribution::Bits.sample::<8, 1>(rng));
+    if a <= b {I::new(a, b)} else {I::new(b, a)}
+}
+
+// endpoints and a few interior points of a bounded interval
+fn samples(x: I, rng: &mut XorShift) -> Vec<f64> {
+    match x {
+        Interval::Arc{lo, hi} => {
+            let (lo, hi) = (f64::from(lo), f64::from(hi));
+            let mut v = vec![lo, hi];
+            v.extend((0..4).map(|_| lo + (hi - lo) * rng.next_f64()));
+            v
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn containment_test() {
+    let mut rng = XorShift::new(1);
+
+    for _ in 0..2000 {
+        let (a, b) = (random(&mut rng), random(&mut rng));
+        let (xs, ys) = (samples(a, &mut rng), samples(b, &mut rng));
+
+        for (op, f) in OPS.iter() {
+            let r = op(a, b);
+            for &x in &xs {
+                for &y in &ys {
+                    let v = f(x, y);
+                    if !v.is_nan() {
+                        assert!(r.contains_f64(v), "{} and {} give {} without {}", a, b, r, v);
+                    }
+                }
+            }
+        }
+
+        let r = a.sqrt();
+        for &x in xs.iter().filter(|&&x| x >= 0.0) {
+            assert!(r.contains_f64(x.sqrt()), "sqrt {} gives {} without {}", a, r, x.sqrt());
+        }
+    }
+}
+
+#[test]
+fn tightness_test() {
+    let mut rng = XorShift::new(2);
+
+    // point operands give the two posits around the exact result, or the result itself
+    for _ in 0..5000 {
+        let (x, y) = (Distribution::Bits.sample::<8, 1>(&mut rng), Distribution::Bits.sample::<8, 1>(&mut rng));
+        if y == P::from(0.0) {
+            continue;
+        }
+
+        for (op, f) in OPS.iter() {
+            let v = f(f64::from(x), f64::from(y));
+            match op(I::point(x), I::point(y)) {
+                Interval::Arc{lo, hi} if v.abs() <= 4096.0 => {
+                    assert!(f64::from(lo) <= v && v <= f64::from(hi));
+                    assert_eq!(ulp_distance(lo, hi), if f64::from(p(v)) == v {0} else {1}, "{} {} {}", x, y, v);
+                },
+                r => assert!(r.contains_f64(v)),
+            }
+        }
+    }
+
+    assert_eq!(iv(4.0, 9.0).sqrt(), iv(2.0, 3.0));
+    match I::from_f64(0.1, 0.1) {
+        Interval::Arc{lo, hi} => assert!(f64::from(lo) < 0.1 && 0.1 < f64::from(hi) && ulp_distance(lo, hi) == 1),
+        _ => unreachable!(),
+    }
+    assert_eq!(I::from_f64(1.0, 1e9), I::new(p(1.0), P::NAR));
+}
+
+#[test]
+fn wraparound_test() {
+    // 1 / x turns the circle around: through zero becomes through infinity
+    let r = iv(-1.0, 1.0).recip();
+    assert_eq!(r, I::new(p(1.0), p(-1.0)));
+    assert!(r.contains_f64(100.0) && r.contains_f64(-100.0) && r.contains_f64(f64::INFINITY));
+    assert!(!r.contains_f64(0.5) && !r.contains(p(0.0)));
+    assert_eq!(r.recip(), iv(-1.0, 1.0));
+
+    assert_eq!(iv(1.0, 2.0) / iv(-1.0, 1.0), I::new(p(1.0), p(-1.0)));
+    assert_eq!(iv(1.0, 2.0) / iv(0.0, 2.0), I::new(p(0.5), P::NAR));
+    assert_eq!(iv(1.0, 2.0) / iv(-2.0, 0.0), I::new(P::NAR, p(-0.5)));
+    assert_eq!(iv(1.0, 2.0) / iv(0.0, 0.0), I::NAR);
+    assert_eq!(iv(-1.0, 2.0) / iv(0.0, 0.0), I::Everything);
+    assert_eq!(iv(3.0, 4.0) / I::NAR, iv(0.0, 0.0));
+
+    // infinity plus infinity and infinity times zero are indeterminate
+    assert_eq!(I::NAR + iv(1.0, 2.0), I::NAR);
+    assert_eq!(I::NAR + I::new(p(1.0), p(-1.0)), I::Everything);
+    assert_eq!(I::NAR * iv(1.0, 2.0), I::NAR);
+    assert_eq!(I::NAR * iv(-1.0, 2.0), I::Everything);
+    assert_eq!(I::new(P::NAR, p(1.0)) + I::new(p(1.0), P::NAR), I::Everything);
+    assert_eq!(I::new(P::NAR, p(1.0)) + I::new(P::NAR, p(2.0)), I::new(P::NAR, p(3.0)));
+    assert_eq!(I::new(p(2.0), P::NAR) * iv(-2.0, -1.0), I::new(P::NAR, p(-2.0)));
+
+    // a wrapped arc times a positive interval stays wrapped
+    let w = I::new(p(2.0), p(-4.0)) * iv(0.5, 1.0);
+    assert_eq!(w, I::new(p(1.0), p(-2.0)));
+
+    // square roots keep the non-negative part only
+    assert_eq!(iv(-4.0, 2.0).sqrt(), iv(0.0, 1.4375));
+    assert_eq!(iv(-4.0, -1.0).sqrt(), I::Empty);
+    assert_eq!(I::new(p(4.0), p(-1.0)).sqrt(), I::new(p(2.0), P::NAR));
+    assert_eq!(I::NAR.sqrt(), I::NAR);
+
+    assert_eq!(iv(1.0, 2.0).hull(iv(4.0, 8.0)), iv(1.0, 8.0));
+    // the shorter way round the circle may pass through infinity
+    assert_eq!(iv(1.0, 2.0).hull(iv(-8.0, -4.0)), I::new(p(1.0), p(-4.0)));
+    assert_eq!(iv(1.0, 2.0).hull(iv(-0.5, -0.25)), iv(-0.5, 2.0));
+    assert_eq!(I::new(p(1.0), p(-4.0)).hull(iv(-8.0, -2.0)), I::new(p(1.0), p(-2.0)));
+    assert_eq!(I::new(p(1.0), p(-1.0)).hull(iv(-2.0, 2.0)), I::Everything);
+
+    assert_eq!(format!("{} {} {} {}", iv(-1.5, 2.0), I::new(P::NAR, p(1.0)), I::new(p(1.0), P::NAR), I::NAR), "[-1.5, 2] [-inf, 1] [1, inf] [NaR]");
+}
+
+#[test]
+fn dot_test() {
+    let mut rng = XorShift::new(3);
+
+    // the quire bound rounds once, the interval fold rounds at every step
+    for _ in 0..200 {
+        let a: Array1<I> = (0..16).map(|_| I::point(p(rng.next_normal()))).collect();
+        let b: Array1<I> = (0..16).map(|_| I::point(p(rng.next_normal()))).collect();
+        let exact: f64 = a.iter().zip(&b).map(|(x, y)| match (x, y) {
+            (Interval::Arc{lo: x, ..}, Interval::Arc{lo: y, ..}) => f64::from(*x) * f64::from(*y),
+            _ => unreachable!(),
+        }).sum();
+
+        let tight = interval::dot(a.view(), b.view());
+        let naive = a.iter().zip(&b).fold(I::point(p(0.0)), |acc, (&x, &y)| acc + x * y);
+
+        assert!(tight.contains_f64(exact) && naive.contains_f64(exact));
+        assert!(tight.width() <= naive.width());
+        match tight {
+            Interval::Arc{lo, hi} => assert!(ulp_distance(lo, hi) <= 1),
+            _ => unreachable!(),
+        }
+    }
+
+    // wide operands: every product of the enclosed reals is enclosed
+    let a: Array1<I> = (0..8).map(|_| random(&mut rng)).collect();
+    let b: Array1<I> = (0..8).map(|_| random(&mut rng)).collect();
+    let r = interval::dot(a.view(), b.view());
+    for _ in 0..100 {
+        let v: f64 = a.iter().zip(&b).map(|(&x, &y)| {
+            let (xs, ys) = (samples(x, &mut rng), samples(y, &mut rng));
+            xs[2] * ys[2]
+        }).sum();
+        assert!(r.contains_f64(v));
+    }
+
+    // unbounded terms fall back to interval arithmetic
+    let a = Array1::from(vec![iv(1.0, 2.0), I::new(p(1.0), P::NAR)]);
+    let b = Array1::from(vec![iv(1.0, 1.0), iv(1.0, 1.0)]);
+    assert_eq!(interval::dot(a.view(), b.view()), I::new(p(2.0), P::NAR));
+}
+
+#[test]
+fn posit32_test() {
+    type Q = Posit<32, 2>;
+    type J = Interval<32, 2>;
+
+    let a = Array1::from_elem(2, J::from_f64(1.0, 2.0));
+    assert_eq!(interval::dot(a.view(), a.view()), J::new(Q::from(2.0), Q::from(8.0)));
+    assert_eq!(J::from_f64(1.0, 2.0) + J::from_f64(0.5, 0.5), J::new(Q::from(1.5), Q::from(2.5)));
+
+    // inexact results enclose the real value within one step either side
+    let third = J::point(Q::from(1.0)) / J::point(Q::from(3.0));
+    assert!(third.contains_f64(1.0 / 3.0) && third.width() < 1e-8 && third.width() > 0.0);
+    let root = J::point(Q::from(2.0)).sqrt();
+    assert!(root.contains_f64(2f64.sqrt()) && root.width() < 1e-8 && root.width() > 0.0);
+
+    let mut rng = XorShift::new(4);
+    let a: Array1<J> = (0..16).map(|_| J::point(Q::from(rng.next_normal()))).collect();
+    let b: Array1<J> = (0..16).map(|_| J::point(Q::from(rng.next_normal()))).collect();
+    let exact: f64 = a.iter().zip(&b).map(|(x, y)| match (x, y) {
+        (Interval::Arc{lo: x, ..}, Interval::Arc{lo: y, ..}) => f64::from(*x) * f64::from(*y),
+        _ => unreachable!(),
+    }).sum();
+    let r = interval::dot(a.view(), b.view());
+    assert!(r.contains_f64(exact) && r.width() < 1e-7, "{} without {}", r, exact);
+
+    let (x, y) = (J::from_f64(-1.5, 0.25), J::from_f64(2.0, 3.0));
+    for (u, v) in [(-1.5, 2.0), (0.25, 3.0), (-0.7, 2.9)] {
+        assert!((x + y).contains_f64(u + v) && (x - y).contains_f64(u - v));
+        assert!((x * y).contains_f64(u * v) && (x / y).contains_f64(u / v));
+    }
+}
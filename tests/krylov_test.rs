@@ -0,0 +1,154 @@
+use namagiri::posit::Posit;
+use namagiri::accumulator::Naive;
+use namagiri::krylov::{self, FnOperator, LinearOperator, SolverConfig};
+use namagiri::linalg::LinalgError;
+use namagiri::rng::XorShift;
+use ndarray::{Array1, Array2, ArrayView1};
+
+type P = Posit<16, 1>;
+
+// shifted 1-D Laplacian, symmetric positive definite
+fn laplacian(n: usize) -> Array2<f64> {
+    Array2::from_shape_fn((n, n), |(i, j)| if i == j {2.5} else if i.abs_diff(j) == 1 {-1.0} else {0.0})
+}
+
+// nonsymmetric and diagonally dominant
+fn convection(rng: &mut XorShift, n: usize) -> Array2<f64> {
+    Array2::from_shape_fn((n, n), |(i, j)| if i == j {4.0} else if j == i + 1 {-1.5} else if i == j + 1 {-0.5} else {0.1 * rng.next_normal()})
+}
+
+fn true_residual(a: &Array2<f64>, x: &Array1<f64>, b: &Array1<f64>) -> f64 {
+    let r: Array1<f64> = a.dot(x) - b;
+    r.dot(&r).sqrt() / b.dot(b).sqrt()
+}
+
+fn posit_run(a: &Array2<f64>, b: &Array1<f64>) -> (Array2<P>, Array1<P>) {
+    (a.mapv(P::from), b.mapv(P::from))
+}
+
+#[test]
+fn cg_test() {
+    let mut rng = XorShift::new(7);
+    let a = laplacian(40);
+    let b: Array1<f64> = (0..40).map(|_| rng.next_normal()).collect();
+    let cfg = SolverConfig{tol: 1e-3, max_iter: 100};
+
+    let (ap, bp) = posit_run(&a, &b);
+    let p = krylov::cg(&ap, bp.view(), cfg).unwrap();
+    assert!(p.converged, "{:?}", p.residuals);
+    assert!(true_residual(&a, &p.x.mapv(f64::from), &b) < 5e-3);
+
+    let f = krylov::cg(&a.mapv(|v| v as f32), b.mapv(|v| v as f32).view(), cfg).unwrap();
+    let d = krylov::cg(&a, b.view(), cfg).unwrap();
+    assert!(f.converged && d.converged);
+    assert!(p.residuals.len() <= d.residuals.len() + 3, "{} vs {}", p.residuals.len(), d.residuals.len());
+    assert_eq!(p.residuals[0], 1.0);
+}
+
+#[test]
+fn posit32_test() {
+    let mut rng = XorShift::new(8);
+    let a = laplacian(30);
+    let b: Array1<f64> = (0..30).map(|_| rng.next_normal()).collect();
+    let cfg = SolverConfig{tol: 1e-7, max_iter: 100};
+
+    // the posit32 quire needs more than 128 bits, the default accumulator still sums exactly
+    let p = krylov::cg(&a.mapv(Posit::<32, 2>::from), b.mapv(Posit::<32, 2>::from).view(), cfg).unwrap();
+    assert!(p.converged, "{:?}", p.residuals);
+    assert!(true_residual(&a, &p.x.mapv(f64::from), &b) < 1e-6);
+}
+
+#[test]
+fn bicgstab_test() {
+    let mut rng = XorShift::new(8);
+    let a = convection(&mut rng, 30);
+    let b: Array1<f64> = (0..30).map(|_| rng.next_normal()).collect();
+    let cfg = SolverConfig{tol: 1e-3, max_iter: 100};
+
+    let (ap, bp) = posit_run(&a, &b);
+    let p = krylov::bicgstab(&ap, bp.view(), cfg).unwrap();
+    assert!(p.converged, "{:?}", p.residuals);
+    assert!(true_residual(&a, &p.x.mapv(f64::from), &b) < 5e-3);
+
+    let d = krylov::bicgstab(&a, b.view(), SolverConfig{tol: 1e-12, max_iter: 100}).unwrap();
+    assert!(d.converged);
+    assert!(true_residual(&a, &d.x, &b) < 1e-10);
+}
+
+#[test]
+fn gmres_test() {
+    let mut rng = XorShift::new(9);
+    let a = convection(&mut rng, 30);
+    let b: Array1<f64> = (0..30).map(|_| rng.next_normal()).collect();
+    let cfg = SolverConfig{tol: 1e-3, max_iter: 200};
+
+    let (ap, bp) = posit_run(&a, &b);
+    for m in [5, 30] {
+        let p = krylov::gmres(&ap, bp.view(), m, cfg).unwrap();
+        assert!(p.converged, "{:?}", p.residuals);
+        assert!(true_residual(&a, &p.x.mapv(f64::from), &b) < 5e-3);
+    }
+
+    // full GMRES in f64 reaches rounding level within n steps
+    let d = krylov::gmres(&a, b.view(), 30, SolverConfig{tol: 1e-12, max_iter: 30}).unwrap();
+    assert!(d.converged);
+    assert!(true_residual(&a, &d.x, &b) < 1e-10);
+
+    // rounding every product loses to the quire
+    let q = krylov::gmres(&ap, bp.view(), 10, SolverConfig{tol: 0.0, max_iter: 60}).unwrap();
+    let n = krylov::gmres_with::<P, Naive<P>, _>(&ap, bp.view(), 10, SolverConfig{tol: 0.0, max_iter: 60}).unwrap();
+    let best = |r: &[f64]| r.iter().cloned().fold(f64::INFINITY, f64::min);
+    assert!(best(&q.residuals) <= best(&n.residuals));
+}
+
+#[test]
+fn matrix_free_test() {
+    let a = laplacian(25).mapv(P::from);
+    let b = Array1::from_elem(25, P::from(1.0));
+    let op = FnOperator{nrows: 25, ncols: 25, f: |x: ArrayView1<P>| a.apply(x)};
+    let cfg = SolverConfig{tol: 1e-3, max_iter: 100};
+
+    assert_eq!(krylov::cg(&op, b.view(), cfg), krylov::cg(&a, b.view(), cfg));
+    assert_eq!(krylov::bicgstab(&op, b.view(), cfg), krylov::bicgstab(&a, b.view(), cfg));
+    assert_eq!(krylov::gmres(&op, b.view(), 8, cfg), krylov::gmres(&a, b.view(), 8, cfg));
+}
+
+#[test]
+fn error_test() {
+    let a = laplacian(4).mapv(P::from);
+    let cfg = SolverConfig::default();
+
+    assert_eq!(krylov::cg(&a, Array1::from_elem(3, P::from(1.0)).view(), cfg), Err(LinalgError::Shape));
+    assert_eq!(krylov::gmres(&Array2::<P>::zeros((3, 4)), Array1::from_elem(3, P::from(1.0)).view(), 2, cfg), Err(LinalgError::Shape));
+
+    let mut b = Array1::from_elem(4, P::from(1.0));
+    b[2] = P::NAR;
+    assert_eq!(krylov::bicgstab(&a, b.view(), cfg), Err(LinalgError::NaR(0)));
+
+    let zero = krylov::cg(&a, Array1::zeros(4).view(), cfg).unwrap();
+    assert!(zero.converged && zero.x.iter().all(|p| *p == P::from(0.0)));
+
+    let mut c = a.clone();
+    c[[1, 2]] = P::NAR;
+    assert_eq!(krylov::cg(&c, Array1::from_elem(4, P::from(1.0)).view(), cfg), Err(LinalgError::NaR(1)));
+}
+
+#[cfg(feature = "half")]
+#[test]
+fn posit16_f16_test() {
+    use half::f16;
+
+    let mut rng = XorShift::new(11);
+    let a = laplacian(40);
+    let b: Array1<f64> = (0..40).map(|_| rng.next_normal()).collect();
+    let cfg = SolverConfig{tol: 1e-3, max_iter: 100};
+
+    let (ap, bp) = posit_run(&a, &b);
+    let p = krylov::cg(&ap, bp.view(), cfg).unwrap();
+    let h = krylov::cg(&a.mapv(f16::from_f64), b.mapv(f16::from_f64).view(), cfg).unwrap();
+
+    let (rp, rh) = (true_residual(&a, &p.x.mapv(f64::from), &b), true_residual(&a, &h.x.mapv(f16::to_f64), &b));
+    // the quire-exact dot products leave posit16 closer to the solution
+    assert!(p.converged && h.converged);
+    assert!(rp < rh, "{} vs {}", rp, rh);
+}
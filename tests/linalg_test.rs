@@ -0,0 +1,168 @@
+use namagiri::posit::Posit;
+use namagiri::linalg::{self, LinalgError, Triangle};
+use namagiri::quire::Quire;
+use namagiri::rng::XorShift;
+use ndarray::{array, Array1, Array2};
+
+type P = Posit<16, 1>;
+
+fn to_f64(a: &Array2<P>) -> Array2<f64> {
+    a.mapv(|p| f32::from(p) as f64)
+}
+
+fn max_diff(a: &Array2<f64>, b: &Array2<f64>) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).abs()).fold(0.0, f64::max)
+}
+
+// diagonally dominant, so pivots stay away from zero
+fn random_matrix(rng: &mut XorShift, n: usize) -> Array2<P> {
+    Array2::from_shape_fn((n, n), |(i, j)| P::from((rng.next_normal() + if i == j {4.0} else {0.0}) as f32))
+}
+
+#[test]
+fn lu_test() {
+    let mut rng = XorShift::new(1);
+    for n in [1, 2, 5, 12] {
+        let a = random_matrix(&mut rng, n);
+        let f = linalg::lu(a.view()).unwrap();
+
+        let pa = Array2::from_shape_fn((n, n), |(i, j)| a[[f.perm[i], j]]);
+        assert!(max_diff(&to_f64(&f.l()).dot(&to_f64(&f.u())), &to_f64(&pa)) < 1e-2);
+
+        let b: Array1<P> = (0..n).map(|_| P::from(rng.next_normal() as f32)).collect();
+        let x = f.solve(b.view()).unwrap();
+        let r = to_f64(&a).dot(&x.mapv(|p| f32::from(p) as f64)) - b.mapv(|p| f32::from(p) as f64);
+        assert!(r.iter().all(|v| v.abs() < 1e-2));
+
+        let ai = f.inv().unwrap();
+        assert!(max_diff(&to_f64(&a).dot(&to_f64(&ai)), &Array2::eye(n)) < 1e-2);
+    }
+}
+
+#[test]
+fn lu_single_rounding_test() {
+    // every entry of L and U is its exact Crout sum rounded once, and each pivot
+    // is the largest of the exact sums in its column
+    let mut rng = XorShift::new(8);
+    for n in [3, 6, 10] {
+        let a = Array2::from_shape_fn((n, n), |_| P::from(rng.next_normal() as f32));
+        let f = linalg::lu(a.view()).unwrap();
+        let (l, u) = (f.l(), f.u());
+
+        for j in 0..n {
+            let sum = |i: usize| (0..i.min(j)).fold(Quire::from(a[[f.perm[i], j]]), |q, k| q - Quire::from(l[[i, k]]) * Quire::from(u[[k, j]]));
+            for i in 0..n {
+                if i <= j {
+                    assert_eq!(u[[i, j]], P::from(sum(i)));
+                }
+                else {
+                    assert_eq!(l[[i, j]], sum(i).div_posit(u[[j, j]]));
+                    assert!((sum(i).0 as i128).unsigned_abs() <= (sum(j).0 as i128).unsigned_abs());
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn det_test() {
+    let a: Array2<P> = array![[2.0, 1.0, 0.0], [1.0, 3.0, 1.0], [0.0, 1.0, 4.0]].mapv(|x: f32| P::from(x));
+    assert_eq!(linalg::det(a.view()), Ok(P::from(18.0)));
+
+    // one swap flips the sign
+    let b: Array2<P> = array![[0.0, 1.0], [1.0, 0.0]].mapv(|x: f32| P::from(x));
+    assert_eq!(linalg::det(b.view()), Ok(P::from(-1.0)));
+
+    let c: Array2<P> = array![[1.0, 2.0], [2.0, 4.0]].mapv(|x: f32| P::from(x));
+    assert_eq!(linalg::det(c.view()), Ok(P::from(0.0)));
+}
+
+#[test]
+fn triangular_test() {
+    let l: Array2<P> = array![[2.0, 7.0], [1.0, 4.0]].mapv(|x: f32| P::from(x));
+    let b: Array1<P> = array![2.0, 9.0].mapv(|x: f32| P::from(x));
+
+    assert_eq!(linalg::solve_triangular(l.view(), b.view(), Triangle::Lower, false).unwrap(), array![1.0, 2.0].mapv(|x: f32| P::from(x)));
+    assert_eq!(linalg::solve_triangular(l.view(), b.view(), Triangle::Lower, true).unwrap(), array![2.0, 7.0].mapv(|x: f32| P::from(x)));
+    let c: Array1<P> = array![4.0, 8.0].mapv(|x: f32| P::from(x));
+    assert_eq!(linalg::solve_triangular(l.t(), c.view(), Triangle::Upper, false).unwrap(), array![1.0, 2.0].mapv(|x: f32| P::from(x)));
+}
+
+#[test]
+fn cholesky_test() {
+    let mut rng = XorShift::new(2);
+    for n in [1, 3, 10] {
+        let b = to_f64(&random_matrix(&mut rng, n));
+        let a = (b.t().dot(&b)).mapv(|x| P::from(x as f32));
+
+        let l = linalg::cholesky(a.view()).unwrap();
+        let l64 = to_f64(&l);
+        assert!(max_diff(&l64.dot(&l64.t()), &to_f64(&a)) < 5e-2);
+        assert!((0..n).all(|i| (i + 1..n).all(|j| l[[i, j]] == P::from(0.0))));
+    }
+}
+
+#[test]
+fn qr_test() {
+    let mut rng = XorShift::new(3);
+    for (m, n) in [(3, 3), (8, 5), (20, 4)] {
+        let a = Array2::from_shape_fn((m, n), |_| P::from(rng.next_normal() as f32));
+        let f = linalg::qr(a.view()).unwrap();
+        let (q, r) = (to_f64(&f.q), to_f64(&f.r));
+
+        assert_eq!((f.q.dim(), f.r.dim()), ((m, n), (n, n)));
+        assert!(max_diff(&q.t().dot(&q), &Array2::eye(n)) < 1e-2);
+        assert!(max_diff(&q.dot(&r), &to_f64(&a)) < 1e-2);
+        assert!((0..n).all(|i| (0..i).all(|j| f.r[[i, j]] == P::from(0.0))));
+
+        // least squares matches the normal equations in f64
+        let b: Array1<P> = (0..m).map(|_| P::from(rng.next_normal() as f32)).collect();
+        let x = f.solve(b.view()).unwrap().mapv(|p| f32::from(p) as f64);
+        let a64 = to_f64(&a);
+        let res: Array1<f64> = a64.dot(&x) - b.mapv(|p| f32::from(p) as f64);
+        let g = a64.t().dot(&res);
+        assert!(g.iter().all(|v| v.abs() < 5e-2));
+    }
+}
+
+#[test]
+fn error_test() {
+    let singular: Array2<P> = array![[1.0, 2.0], [2.0, 4.0]].mapv(|x: f32| P::from(x));
+    assert_eq!(linalg::lu(singular.view()), Err(LinalgError::Singular(1)));
+    assert_eq!(linalg::inv(singular.view()), Err(LinalgError::Singular(1)));
+
+    let mut nar = singular.clone();
+    nar[[0, 1]] = P::NAR;
+    assert_eq!(linalg::lu(nar.view()), Err(LinalgError::NaR(0)));
+    assert!(matches!(linalg::qr(nar.view()), Err(LinalgError::NaR(_))));
+
+    let indefinite: Array2<P> = array![[1.0, 2.0], [2.0, 1.0]].mapv(|x: f32| P::from(x));
+    assert_eq!(linalg::cholesky(indefinite.view()), Err(LinalgError::NotPositiveDefinite(1)));
+
+    let wide = Array2::from_elem((2, 3), P::from(1.0));
+    assert_eq!(linalg::lu(wide.view()), Err(LinalgError::Shape));
+    assert_eq!(linalg::qr(wide.view()), Err(LinalgError::Shape));
+
+    let id = Array2::<P>::eye(2);
+    assert_eq!(linalg::solve(id.view(), Array1::from_elem(3, P::from(1.0)).view()), Err(LinalgError::Shape));
+    let zero_diagonal: Array2<P> = array![[1.0, 2.0], [2.0, 0.0]].mapv(|x: f32| P::from(x));
+    assert_eq!(linalg::solve_triangular(zero_diagonal.view(), Array1::from_elem(2, P::from(1.0)).view(), Triangle::Upper, false), Err(LinalgError::Singular(1)));
+}
+
+#[test]
+fn posit32_test() {
+    type P32 = Posit<32, 2>;
+
+    // the posit32 quire needs more than 128 bits; integer systems with integer solutions come out exact
+    let a: Array2<P32> = array![[4.0, -2.0, 1.0, 0.0], [3.0, 6.0, -4.0, 2.0], [2.0, 1.0, 8.0, -5.0], [1.0, -3.0, 2.0, 7.0]].mapv(P32::from);
+    let x: Array1<P32> = array![1.0, -2.0, 3.0, 0.5].mapv(P32::from);
+    let b = a.mapv(f64::from).dot(&x.mapv(f64::from)).mapv(P32::from);
+
+    assert_eq!(linalg::solve(a.view(), b.view()).unwrap(), x);
+    assert_eq!(linalg::qr(a.view()).unwrap().solve(b.view()).unwrap().mapv(|p| (f64::from(p) * 1e6).round() / 1e6), x.mapv(f64::from));
+
+    let s: Array2<P32> = array![[4.0, 2.0, -2.0], [2.0, 10.0, 2.0], [-2.0, 2.0, 6.0]].mapv(P32::from);
+    let l = linalg::cholesky(s.view()).unwrap();
+    assert_eq!(l, array![[2.0, 0.0, 0.0], [1.0, 3.0, 0.0], [-1.0, 1.0, 2.0]].mapv(P32::from));
+    assert_eq!(linalg::det(s.view()).unwrap(), P32::from(144.0));
+}
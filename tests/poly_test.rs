@@ -0,0 +1,133 @@
+use namagiri::posit::Posit;
+use namagiri::poly::{self, PolyError};
+use namagiri::rng::XorShift;
+use namagiri::sizing::ulp_distance;
+use ndarray::{array, Array1};
+
+type P = Posit<16, 1>;
+
+fn p(v: f64) -> P {
+    P::from(v)
+}
+
+// compensated Horner in f64, about twice double precision
+fn reference(c: &Array1<P>, x: f64) -> f64 {
+    let (s, e) = c.iter().rev().fold((0.0f64, 0.0f64), |(s, e), &c| {
+        let h = s * x;
+        let r = s.mul_add(x, -h);
+        let t = h + f64::from(c);
+        let z = t - h;
+        (t, e * x + r + (h - (t - z)) + (f64::from(c) - z))
+    });
+    s + e
+}
+
+#[test]
+fn poly_eval_test() {
+    let mut rng = XorShift::new(1);
+    let mut worse = 0;
+
+    for _ in 0..2000 {
+        let c: Array1<P> = (0..6).map(|_| p(rng.next_normal())).collect();
+        let x = p(2.0 * rng.next_f64() - 1.0);
+
+        // a single rounding of the exact value
+        let y = poly::poly_eval(c.view(), x);
+        assert_eq!(y, p(reference(&c, f64::from(x))), "{} at {}", c, x);
+
+        // Horner in posit arithmetic rounds at every step
+        let horner = c.iter().rev().fold(p(0.0), |acc, &k| acc * x + k);
+        let exact = reference(&c, f64::from(x));
+        assert!(ulp_distance(y, p(exact)) <= ulp_distance(horner, p(exact)));
+        worse += (horner != y) as usize;
+    }
+    assert!(worse > 0);
+
+    assert_eq!(poly::poly_eval(array![p(1.0), p(-3.0), p(2.0)].view(), p(0.5)), p(0.0));
+    assert_eq!(poly::poly_eval(Array1::<P>::zeros(0).view(), p(3.0)), p(0.0));
+    assert!(poly::poly_eval(array![p(1.0), P::NAR].view(), p(0.5)).is_nar());
+    assert!(poly::poly_eval(array![p(1.0)].view(), P::NAR).is_nar());
+}
+
+#[test]
+fn estrin_fallback_test() {
+    // forty powers of a long fraction outgrow 128 bits, so the tree of fused steps takes over
+    let c: Array1<P> = (0..40).map(|k| p(1.0 / (k + 1) as f64)).collect();
+    let x = p(0.7);
+    let y = f64::from(poly::poly_eval(c.view(), x));
+    let exact = reference(&c, f64::from(x));
+    assert!((y - exact).abs() / exact < 1e-3, "{} {}", y, exact);
+}
+
+#[test]
+fn rational_eval_test() {
+    let mut rng = XorShift::new(2);
+
+    for _ in 0..2000 {
+        let a: Array1<P> = (0..4).map(|_| p(rng.next_normal())).collect();
+        let b: Array1<P> = (0..3).map(|_| p(rng.next_normal())).collect();
+        let x = p(4.0 * rng.next_f64() - 2.0);
+
+        let (num, den) = (reference(&a, f64::from(x)), reference(&b, f64::from(x)));
+        assert_eq!(poly::rational_eval(a.view(), b.view(), x), p(num / den));
+    }
+
+    // (x^2 - 1) / (x - 1) is x + 1 away from the pole, and NaR on it
+    let (num, den) = (array![p(-1.0), p(0.0), p(1.0)], array![p(-1.0), p(1.0)]);
+    assert_eq!(poly::rational_eval(num.view(), den.view(), p(3.5)), p(4.5));
+    assert!(poly::rational_eval(num.view(), den.view(), p(1.0)).is_nar());
+}
+
+#[test]
+fn minimax_test() {
+    // the best line through x^2 on [-1, 1] is the constant 1/2
+    let fit = poly::minimax::<16, 1, _>(|x| x * x, 1, (-1.0, 1.0)).unwrap();
+    assert_eq!(fit.coeffs, array![p(0.5), p(0.0)]);
+    assert!((fit.error - 0.5).abs() < 1e-12);
+
+    // exp on [0, 1]: close to the real minimax error of 5.45e-4, far below the rounded Taylor series
+    let fit = poly::minimax::<16, 1, _>(f64::exp, 3, (0.0, 1.0)).unwrap();
+    let taylor = array![p(1.0), p(1.0), p(0.5), p(1.0 / 6.0)];
+    let max_err = |c: &Array1<P>| (0..=4096).map(|i| i as f64 / 4096.0).map(|x| (reference(c, x) - x.exp()).abs()).fold(0.0, f64::max);
+
+    assert!(fit.error < 6.5e-4 && fit.error < max_err(&taylor) / 10.0, "{}", fit.error);
+    assert!((max_err(&fit.coeffs) - fit.error).abs() < 1e-9);
+
+    // never worse than rounding a (nearly) real fit, and here clearly better
+    let fine = poly::minimax::<32, 2, _>(f64::exp, 5, (0.0, 1.0)).unwrap();
+    let coarse = poly::minimax::<16, 1, _>(f64::exp, 5, (0.0, 1.0)).unwrap();
+    let rounded: Array1<P> = fine.coeffs.iter().map(|&c| p(f64::from(c))).collect();
+    assert!(fine.error < 2e-6);
+    assert!(coarse.error < 0.8 * max_err(&rounded), "{} {}", coarse.error, max_err(&rounded));
+
+    // evaluated on posits, the error adds at most the final rounding
+    for i in 0..=64 {
+        let x = p(i as f64 / 64.0);
+        let y = poly::poly_eval(fit.coeffs.view(), x);
+        let ulp = f64::from(y) * 2f64.powi(-11);
+        assert!((f64::from(y) - f64::from(x).exp()).abs() <= fit.error + ulp);
+    }
+
+    let sin = poly::minimax::<16, 1, _>(f64::sin, 5, (-1.0, 1.0)).unwrap();
+    assert!(sin.error < 1e-4);
+    assert_eq!(poly::minimax::<16, 1, _>(f64::exp, 3, (1.0, 0.0)), Err(PolyError::Interval(1.0, 0.0)));
+    assert_eq!(poly::minimax::<16, 1, _>(f64::exp, 3, (0.0, f64::INFINITY)), Err(PolyError::Interval(0.0, f64::INFINITY)));
+}
+
+#[test]
+fn posit32_test() {
+    type Q = Posit<32, 2>;
+
+    // x + x^2 + ... + x^11 at a third needs the fused fallback and is just under a half
+    let c: Array1<Q> = (0..12).map(|k| Q::from(if k == 0 {0.0} else {1.0})).collect();
+    let x = Q::from(1.0 / 3.0);
+    let xf = f64::from(x);
+    let exact = (1..12).map(|k| xf.powi(k)).sum::<f64>();
+
+    let y = f64::from(poly::poly_eval(c.view(), x));
+    assert!((y - exact).abs() < 1e-8 && (y - 0.5).abs() < 1e-5, "{} {}", y, exact);
+
+    // short polynomials in short fractions still round once
+    assert_eq!(poly::poly_eval(array![1.0, -3.0, 2.0].mapv(Q::from).view(), Q::from(0.25)), Q::from(0.375));
+    assert_eq!(poly::rational_eval(array![-1.0, 0.0, 1.0].mapv(Q::from).view(), array![-1.0, 1.0].mapv(Q::from).view(), Q::from(3.5)), Q::from(4.5));
+}
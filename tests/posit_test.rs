@@ -1,3 +1,4 @@
+use num_traits::identities::Zero;
 use namagiri::posit::Posit;
 
 #[test]
@@ -9,3 +10,64 @@ fn to_from_test() {
         assert_eq!(a, af);
     }
 }
+
+#[test]
+fn sqrt_test() {
+    assert!(Posit::<16, 1>::from(-4.0).sqrt().is_nar());
+    assert_eq!(Posit::<16, 1>::from(0.0).sqrt(), Posit::<16, 1>::from(0.0));
+    assert_eq!(Posit::<16, 1>::from(2.25).sqrt(), Posit::<16, 1>::from(1.5));
+
+    // the result is the posit nearest to the real square root
+    for i in 1..0x7fff {
+        let x = Posit::<16, 1>(i);
+        let r = x.sqrt();
+        let exact = (f32::from(x) as f64).sqrt();
+        let err = |p: Posit<16, 1>| (f32::from(p) as f64 - exact).abs();
+
+        assert!(err(r) <= err(Posit::<16, 1>(r.0 - 1)));
+        if r.0 < 0x7fff {
+            assert!(err(r) <= err(Posit::<16, 1>(r.0 + 1)));
+        }
+    }
+}
+
+#[test]
+fn f64_test() {
+    for i in 0..0x10000 {
+        let a = Posit::<16, 1>(i);
+        if !a.is_nar() {
+            assert_eq!(Posit::<16, 1>::from(f64::from(a)), a);
+            assert_eq!(f64::from(a), f32::from(a) as f64);
+        }
+    }
+
+    // one rounding straight from f64, where f32 would round first
+    let x = 1.0 + 2f64.powi(-27) + 2f64.powi(-40);
+    assert_eq!(Posit::<32, 2>::from(x), Posit::<32, 2>(0x40000001));
+    assert!(Posit::<16, 1>::from(f64::NAN).is_nar());
+}
+
+#[test]
+fn mul_div_test() {
+    // both round the exact result once
+    for i in 0..0b100000000 {
+        let a = Posit::<8, 1>(i);
+        for j in 0..0b100000000 {
+            let b = Posit::<8, 1>(j);
+            if a.is_nar() || b.is_nar() {
+                assert!((a * b).is_nar() && (a / b).is_nar());
+                continue;
+            }
+            assert_eq!(a * b, Posit::<8, 1>::from(f64::from(a) * f64::from(b)));
+            if !b.is_zero() {
+                assert_eq!(a / b, Posit::<8, 1>::from(f64::from(a) / f64::from(b)));
+            }
+        }
+    }
+
+    // posit32 significands are too wide for 32-bit products
+    let third = Posit::<32, 2>::from(1.0) / Posit::<32, 2>::from(3.0);
+    assert_eq!(third, Posit::<32, 2>::from(1.0 / 3.0));
+    assert_eq!(third * Posit::<32, 2>::from(3.0), Posit::<32, 2>::from(f64::from(third) * 3.0));
+    assert_eq!(Posit::<32, 2>::from(-2.5) / Posit::<32, 2>::from(0.5), Posit::<32, 2>::from(-5.0));
+}
@@ -1,3 +1,4 @@
+use num_traits::identities::Zero;
 use namagiri::posit::Posit;
 use namagiri::quire::Quire;
 
@@ -43,4 +44,107 @@ fn mul_test() {
 #[test]
 fn add_test() {
     op_test(|x, y| x + y, |x, y| x + y)
-}
\ No newline at end of file
+}
+
+#[test]
+fn exact_value_test() {
+    for i in 0..0b100000000 {
+        let a = Posit::<8, 1>(i);
+        if a.is_nar() {
+            continue;
+        }
+        let q = Quire::from(a);
+        let (m, e) = q.to_rational();
+        assert_eq!(q.to_f64(), f32::from(a) as f64);
+        assert_eq!(m as f64 * 2f64.powi(e), f32::from(a) as f64);
+        assert_eq!(q.to_decimal().parse::<f64>().unwrap(), f32::from(a) as f64);
+    }
+
+    let minpos = Quire::from(Posit::<8, 1>::MINPOS);
+    assert_eq!(minpos.to_decimal(), "0.000244140625");
+    assert_eq!(minpos.to_rational(), (1, -12));
+    assert_eq!((minpos * minpos).to_decimal(), "0.000000059604644775390625");
+    assert_eq!((-minpos - Quire::from(Posit::<8, 1>::MAXPOS)).to_decimal(), "-4096.000244140625");
+}
+
+#[test]
+fn div_test() {
+    for i in 0..0b100000000 {
+        let a = Posit::<8, 1>(i);
+        let qa: Quire<8, 1> = a.into();
+        for j in 0..0b100000000 {
+            let b = Posit::<8, 1>(j);
+            if a.is_nar() || b.is_nar() || b.is_zero() {
+                continue;
+            }
+            let qb: Quire<8, 1> = b.into();
+
+            assert_eq!(qa / b, Posit::<8, 1>::from(f32::from(a) / f32::from(b)));
+            if !a.is_zero() {
+                assert_eq!(((qa * qb) / qb).0, qa.0);
+            }
+        }
+        if a.is_nar() {
+            continue;
+        }
+        for n in 1..20 {
+            assert_eq!(qa.div_int(n), Posit::<8, 1>::from(f32::from(a) / n as f32));
+            assert_eq!(qa.div_int(-n), Posit::<8, 1>::from(f32::from(a) / -n as f32));
+        }
+        assert_eq!(qa.scale(1).0, (qa + qa).0);
+        assert_eq!(qa.scale(3).scale(-3).0, qa.0);
+    }
+}
+
+#[test]
+fn nar_test() {
+    let one = Quire::<8, 1>::from(Posit::<8, 1>::from(1.0));
+
+    // dividing by zero, or scaling past the top of the quire, gives NaR
+    assert!((one / Quire::zero()).is_nar());
+    assert!((Quire::<8, 1>::zero() / Quire::zero()).is_nar());
+    assert!(one.scale(127).is_nar());
+    assert!((-one).scale(200).is_nar());
+    assert!(!one.scale(101).is_nar() && one.scale(102).is_nar());
+    assert_eq!(Quire::<8, 1>::zero().scale(200).0, 0);
+    assert_eq!(one.scale(-2).scale(2).0, one.0);
+
+    // bits shifted off the bottom still keep the value away from zero
+    let tiny = Quire::from(Posit::<8, 1>::MINPOS) * Quire::from(Posit::<8, 1>::MINPOS);
+    assert_eq!(Posit::<8, 1>::from(tiny.scale(-2)), tiny.div_int(4));
+    assert_eq!(Posit::<8, 1>::from((-tiny).scale(-2)), -Posit::<8, 1>::MINPOS);
+
+    // and NaR stays NaR through every way out of the quire
+    let nar = Quire::<8, 1>::NAR;
+    assert!((nar / one).is_nar() && nar.scale(3).is_nar() && nar.scale(-3).is_nar());
+    assert!(Posit::<8, 1>::from(nar).is_nar());
+    assert!(nar.div_int(3).is_nar() && nar.div_posit(Posit::from(2.0)).is_nar() && nar.sqrt().is_nar());
+    assert_eq!(nar.to_decimal(), "NaR");
+    assert_eq!(nar.to_f64(), f64::INFINITY);
+}
+
+#[test]
+fn mean_test() {
+    let xs: Vec<Posit<8, 1>> = (0..64).map(|i| Posit::<8, 1>::from(0.25 + i as f32 / 16.0)).collect();
+    let sum = xs.iter().fold(Quire::<8, 1>::default(), |acc, &x| acc + x.into());
+    let exact = xs.iter().map(|&x| f32::from(x)).sum::<f32>() / 64.0;
+
+    assert_eq!(sum.div_int(64), Posit::<8, 1>::from(exact));
+    assert_eq!(Posit::<8, 1>::from(sum.scale(-6)), sum.div_int(64));
+}
+
+#[test]
+fn sqrt_test() {
+    // matches the posit square root whenever the quire holds a posit
+    for i in 0..0b100000000 {
+        let a = Posit::<8, 1>(i);
+        if !a.is_nar() {
+            assert_eq!(Quire::from(a).sqrt(), a.sqrt());
+        }
+    }
+
+    // the root of an exact sum that is not itself a posit
+    let q = Quire::<16, 1>::from(Posit::<16, 1>::from(1.0)) + Quire::from(Posit::<16, 1>::from(0.00048828125)) * Quire::from(Posit::<16, 1>::from(0.001953125));
+    assert_eq!(q.sqrt(), Posit::<16, 1>::from((1.0f64 + 2f64.powi(-20)).sqrt()));
+    assert!((-Quire::<8, 1>::from(Posit::<8, 1>::from(4.0))).sqrt().is_nar());
+}
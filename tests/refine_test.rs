@@ -0,0 +1,72 @@
+use namagiri::posit::Posit;
+use namagiri::flquire::FLQuire;
+use namagiri::linalg::LinalgError;
+use namagiri::refine::{self, RefineConfig};
+use namagiri::rng::XorShift;
+use namagiri::window::Wide;
+use ndarray::{Array1, Array2};
+
+fn random_system(rng: &mut XorShift, n: usize) -> (Array2<f64>, Array1<f64>) {
+    let a = Array2::from_shape_fn((n, n), |(i, j)| rng.next_normal() + if i == j {n as f64} else {0.0});
+    let b = (0..n).map(|_| rng.next_normal()).collect();
+    (a, b)
+}
+
+#[test]
+fn posit16_test() {
+    let mut rng = XorShift::new(4);
+    let (a, b) = random_system(&mut rng, 12);
+    let (a, b) = (a.mapv(Posit::<16, 1>::from), b.mapv(Posit::<16, 1>::from));
+
+    let cfg = RefineConfig{tol: 1e-3, max_iter: 10};
+    let r = refine::refine::<8, 1, 16, 1>(a.view(), b.view(), cfg).unwrap();
+
+    assert!(r.converged, "{:?}", r.history);
+    assert!(r.history.len() > 1 && r.history[0] > 5e-3);
+    assert!(*r.history.last().unwrap() <= 1e-3);
+    assert!(r.history.windows(2).all(|w| w[1] < w[0]));
+}
+
+#[test]
+fn posit32_test() {
+    let mut rng = XorShift::new(5);
+    let (a, b) = random_system(&mut rng, 20);
+    let (a, b) = (a.mapv(Posit::<32, 2>::from), b.mapv(Posit::<32, 2>::from));
+
+    let cfg = RefineConfig{tol: 1e-7, max_iter: 10};
+    let r = refine::refine_with::<16, 1, 32, 2, FLQuire<32, 2, 300, Wide<5>>>(a.view(), b.view(), cfg).unwrap();
+
+    assert!(r.converged, "{:?}", r.history);
+    assert!(r.history[0] > 1e-6);
+
+    // the default exact residual works for posit32 as well
+    let q = refine::refine::<16, 1, 32, 2>(a.view(), b.view(), cfg).unwrap();
+    assert!(q.converged, "{:?}", q.history);
+    assert_eq!(q.x, r.x);
+}
+
+#[test]
+fn float_test() {
+    let mut rng = XorShift::new(6);
+    let (a, b) = random_system(&mut rng, 20);
+
+    let r = refine::refine_float(a.view(), b.view(), RefineConfig{tol: 1e-14, max_iter: 10}).unwrap();
+    assert!(r.converged, "{:?}", r.history);
+    assert!(r.history[0] > 1e-10);
+
+    // a budget of zero iterations reports the plain solve
+    let r = refine::refine_float(a.view(), b.view(), RefineConfig{tol: 0.0, max_iter: 0}).unwrap();
+    assert!(!r.converged && r.history.len() == 1);
+}
+
+#[test]
+fn error_test() {
+    let mut a = Array2::from_elem((2, 2), Posit::<16, 1>::from(1.0));
+    let b = Array1::from_elem(2, Posit::<16, 1>::from(1.0));
+    assert_eq!(refine::refine::<8, 1, 16, 1>(a.view(), b.view(), RefineConfig::default()), Err(LinalgError::Singular(1)));
+
+    a[[0, 1]] = Posit::NAR;
+    assert_eq!(refine::refine::<8, 1, 16, 1>(a.view(), b.view(), RefineConfig::default()), Err(LinalgError::NaR(0)));
+
+    assert_eq!(refine::refine::<8, 1, 16, 1>(a.view(), Array1::from_elem(3, Posit::<16, 1>::from(1.0)).view(), RefineConfig::default()), Err(LinalgError::NaR(0)));
+}
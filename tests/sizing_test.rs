@@ -0,0 +1,41 @@
+use namagiri::posit::Posit;
+use namagiri::sizing::{self, Distribution, SizeReport, SweepConfig};
+
+#[test]
+fn ulp_distance_test() {
+    let one = Posit::<8, 1>::from(1.0);
+    assert_eq!(sizing::ulp_distance(one, one), 0);
+    assert_eq!(sizing::ulp_distance(one, Posit::<8, 1>(one.0 + 3)), 3);
+    assert_eq!(sizing::ulp_distance(-Posit::<8, 1>::MINPOS, Posit::<8, 1>::MINPOS), 2);
+    assert_eq!(sizing::ulp_distance(-Posit::<8, 1>::MAXPOS, Posit::<8, 1>::MAXPOS), 254);
+}
+
+#[test]
+fn sweep_test() {
+    let cfg = SweepConfig{len: 32, trials: 200, dist: Distribution::Bits, seed: 7};
+    let reports = sizing::sweep::<8, 1>(&cfg, &[4, 8, 12, 48, 64]);
+
+    assert_eq!(reports.iter().map(|r| r.size).collect::<Vec<_>>(), vec![8, 12, 48, 64]);
+    assert!(reports[0].mismatches > reports[3].mismatches);
+    assert_eq!(reports[3].mismatches, 0);
+    assert_eq!(sizing::recommend(&reports, 0.0), Some(48));
+}
+
+#[test]
+fn recommend_test() {
+    let report = |size, mismatches| SizeReport{size, trials: 100, mismatches, max_ulps: 1, mean_ulps: 0.0};
+    let reports = [report(24, 0), report(16, 20), report(18, 0), report(20, 2)];
+
+    assert_eq!(sizing::recommend(&reports, 0.0), Some(24));
+    assert_eq!(sizing::recommend(&reports, 0.05), Some(18));
+    assert_eq!(sizing::recommend(&reports[1..2], 0.05), None);
+}
+
+#[test]
+fn distribution_parse_test() {
+    assert_eq!("bits".parse::<Distribution>(), Ok(Distribution::Bits));
+    assert_eq!("normal:0,2".parse::<Distribution>(), Ok(Distribution::Normal(0.0, 2.0)));
+    assert_eq!("uniform:-1, 1".parse::<Distribution>(), Ok(Distribution::Uniform(-1.0, 1.0)));
+    assert!("normal:1".parse::<Distribution>().is_err());
+    assert!("gamma:1,1".parse::<Distribution>().is_err());
+}
@@ -0,0 +1,144 @@
+use namagiri::posit::Posit;
+use namagiri::gemm::{self, Trans};
+use namagiri::krylov::{self, SolverConfig};
+use namagiri::sparse::{CooMatrix, CscMatrix, CsrMatrix, SparseError};
+use namagiri::rng::XorShift;
+use ndarray::{array, Array1, Array2};
+
+type P = Posit<8, 1>;
+
+fn random_sparse(rng: &mut XorShift, m: usize, n: usize, density: f64) -> Array2<P> {
+    Array2::from_shape_fn((m, n), |_| if rng.next_f64() < density {P::from(rng.next_normal() as f32)} else {P::from(0.0)})
+}
+
+#[test]
+fn conversion_test() {
+    let mut rng = XorShift::new(3);
+    let a = random_sparse(&mut rng, 9, 7, 0.3);
+
+    let coo = CooMatrix::from_dense(a.view());
+    let (csr, csc) = (coo.to_csr(), coo.to_csc());
+    assert_eq!(csr.nnz(), a.iter().filter(|p| **p != P::from(0.0)).count());
+
+    assert_eq!(coo.to_dense(), a);
+    assert_eq!(csr.to_dense(), a);
+    assert_eq!(csc.to_dense(), a);
+    assert_eq!(csr.to_csc(), csc);
+    assert_eq!(csc.to_csr(), csr);
+    assert_eq!(CsrMatrix::from_dense(a.view()), csr);
+    assert_eq!(csr.clone().transpose().to_dense(), a.t());
+    assert_eq!(csc.transpose().to_dense(), a.t());
+}
+
+#[test]
+fn duplicate_test() {
+    // 1 + 2^-5 + 2^-5 is representable, while adding one at a time ties back to 1
+    let mut coo = CooMatrix::<8, 1>::new(2, 2);
+    for v in [1.0, 0.03125, 0.03125] {
+        coo.push(1, 0, P::from(v)).unwrap();
+    }
+    coo.push(0, 1, P::from(2.0)).unwrap();
+
+    assert_eq!(P::from(1.0) + P::from(0.03125) + P::from(0.03125), P::from(1.0));
+
+    let csr = coo.to_csr();
+    assert_eq!(csr.indptr, vec![0, 1, 2]);
+    assert_eq!(csr.to_dense(), array![[0.0, 2.0], [1.0625, 0.0]].mapv(|x: f32| P::from(x)));
+    assert_eq!(coo.to_csc().to_dense(), csr.to_dense());
+}
+
+#[test]
+fn spmv_test() {
+    let mut rng = XorShift::new(5);
+    for (m, n) in [(1, 1), (40, 30), (300, 200)] {
+        let a = random_sparse(&mut rng, m, n, 0.1);
+        let x: Array1<P> = (0..n).map(|_| P::from(rng.next_normal() as f32)).collect();
+        let y = gemm::gemv(Trans::No, a.view(), x.view());
+
+        assert_eq!(CsrMatrix::from_dense(a.view()).spmv(x.view()).unwrap(), y);
+        assert_eq!(CscMatrix::from_dense(a.view()).spmv(x.view()).unwrap(), y);
+    }
+}
+
+#[test]
+fn spmm_test() {
+    let mut rng = XorShift::new(6);
+    let a = random_sparse(&mut rng, 150, 60, 0.2);
+    let b = Array2::from_shape_fn((60, 9), |_| P::from(rng.next_normal() as f32));
+    let c = gemm::gemm(Trans::No, a.view(), Trans::No, b.view());
+
+    let csr = CsrMatrix::from_dense(a.view());
+    assert_eq!(csr.spmm(b.view()).unwrap(), c);
+    assert_eq!(csr.spmm(b.view()), csr.spmm(b.view()));
+    assert_eq!(CscMatrix::from_dense(a.view()).spmm(b.view()).unwrap(), c);
+}
+
+#[test]
+fn nar_test() {
+    let mut a = Array2::from_elem((3, 3), P::from(1.0));
+    a[[1, 2]] = P::NAR;
+    let mut x = Array1::from_elem(3, P::from(1.0));
+
+    let y = CsrMatrix::from_dense(a.view()).spmv(x.view()).unwrap();
+    assert!(!y[0].is_nar() && y[1].is_nar() && !y[2].is_nar());
+    assert_eq!(CscMatrix::from_dense(a.view()).spmv(x.view()).unwrap(), y);
+
+    x[0] = P::NAR;
+    assert!(CscMatrix::from_dense(a.view()).spmv(x.view()).unwrap().iter().all(|p| p.is_nar()));
+}
+
+#[test]
+fn operator_test() {
+    let n = 50;
+    let mut coo = CooMatrix::<16, 1>::new(n, n);
+    for i in 0..n {
+        coo.push(i, i, Posit::from(2.5)).unwrap();
+        if i > 0 {
+            coo.push(i, i - 1, Posit::from(-1.0)).unwrap();
+            coo.push(i - 1, i, Posit::from(-1.0)).unwrap();
+        }
+    }
+    let b = Array1::from_elem(n, Posit::<16, 1>::from(1.0));
+    let cfg = SolverConfig{tol: 1e-3, max_iter: 100};
+
+    let sparse = krylov::cg(&coo.to_csr(), b.view(), cfg).unwrap();
+    assert!(sparse.converged);
+    assert_eq!(sparse, krylov::cg(&coo.to_dense(), b.view(), cfg).unwrap());
+    assert_eq!(sparse, krylov::cg(&coo.to_csc(), b.view(), cfg).unwrap());
+}
+
+#[test]
+fn error_test() {
+    assert_eq!(CooMatrix::<8, 1>::from_triplets(2, 2, vec![0, 2], vec![0, 0], vec![P::from(1.0); 2]), Err(SparseError::OutOfBounds(1)));
+    assert_eq!(CooMatrix::<8, 1>::from_triplets(2, 2, vec![0], vec![0, 0], vec![P::from(1.0); 2]), Err(SparseError::Shape));
+    assert_eq!(CooMatrix::<8, 1>::new(2, 2).push(0, 3, P::from(1.0)), Err(SparseError::OutOfBounds(0)));
+
+    assert_eq!(CsrMatrix::<8, 1>::new(2, 2, vec![0, 1], vec![0], vec![P::from(1.0)]), Err(SparseError::Shape));
+    assert_eq!(CsrMatrix::<8, 1>::new(2, 2, vec![0, 2, 1], vec![0], vec![P::from(1.0)]), Err(SparseError::Indptr));
+    assert_eq!(CscMatrix::<8, 1>::new(2, 2, vec![0, 1, 1], vec![5], vec![P::from(1.0)]), Err(SparseError::OutOfBounds(0)));
+    assert!(CsrMatrix::<8, 1>::new(2, 2, vec![0, 0, 1], vec![1], vec![P::from(1.0)]).is_ok());
+
+    let a = Array2::from_elem((2, 3), P::from(1.0));
+    let (x, b) = (Array1::from_elem(2, P::from(1.0)), Array2::from_elem((4, 2), P::from(1.0)));
+    assert_eq!(CsrMatrix::from_dense(a.view()).spmv(x.view()), Err(SparseError::Dimensions(3, 2)));
+    assert_eq!(CscMatrix::from_dense(a.view()).spmv(x.view()), Err(SparseError::Dimensions(3, 2)));
+    assert_eq!(CsrMatrix::from_dense(a.view()).spmm(b.view()), Err(SparseError::Dimensions(3, 4)));
+    assert_eq!(CscMatrix::from_dense(a.view()).spmm(b.view()), Err(SparseError::Dimensions(3, 4)));
+}
+
+#[test]
+fn posit32_test() {
+    type Q = Posit<32, 2>;
+    let mut rng = XorShift::new(8);
+    let a = Array2::from_shape_fn((30, 20), |_| if rng.next_f64() < 0.3 {Q::from(rng.next_normal() as f32)} else {Q::from(0.0)});
+    let x: Array1<Q> = (0..20).map(|_| Q::from(rng.next_normal() as f32)).collect();
+    let y = gemm::gemv(Trans::No, a.view(), x.view());
+
+    let expected: Vec<f64> = a.outer_iter().map(|r| r.iter().zip(&x).map(|(p, q)| f64::from(*p) * f64::from(*q)).sum()).collect();
+    assert!(y.iter().zip(&expected).all(|(p, e)| (f64::from(*p) - e).abs() <= 1e-7 * e.abs().max(1.0)));
+
+    assert_eq!(CsrMatrix::from_dense(a.view()).spmv(x.view()).unwrap(), y);
+    assert_eq!(CscMatrix::from_dense(a.view()).spmv(x.view()).unwrap(), y);
+    let b = x.clone().into_shape((20, 1)).unwrap();
+    assert_eq!(CsrMatrix::from_dense(a.view()).spmm(b.view()).unwrap().column(0), y);
+}
@@ -0,0 +1,185 @@
+use namagiri::posit::Posit;
+use namagiri::stats::{self, NarPolicy, Variance, Welford};
+use namagiri::rng::XorShift;
+use ndarray::{array, Array1, Array2, Axis};
+
+type P = Posit<16, 1>;
+
+fn p(v: f64) -> P {
+    P::from(v)
+}
+
+fn random(rows: usize, cols: usize, offset: f64, rng: &mut XorShift) -> Array2<P> {
+    Array2::from_shape_fn((rows, cols), |_| p(offset + rng.next_normal()))
+}
+
+// the lane's variance about its exact mean, in f64 from the exact posit values
+fn reference_var(x: &[f64], ddof: usize) -> f64 {
+    let m = x.iter().sum::<f64>() / x.len() as f64;
+    x.iter().map(|v| (v - m) * (v - m)).sum::<f64>() / (x.len() - ddof) as f64
+}
+
+#[test]
+fn moments_test() {
+    let mut rng = XorShift::new(1);
+    let x = random(6, 50, 100.0, &mut rng);
+
+    let sums = stats::sum(x.view(), Axis(1));
+    let means = stats::mean(x.view(), Axis(1));
+    let vars = stats::var(x.view(), Axis(1), 1, Variance::TwoPass);
+    let welford = stats::var(x.view(), Axis(1), 1, Variance::Welford);
+
+    for (i, row) in x.rows().into_iter().enumerate() {
+        let v: Vec<f64> = row.iter().map(|&q| f64::from(q)).collect();
+        let s: f64 = v.iter().sum();
+
+        // f64 holds these sums exactly, so each result is the posit nearest the true value
+        assert_eq!(sums[i], p(s));
+        assert_eq!(means[i], p(s / 50.0));
+        assert_eq!(vars[i], p(reference_var(&v, 1)));
+        assert_eq!(welford[i], vars[i]);
+    }
+
+    // the naive loop drifts: the data sit near 100 where posit16 has few fraction bits
+    let row = x.row(0);
+    let naive = row.iter().fold(P::from(0.0), |s, &v| s + v);
+    assert_ne!(naive, sums[0]);
+
+    // reductions along the other axis, and on higher-rank arrays
+    let cols = stats::mean(x.view(), Axis(0));
+    assert_eq!(cols.len(), 50);
+    let x3 = x.clone().into_shape((2, 3, 50)).unwrap();
+    assert_eq!(stats::sum(x3.view(), Axis(2)).into_shape(6).unwrap(), sums);
+
+    // too few values, and NaR, give NaR
+    assert!(stats::var(array![p(1.0)].view(), Axis(0), 1, Variance::TwoPass)[()].is_nar());
+    assert!(stats::mean(array![p(1.0), P::NAR].view(), Axis(0))[()].is_nar());
+    assert!(stats::var(array![p(1.0), P::NAR].view(), Axis(0), 0, Variance::Welford)[()].is_nar());
+}
+
+#[test]
+fn welford_test() {
+    let mut rng = XorShift::new(2);
+    let x: Array1<P> = (0..200).map(|_| p(rng.next_normal() * 4.0 - 10.0)).collect();
+    let v: Vec<f64> = x.iter().map(|&q| f64::from(q)).collect();
+
+    // streaming halves and merging them matches one pass over everything
+    let (mut a, mut b, mut all) = (Welford::new(), Welford::new(), Welford::new());
+    for (k, &q) in x.iter().enumerate() {
+        if k < 70 {a.push(q)} else {b.push(q)}
+        all.push(q);
+    }
+
+    let merged = a.merge(b);
+    assert_eq!(merged.count, 200);
+    assert_eq!(merged.mean(), p(v.iter().sum::<f64>() / 200.0));
+    assert_eq!(merged.variance(0), p(reference_var(&v, 0)));
+    assert_eq!(all.variance(0), merged.variance(0));
+    assert!(Welford::<16, 1>::new().mean().is_nar());
+}
+
+#[test]
+fn covariance_test() {
+    let mut rng = XorShift::new(3);
+    let mut x = random(3, 40, 0.0, &mut rng);
+    // the third row follows the first
+    for k in 0..40 {
+        x[(2, k)] = p(2.0 * f64::from(x[(0, k)]) + 0.5);
+    }
+
+    let c = stats::cov(x.view(), 1);
+    let r = stats::corrcoef(x.view());
+    let rows: Vec<Vec<f64>> = x.rows().into_iter().map(|r| r.iter().map(|&q| f64::from(q)).collect()).collect();
+    let m: Vec<f64> = rows.iter().map(|r| r.iter().sum::<f64>() / 40.0).collect();
+
+    for i in 0..3 {
+        for j in 0..3 {
+            let s: f64 = (0..40).map(|k| (rows[i][k] - m[i]) * (rows[j][k] - m[j])).sum();
+            assert_eq!(c[(i, j)], p(s / 39.0), "{} {}", i, j);
+            assert_eq!(c[(i, j)], c[(j, i)]);
+        }
+        assert_eq!(r[(i, i)], p(1.0));
+        assert_eq!(c[(i, i)], stats::var(x.row(i), Axis(0), 1, Variance::TwoPass)[()]);
+    }
+
+    assert!(f64::from(r[(0, 2)]) > 0.99);
+    assert!(f64::from(r[(0, 1)]).abs() < 0.5);
+
+    // a constant row has no correlation
+    let flat = array![[p(1.0), p(2.0), p(3.0)], [p(4.0), p(4.0), p(4.0)]];
+    assert_eq!(stats::cov(flat.view(), 0)[(1, 1)], p(0.0));
+    assert!(stats::corrcoef(flat.view())[(0, 1)].is_nar());
+}
+
+#[test]
+fn order_test() {
+    let x = array![[p(3.0), p(-1.0), P::NAR, p(7.0), p(-1.0)], [p(2.0), p(2.0), p(0.5), p(8.0), p(1.0)]];
+
+    assert_eq!(stats::argmin(x.view(), Axis(1), NarPolicy::Omit), array![Some(1), Some(2)]);
+    assert_eq!(stats::argmax(x.view(), Axis(1), NarPolicy::Omit), array![Some(3), Some(3)]);
+    assert_eq!(stats::argmax(x.view(), Axis(1), NarPolicy::Propagate), array![Some(2), Some(3)]);
+    assert_eq!(stats::min(x.view(), Axis(1), NarPolicy::Omit), array![p(-1.0), p(0.5)]);
+    assert!(stats::max(x.view(), Axis(1), NarPolicy::Propagate)[0].is_nar());
+    assert_eq!(stats::max(x.view(), Axis(0), NarPolicy::Propagate)[0], p(3.0));
+
+    let all_nar = array![P::NAR, P::NAR];
+    assert_eq!(stats::argmin(all_nar.view(), Axis(0), NarPolicy::Omit)[()], None);
+    assert!(stats::min(all_nar.view(), Axis(0), NarPolicy::Omit)[()].is_nar());
+
+    // linear interpolation between order statistics, as numpy does
+    assert_eq!(stats::median(x.view(), Axis(1), NarPolicy::Omit), array![p(1.0), p(2.0)]);
+    assert_eq!(stats::quantile(x.view(), Axis(1), 0.25, NarPolicy::Omit), array![p(-1.0), p(1.0)]);
+    assert_eq!(stats::quantile(x.view(), Axis(1), 0.9, NarPolicy::Omit), array![p(5.8), p(5.6)]);
+    assert_eq!(stats::quantile(x.view(), Axis(1), 1.0, NarPolicy::Omit), stats::max(x.view(), Axis(1), NarPolicy::Omit));
+    assert!(stats::median(x.view(), Axis(1), NarPolicy::Propagate)[0].is_nar());
+}
+
+#[test]
+fn exact_moment_test() {
+    // n sum x^2 - (sum x)^2 over n (n - ddof) in integers, the data being multiples of 2^-16
+    let mut rng = XorShift::new(9);
+    for lane in 0..300 {
+        let n = 3 + lane % 10;
+        let x: Array1<P> = (0..n).map(|_| p(100.0 + 0.01 * rng.next_normal())).collect();
+        let ints: Vec<i128> = x.iter().map(|&q| (f64::from(q) * 65536.0) as i128).collect();
+
+        let (s, s2) = (ints.iter().sum::<i128>(), ints.iter().map(|v| v * v).sum::<i128>());
+        let num = n as i128 * s2 - s * s;
+        let exact = num as f64 / (n * (n - 1)) as f64 / 2f64.powi(32);
+
+        assert_eq!(stats::var(x.view(), Axis(0), 1, Variance::TwoPass)[()], p(exact), "{:?}", x);
+    }
+}
+
+#[test]
+fn posit32_test() {
+    type Q = Posit<32, 2>;
+    let x = array![1.0, 2.0, 3.0].mapv(Q::from);
+
+    assert_eq!(stats::sum(x.view(), Axis(0))[()], Q::from(6.0));
+    assert_eq!(stats::mean(x.view(), Axis(0))[()], Q::from(2.0));
+    assert_eq!(stats::var(x.view(), Axis(0), 1, Variance::TwoPass)[()], Q::from(1.0));
+    assert_eq!(stats::var(x.view(), Axis(0), 0, Variance::TwoPass)[()], Q::from(2.0 / 3.0));
+
+    let mut rng = XorShift::new(10);
+    for lane in 0..100 {
+        let n = 3 + lane % 10;
+        let x: Array1<Q> = (0..n).map(|_| Q::from(((100.0 + 0.01 * rng.next_normal()) * 65536.0).round() / 65536.0)).collect();
+        let ints: Vec<i128> = x.iter().map(|&q| (f64::from(q) * 65536.0) as i128).collect();
+
+        let (s, s2) = (ints.iter().sum::<i128>(), ints.iter().map(|v| v * v).sum::<i128>());
+        assert_eq!(stats::sum(x.view(), Axis(0))[()], Q::from(s as f64 / 65536.0));
+        assert_eq!(stats::mean(x.view(), Axis(0))[()], Q::from(s as f64 / n as f64 / 65536.0));
+
+        let exact = (n as i128 * s2 - s * s) as f64 / (n * (n - 1)) as f64 / 2f64.powi(32);
+        assert_eq!(stats::var(x.view(), Axis(0), 1, Variance::TwoPass)[()], Q::from(exact), "{:?}", x);
+    }
+
+    let x = array![[1.0, 2.0, 3.0, 4.0], [2.0, 4.0, 6.0, 8.0], [4.0, 1.0, 3.0, 2.0]].mapv(Q::from);
+    let c = stats::cov(x.view(), 1);
+    assert_eq!(c[(0, 0)], Q::from(5.0 / 3.0));
+    assert_eq!(c[(0, 1)], Q::from(10.0 / 3.0));
+    assert_eq!(c[(2, 0)], Q::from(-2.0 / 3.0));
+    assert_eq!(stats::corrcoef(x.view())[(1, 0)], Q::from(1.0));
+    assert_eq!(stats::corrcoef(x.view())[(0, 2)], Q::from(-0.4));
+}
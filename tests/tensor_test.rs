@@ -0,0 +1,134 @@
+use namagiri::posit::Posit;
+use namagiri::quire::Quire;
+use namagiri::gemm::{self, Trans};
+use namagiri::tensor::{einsum, matmul_batched, tensordot, TensorError};
+use namagiri::rng::XorShift;
+use ndarray::{Array, Array2, ArrayD, Axis, IxDyn};
+
+type P = Posit<8, 1>;
+
+fn random_tensor(rng: &mut XorShift, shape: &[usize]) -> ArrayD<P> {
+    Array::from_shape_fn(IxDyn(shape), |_| P::from(rng.next_normal() as f32))
+}
+
+fn dot_q<'a>(pairs: impl Iterator<Item = (&'a P, &'a P)>) -> P {
+    pairs.fold(Quire::<8, 1>::default(), |acc, (&x, &y)| acc + Quire::from(x) * Quire::from(y)).into()
+}
+
+#[test]
+fn einsum_matmul_test() {
+    let mut rng = XorShift::new(5);
+    let a = random_tensor(&mut rng, &[7, 12]);
+    let b = random_tensor(&mut rng, &[12, 5]);
+    let (a2, b2) = (a.view().into_dimensionality().unwrap(), b.view().into_dimensionality().unwrap());
+    let c = gemm::gemm(Trans::No, a2, Trans::No, b2).into_dyn();
+
+    assert_eq!(einsum("ij,jk->ik", &[a.view(), b.view()]).unwrap(), c);
+    assert_eq!(einsum("ij,jk", &[a.view(), b.view()]).unwrap(), c);
+    assert_eq!(einsum("ij,jk->ki", &[a.view(), b.view()]).unwrap(), c.t());
+    assert_eq!(tensordot(a.view(), b.view(), &[1], &[0]).unwrap(), c);
+}
+
+#[test]
+fn batched_test() {
+    let mut rng = XorShift::new(9);
+    let a = random_tensor(&mut rng, &[2, 3, 4, 6]);
+    let b = random_tensor(&mut rng, &[3, 6, 5]);
+    let c = matmul_batched(a.view(), b.view()).unwrap();
+    assert_eq!(c.shape(), &[2, 3, 4, 5]);
+
+    for i in 0..2 {
+        for h in 0..3 {
+            let x = a.view().index_axis_move(Axis(0), i).index_axis_move(Axis(0), h).into_dimensionality().unwrap();
+            let y = b.index_axis(Axis(0), h).into_dimensionality().unwrap();
+            assert_eq!(c.index_axis(Axis(0), i).index_axis(Axis(0), h), gemm::gemm(Trans::No, x, Trans::No, y).into_dyn());
+        }
+    }
+
+    let bb = b.broadcast(IxDyn(&[2, 3, 6, 5])).unwrap();
+    assert_eq!(einsum("bhij,bhjk->bhik", &[a.view(), bb.view()]).unwrap(), c);
+}
+
+#[test]
+fn contraction_test() {
+    let mut rng = XorShift::new(21);
+    let a = random_tensor(&mut rng, &[3, 4, 5]);
+    let b = random_tensor(&mut rng, &[5, 4, 2]);
+
+    // both contracted axes reduce into a single rounding
+    let c = einsum("ijk,kjl->il", &[a.view(), b.view()]).unwrap();
+    for i in 0..3 {
+        for l in 0..2 {
+            let pairs: Vec<(P, P)> = (0..4).flat_map(|j| (0..5).map(move |k| (j, k))).map(|(j, k)| (a[[i, j, k]], b[[k, j, l]])).collect();
+            assert_eq!(c[[i, l]], dot_q(pairs.iter().map(|(x, y)| (x, y))));
+        }
+    }
+    assert_eq!(tensordot(a.view(), b.view(), &[1, 2], &[1, 0]).unwrap(), c);
+
+    // a label summed on one side only goes through the direct path
+    let d = einsum("ijk,kl->il", &[a.view(), b.index_axis(Axis(1), 0)]).unwrap();
+    for i in 0..3 {
+        for l in 0..2 {
+            let pairs: Vec<(P, P)> = (0..4).flat_map(|j| (0..5).map(move |k| (j, k))).map(|(j, k)| (a[[i, j, k]], b[[k, 0, l]])).collect();
+            assert_eq!(d[[i, l]], dot_q(pairs.iter().map(|(x, y)| (x, y))));
+        }
+    }
+}
+
+#[test]
+fn single_operand_test() {
+    let mut rng = XorShift::new(33);
+    let a = random_tensor(&mut rng, &[6, 6]);
+    let one = P::from(1.0);
+
+    let trace = einsum("ii->", &[a.view()]).unwrap();
+    assert_eq!(trace[[]], dot_q((0..6).map(|i| (&a[[i, i]], &one))));
+
+    let sums = einsum("ij->j", &[a.view()]).unwrap();
+    for j in 0..6 {
+        assert_eq!(sums[[j]], dot_q((0..6).map(|i| (&a[[i, j]], &one))));
+    }
+
+    assert_eq!(einsum("ij->ji", &[a.view()]).unwrap(), a.t());
+}
+
+#[test]
+fn error_test() {
+    let a = ArrayD::from_elem(IxDyn(&[2, 3]), P::from(1.0));
+
+    assert_eq!(einsum("ij,jk->ik", &[a.view(), a.view()]), Err(TensorError::AxisSize));
+    assert_eq!(einsum("ij->ik", &[a.view()]), Err(TensorError::Output));
+    assert_eq!(einsum("ijk->i", &[a.view()]), Err(TensorError::Rank(2, 3)));
+    assert_eq!(einsum("i1->i", &[a.view()]), Err(TensorError::Subscripts("i1->i".to_string())));
+    assert_eq!(einsum("ij,jk,kl->il", &[a.view(), a.view(), a.view()]), Err(TensorError::Operands(3)));
+    assert_eq!(tensordot(a.view(), a.view(), &[0], &[1]), Err(TensorError::AxisSize));
+    assert_eq!(tensordot(a.view(), a.view(), &[0, 1], &[1]), Err(TensorError::Axes));
+
+    let v = ArrayD::from_elem(IxDyn(&[3]), P::from(1.0));
+    let s = ArrayD::from_elem(IxDyn(&[4, 3, 2]), P::from(1.0));
+    assert_eq!(matmul_batched(a.view(), v.view()), Err(TensorError::Matrix(1)));
+    assert_eq!(matmul_batched(a.view(), a.view()), Err(TensorError::Inner(3, 2)));
+    assert_eq!(matmul_batched(a.broadcast(IxDyn(&[3, 2, 3])).unwrap(), s.view()), Err(TensorError::Broadcast));
+    assert_eq!(matmul_batched(a.view(), s.view()).unwrap().shape(), &[4, 2, 2]);
+
+    let mut n = a.clone();
+    n[[1, 2]] = P::NAR;
+    let c = einsum("ij,kj->ik", &[n.view(), a.view()]).unwrap();
+    assert!(c[[1, 0]].is_nar() && !c[[0, 0]].is_nar());
+    assert!(einsum("ij->", &[n.view()]).unwrap()[[]].is_nar());
+}
+
+#[test]
+fn posit32_test() {
+    type P32 = Posit<32, 2>;
+
+    // small dyadic values keep the f64 reference exact
+    let a = Array2::from_shape_fn((3, 5), |(i, j)| (i * 5 + j) as f64 / 4.0 - 2.0);
+    let b = Array2::from_shape_fn((5, 2), |(i, j)| 1.5 - (i + 3 * j) as f64 / 8.0);
+    let c = a.dot(&b).mapv(P32::from).into_dyn();
+    let (ap, bp) = (a.mapv(P32::from).into_dyn(), b.mapv(P32::from).into_dyn());
+
+    assert_eq!(einsum("ij,jk->ik", &[ap.view(), bp.view()]).unwrap(), c);
+    assert_eq!(matmul_batched(ap.view(), bp.view()).unwrap(), c);
+    assert_eq!(einsum("ij->", &[ap.view()]).unwrap().into_iter().next(), Some(P32::from(a.sum())));
+}
@@ -0,0 +1,201 @@
+use namagiri::posit::Posit;
+use namagiri::train::{self, Activation, Adam, Mlp, Optimizer, Rounding, Sgd};
+use namagiri::rng::XorShift;
+use ndarray::{Array1, Array2};
+
+type P8 = Posit<8, 1>;
+type P16 = Posit<16, 1>;
+
+fn random(rng: &mut XorShift, shape: (usize, usize)) -> Array2<P8> {
+    Array2::from_shape_fn(shape, |_| P8::from(rng.next_normal()))
+}
+
+fn matmul_f64(a: &Array2<P8>, b: &Array2<P8>) -> Array2<P8> {
+    a.mapv(f64::from).dot(&b.mapv(f64::from)).mapv(P8::from)
+}
+
+// four blobs labelled by the xor of their quadrant
+fn xor_blobs<const N: u8, const ES: u8>(rng: &mut XorShift, n: usize) -> (Array2<Posit<N, ES>>, Vec<usize>) {
+    let mut x = Array2::zeros((n, 2));
+    let labels = (0..n).map(|i| {
+        let (a, b) = (i % 2, i / 2 % 2);
+        x[[i, 0]] = Posit::from(if a == 1 {1.5} else {-1.5} + 0.4 * rng.next_normal());
+        x[[i, 1]] = Posit::from(if b == 1 {1.5} else {-1.5} + 0.4 * rng.next_normal());
+        a ^ b
+    }).collect();
+
+    (x, labels)
+}
+
+#[test]
+fn linear_test() {
+    let mut rng = XorShift::new(1);
+    let (x, w, dy) = (random(&mut rng, (12, 20)), random(&mut rng, (20, 7)), random(&mut rng, (12, 7)));
+    let b: Array1<P8> = (0..7).map(|_| P8::from(rng.next_normal())).collect();
+
+    let y = train::linear(x.view(), w.view(), b.view());
+    let expect = x.mapv(f64::from).dot(&w.mapv(f64::from)) + b.mapv(f64::from);
+    assert_eq!(y, expect.mapv(P8::from));
+
+    let (dx, dw, db) = train::linear_backward(x.view(), w.view(), dy.view());
+    assert_eq!(dx, matmul_f64(&dy, &w.t().to_owned()));
+    assert_eq!(dw, matmul_f64(&x.t().to_owned(), &dy));
+    assert_eq!(db, dy.mapv(f64::from).sum_axis(ndarray::Axis(0)).mapv(P8::from));
+
+    let (da, db) = train::matmul_backward(x.view(), w.view(), dy.view());
+    assert_eq!((da, db), (dx, dw));
+}
+
+#[test]
+fn posit32_test() {
+    type P32 = Posit<32, 2>;
+
+    // small dyadic values keep the f64 reference exact
+    let mut rng = XorShift::new(2);
+    let mut small = |shape| Array2::from_shape_fn(shape, |_| (rng.next_u32() % 64) as f64 / 8.0 - 4.0);
+    let (x, w, b) = (small((5, 9)), small((9, 4)), small((1, 4)).row(0).to_owned());
+
+    let y = train::linear(x.mapv(P32::from).view(), w.mapv(P32::from).view(), b.mapv(P32::from).view());
+    assert_eq!(y, (x.dot(&w) + b).mapv(P32::from));
+}
+
+#[test]
+fn activation_test() {
+    let x: Array1<P16> = [-1.0, 0.0, 1.0, 2.0].iter().map(|&v| P16::from(v)).collect();
+    let ones = Array1::from_elem(4, P16::from(1.0));
+    let d = |a: Activation| train::Activation::backward(a, x.view(), ones.view()).mapv(f64::from).to_vec();
+
+    // f'(x) at -1, 0, 1, 2, rounded
+    let r = |v: [f64; 4]| v.map(|v| f64::from(P16::from(v))).to_vec();
+    assert_eq!(d(Activation::Identity), r([1.0; 4]));
+    assert_eq!(d(Activation::Relu), r([0.0, 0.0, 1.0, 1.0]));
+    assert_eq!(d(Activation::Sigmoid), r([0.19661193324148185, 0.25, 0.19661193324148185, 0.10499358540350662]));
+    assert_eq!(d(Activation::Tanh), r([0.41997434161402614, 1.0, 0.41997434161402614, 0.07065082485316443]));
+    assert_eq!(d(Activation::Gelu), r([-0.08331548357410152, 0.5, 1.0833154835741015, 1.0852318023641487]));
+
+    let y = Activation::Gelu.forward(x.view());
+    assert_eq!(y[2], P16::from(0.8413447460685429));
+
+    // the chain rule scales by dy, NaR propagates
+    let dy: Array1<P16> = [2.0, 3.0, -0.5, 0.0].iter().map(|&v| P16::from(v)).collect();
+    assert_eq!(Activation::Tanh.backward(x.view(), dy.view())[1], P16::from(3.0));
+    let mut x = x;
+    x[0] = P16::NAR;
+    assert!(Activation::Relu.backward(x.view(), dy.view())[0].is_nar());
+    assert!(Activation::Relu.forward(x.view())[0].is_nar());
+}
+
+#[test]
+fn softmax_cross_entropy_test() {
+    // uniform logits: ln C loss and an exact gradient
+    let logits = Array2::from_elem((4, 4), P16::from(0.5));
+    let (loss, grad) = train::softmax_cross_entropy(logits.view(), &[0, 1, 2, 3]);
+    assert!((loss - 4f64.ln()).abs() < 1e-12);
+    for ((i, j), &g) in grad.indexed_iter() {
+        assert_eq!(g, P16::from(if i == j {-0.1875} else {0.0625}));
+    }
+
+    let mut rng = XorShift::new(2);
+    let logits: Array2<P16> = Array2::from_shape_fn((8, 5), |_| P16::from(3.0 * rng.next_normal()));
+    let labels = [0, 4, 2, 2, 1, 3, 0, 4];
+    let (loss, grad) = train::softmax_cross_entropy(logits.view(), &labels);
+
+    let mut expect = 0.0;
+    for (row, &label) in logits.outer_iter().zip(&labels) {
+        let x: Vec<f64> = row.iter().map(|&p| f64::from(p)).collect();
+        expect += x.iter().map(|v| v.exp()).sum::<f64>().ln() - x[label];
+    }
+    assert!((loss - expect / 8.0).abs() < 1e-12);
+    for row in grad.outer_iter() {
+        assert!(row.iter().map(|&p| f64::from(p)).sum::<f64>().abs() < 1e-4);
+    }
+
+    // NaR in a row poisons its gradient row and the loss
+    let mut logits = logits;
+    logits[[3, 1]] = P16::NAR;
+    let (loss, grad) = train::softmax_cross_entropy(logits.view(), &labels);
+    assert!(loss.is_nan());
+    assert!(grad.row(3).iter().all(|p| p.is_nar()) && grad.row(2).iter().all(|p| !p.is_nar()));
+}
+
+#[test]
+fn stochastic_round_test() {
+    let mut rng = XorShift::new(3);
+
+    for v in [1.01, -1.01, 0.3, 1e-4, -7.7, 100.0] {
+        let near = P8::from(v);
+        let samples: Vec<P8> = (0..20000).map(|_| train::stochastic_round(v, &mut rng)).collect();
+        let mean = samples.iter().map(|&p| f64::from(p)).sum::<f64>() / samples.len() as f64;
+
+        // only the two neighbours of v, in the right proportion
+        assert!(samples.iter().all(|&p| (p.0 as i32 - near.0 as i32).abs() <= 1));
+        assert!((mean - v).abs() < 0.03 * v.abs(), "{} vs {}", mean, v);
+    }
+
+    assert_eq!(train::stochastic_round::<8, 1>(1.5, &mut rng), P8::from(1.5));
+    assert_eq!(f64::from(train::stochastic_round::<8, 1>(1e9, &mut rng)), 4096.0);
+    assert!(train::stochastic_round::<8, 1>(f64::NAN, &mut rng).is_nar());
+}
+
+#[test]
+fn optimizer_test() {
+    // updates far below half an ulp vanish under round to nearest but survive on average stochastically
+    let grad = Array1::from_elem(64, P8::from(-1.0)).into_dyn();
+    let mut nearest = Array1::from_elem(64, P8::from(1.0)).into_dyn();
+    let mut stochastic = nearest.clone();
+
+    let mut a = Sgd::new(1e-3, 0.0, Rounding::Nearest);
+    let mut b = Sgd::new(1e-3, 0.0, Rounding::Stochastic(7));
+    for _ in 0..500 {
+        a.step(&mut [nearest.view_mut()], &[grad.view()]);
+        b.step(&mut [stochastic.view_mut()], &[grad.view()]);
+    }
+
+    assert!(nearest.iter().all(|&p| p == P8::from(1.0)));
+    let mean = stochastic.iter().map(|&p| f64::from(p)).sum::<f64>() / 64.0;
+    assert!((mean - 1.5).abs() < 0.1, "{}", mean);
+
+    // Adam's first step moves every weight by lr against the gradient sign
+    let mut w = Array1::from_elem(3, P16::from(1.0)).into_dyn();
+    let g: Array1<P16> = [0.5, -2.0, 0.0].iter().map(|&v| P16::from(v)).collect();
+    let mut adam = Adam::new(0.125, Rounding::Nearest);
+    adam.step(&mut [w.view_mut()], &[g.into_dyn().view()]);
+    assert_eq!(w.as_slice().unwrap(), &[P16::from(0.875), P16::from(1.125), P16::from(1.0)]);
+}
+
+#[test]
+fn mlp_test() {
+    let mut rng = XorShift::new(4);
+
+    let (x, labels) = xor_blobs::<16, 1>(&mut rng, 128);
+    let mut mlp = Mlp::<16, 1>::new(&[2, 16, 2], Activation::Tanh, &mut rng);
+    let mut adam = Adam::new(0.05, Rounding::Nearest);
+
+    let first = mlp.train_step(x.view(), &labels, &mut adam);
+    let last = (0..100).map(|_| mlp.train_step(x.view(), &labels, &mut adam)).last().unwrap();
+    assert!(last < 0.2 * first, "loss {} -> {}", first, last);
+    assert!(mlp.accuracy(x.view(), &labels) > 0.95);
+
+    let (x, labels) = xor_blobs::<8, 1>(&mut rng, 128);
+    let mut mlp = Mlp::<8, 1>::new(&[2, 16, 2], Activation::Relu, &mut rng);
+    let mut sgd = Sgd::new(0.1, 0.9, Rounding::Stochastic(11));
+    for _ in 0..100 {
+        mlp.train_step(x.view(), &labels, &mut sgd);
+    }
+    assert!(mlp.accuracy(x.view(), &labels) > 0.9);
+
+    // the gradients agree with central differences of the loss
+    let mlp = Mlp::<16, 1>::new(&[2, 4, 2], Activation::Sigmoid, &mut rng);
+    let (x, labels) = xor_blobs::<16, 1>(&mut rng, 8);
+    let (_, grads) = mlp.gradients(x.view(), &labels);
+    for (i, j) in [(0, 0), (1, 3)] {
+        let loss = |d: f64| {
+            let mut m = mlp.clone();
+            m.layers[0].w[[i, j]] = P16::from(f64::from(m.layers[0].w[[i, j]]) + d);
+            train::softmax_cross_entropy(m.forward(x.view()).view(), &labels).0
+        };
+        let h = 1.0 / 64.0;
+        let numeric = (loss(h) - loss(-h)) / (2.0 * h);
+        assert!((f64::from(grads[0].0[[i, j]]) - numeric).abs() < 0.01, "{} vs {}", grads[0].0[[i, j]], numeric);
+    }
+}
@@ -0,0 +1,238 @@
+use namagiri::posit::Posit;
+use namagiri::transformer::{self, Gelu, TransformerError};
+use namagiri::rng::XorShift;
+use ndarray::{Array1, Array2, Array3, Axis};
+
+type P8 = Posit<8, 1>;
+type P16 = Posit<16, 1>;
+
+fn random<const N: u8, const ES: u8>(rng: &mut XorShift, shape: (usize, usize)) -> Array2<Posit<N, ES>> {
+    Array2::from_shape_fn(shape, |_| Posit::from(rng.next_normal()))
+}
+
+fn to_f64<const N: u8, const ES: u8>(a: &Array2<Posit<N, ES>>) -> Array2<f64> {
+    a.mapv(f64::from)
+}
+
+fn softmax_f64(s: &Array1<f64>) -> Array1<f64> {
+    let m = s.fold(f64::NEG_INFINITY, |m, &v| m.max(v));
+    let e = s.mapv(|v| (v - m).exp());
+    let sum = e.sum();
+    e / sum
+}
+
+fn attention_f64(q: &Array2<f64>, k: &Array2<f64>, v: &Array2<f64>, mask: Option<&Array2<bool>>) -> Array2<f64> {
+    let scores = q.dot(&k.t()) / (q.ncols() as f64).sqrt();
+    let mut probs = Array2::zeros(scores.dim());
+
+    for (i, row) in scores.outer_iter().enumerate() {
+        let kept: Vec<usize> = (0..row.len()).filter(|&j| !matches!(mask, Some(m) if !m[[i, j]])).collect();
+        let p = softmax_f64(&kept.iter().map(|&j| row[j]).collect());
+        for (&j, &p) in kept.iter().zip(&p) {
+            probs[[i, j]] = p;
+        }
+    }
+
+    probs.dot(v)
+}
+
+fn max_error(a: &Array2<f64>, b: &Array2<f64>) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).abs()).fold(0.0, f64::max)
+}
+
+#[test]
+fn softmax_test() {
+    let mut rng = XorShift::new(1);
+    let x: Array2<P16> = Array2::from_shape_fn((6, 33), |_| P16::from(4.0 * rng.next_normal()));
+
+    for axis in [Axis(0), Axis(1)] {
+        let y = transformer::softmax::<16, 1, 16, 1, _>(x.view(), axis);
+
+        for (xl, yl) in x.lanes(axis).into_iter().zip(y.lanes(axis)) {
+            let expect = softmax_f64(&xl.mapv(f64::from));
+            let sum: f64 = yl.iter().map(|&p| f64::from(p)).sum();
+            assert!((sum - 1.0).abs() < 1e-3, "sum {}", sum);
+
+            for (&p, &e) in yl.iter().zip(&expect) {
+                // within two units in the last place of the tapered format
+                assert!((p.0 as i32 - P16::from(e).0 as i32).abs() <= 2, "{} vs {}", p, e);
+            }
+        }
+    }
+
+    // posit8 probabilities from exponentials carried in posit16
+    let x8: Array2<P8> = x.mapv(|p| p.into_p());
+    let y8 = transformer::softmax::<8, 1, 16, 1, _>(x8.view(), Axis(1));
+    for (xl, yl) in x8.outer_iter().zip(y8.outer_iter()) {
+        let expect = softmax_f64(&xl.mapv(f64::from));
+        for (&p, &e) in yl.iter().zip(&expect) {
+            assert!((p.0 as i32 - P8::from(e).0 as i32).abs() <= 1, "{} vs {}", p, e);
+        }
+    }
+
+    // an exactly representable shift leaves the result unchanged
+    let x: Array2<P16> = Array2::from_shape_fn((4, 9), |(i, j)| P16::from((i * j % 7) as f64 - 3.0));
+    let shifted = x.mapv(|p| P16::from(f64::from(p) + 64.0));
+    let a = transformer::softmax::<16, 1, 16, 1, _>(x.view(), Axis(1));
+    assert_eq!(a, transformer::softmax::<16, 1, 16, 1, _>(shifted.view(), Axis(1)));
+
+    // NaR poisons only its own lane
+    let mut x = Array3::from_shape_fn((2, 3, 4), |(i, j, k)| P16::from((i + j + k) as f64));
+    x[[1, 2, 0]] = P16::NAR;
+    let y = transformer::softmax::<16, 1, 16, 1, _>(x.view(), Axis(2));
+    for ((i, j, _), p) in y.indexed_iter() {
+        assert_eq!(p.is_nar(), (i, j) == (1, 2));
+    }
+
+    // equal inputs split evenly and exactly
+    let y = transformer::softmax::<8, 1, 16, 1, _>(Array1::from_elem(4, P8::from(3.0)).view(), Axis(0));
+    assert!(y.iter().all(|&p| p == P8::from(0.25)));
+}
+
+#[test]
+fn layer_norm_test() {
+    let mut rng = XorShift::new(2);
+    let x: Array2<P16> = Array2::from_shape_fn((5, 64), |_| P16::from(3.0 + 2.0 * rng.next_normal()));
+
+    let y = transformer::layer_norm::<16, 1, 16, 1, _>(x.view(), Axis(1), None, None, 0.0).unwrap();
+    for row in y.outer_iter() {
+        let v: Vec<f64> = row.iter().map(|&p| f64::from(p)).collect();
+        let mean = v.iter().sum::<f64>() / 64.0;
+        let var = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / 64.0;
+        assert!(mean.abs() < 1e-3 && (var - 1.0).abs() < 1e-3, "mean {} var {}", mean, var);
+    }
+
+    // matches f64 statistics, gamma and beta applied per feature
+    let gamma: Array1<P16> = (0..64).map(|k| P16::from(0.5 + k as f64 / 64.0)).collect();
+    let beta: Array1<P16> = (0..64).map(|k| P16::from(k as f64 / 16.0 - 2.0)).collect();
+    let y = transformer::layer_norm::<16, 1, 16, 1, _>(x.view(), Axis(1), Some(gamma.view()), Some(beta.view()), 1e-5).unwrap();
+    for (xr, yr) in x.outer_iter().zip(y.outer_iter()) {
+        let v: Vec<f64> = xr.iter().map(|&p| f64::from(p)).collect();
+        let mean = v.iter().sum::<f64>() / 64.0;
+        let var = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / 64.0;
+
+        for (k, &p) in yr.iter().enumerate() {
+            let e = (v[k] - mean) / (var + 1e-5).sqrt() * f64::from(gamma[k]) + f64::from(beta[k]);
+            assert!((f64::from(p) - e).abs() < 2e-3 * e.abs().max(1.0), "{} vs {}", p, e);
+        }
+    }
+
+    // a constant lane normalises to zero, NaR poisons the lane
+    let mut x = Array2::from_elem((2, 8), P8::from(1.5));
+    x[[1, 3]] = P8::NAR;
+    let y = transformer::layer_norm::<8, 1, 16, 1, _>(x.view(), Axis(1), None, None, 1e-5).unwrap();
+    assert!(y.row(0).iter().all(|&p| p == P8::from(0.0)));
+    assert!(y.row(1).iter().all(|p| p.is_nar()));
+}
+
+#[test]
+fn gelu_test() {
+    // x Phi(x) to double precision
+    let cases = [(1.0, 0.8413447460685429), (-1.0, -0.15865525393145707), (-3.0, -0.004049694094890319), (2.5, 2.4844757875625394), (0.0, 0.0)];
+    let x: Array1<P16> = cases.iter().map(|&(x, _)| P16::from(x)).collect();
+    let y = transformer::gelu(x.view(), Gelu::Erf);
+    for (&p, &(_, e)) in y.iter().zip(&cases) {
+        assert_eq!(p, P16::from(e));
+    }
+
+    // the tanh form tracks the exact one, NaR passes through
+    let x: Array1<P16> = (-40..=40).map(|k| P16::from(k as f64 / 8.0)).chain([P16::NAR]).collect();
+    let exact = transformer::gelu(x.view(), Gelu::Erf);
+    let approx = transformer::gelu(x.view(), Gelu::Tanh);
+    for (&a, &b) in exact.iter().zip(&approx).take(81) {
+        assert!((f64::from(a) - f64::from(b)).abs() < 1e-3);
+    }
+    assert!(exact[81].is_nar() && approx[81].is_nar());
+}
+
+#[test]
+fn attention_test() {
+    let mut rng = XorShift::new(3);
+    let (n, d) = (16, 8);
+
+    let mut wide = 0.0;
+    let mut narrow = 0.0;
+    for _ in 0..8 {
+        let q = random::<8, 1>(&mut rng, (n, d));
+        let k = random::<8, 1>(&mut rng, (n, d));
+        let v = random::<8, 1>(&mut rng, (n, d));
+        let expect = attention_f64(&to_f64(&q), &to_f64(&k), &to_f64(&v), None);
+
+        let a = transformer::attention::<8, 1, 16, 1>(q.view(), k.view(), v.view(), None).unwrap();
+        let b = transformer::attention::<8, 1, 8, 1>(q.view(), k.view(), v.view(), None).unwrap();
+        assert!(max_error(&to_f64(&a), &expect) < 0.1);
+
+        wide += max_error(&to_f64(&a), &expect);
+        narrow += max_error(&to_f64(&b), &expect);
+    }
+    assert!(wide < narrow, "posit16 accumulation {} vs posit8 {}", wide, narrow);
+
+    // the causal mask: the first query sees only the first key
+    let q = random::<16, 1>(&mut rng, (n, d));
+    let k = random::<16, 1>(&mut rng, (n + 4, d));
+    let v = random::<16, 1>(&mut rng, (n + 4, d));
+    let mask = transformer::causal_mask(n + 4).slice_move(ndarray::s![..n, ..]);
+
+    let y = transformer::attention::<16, 1, 16, 1>(q.view(), k.view(), v.view(), Some(mask.view())).unwrap();
+    assert_eq!(y.row(0), v.row(0));
+
+    let expect = attention_f64(&to_f64(&q), &to_f64(&k), &to_f64(&v), Some(&mask));
+    assert!(max_error(&to_f64(&y), &expect) < 2e-2);
+
+    // a fully masked query has nothing to attend to
+    let mut mask = mask;
+    mask.row_mut(5).fill(false);
+    let y = transformer::attention::<16, 1, 16, 1>(q.view(), k.view(), v.view(), Some(mask.view())).unwrap();
+    assert!(y.row(5).iter().all(|p| p.is_nar()));
+    assert!(y.row(4).iter().all(|p| !p.is_nar()));
+}
+
+#[test]
+fn attention_rounding_test() {
+    // zero queries give every key the probability 1/3 rounded in the working format;
+    // the output must be p sum(v) rounded once, not rounded to posit16 first
+    let mut rng = XorShift::new(9);
+    let q = Array2::<P8>::zeros((1, 4));
+    let k: Array2<P8> = random(&mut rng, (3, 4));
+    let v: Array2<P8> = random(&mut rng, (3, 256));
+
+    let y = transformer::attention::<8, 1, 16, 1>(q.view(), k.view(), v.view(), None).unwrap();
+    let p = f64::from(P16::from(1.0 / 3.0));
+
+    for (c, &o) in y.row(0).iter().enumerate() {
+        assert_eq!(o, P8::from(p * v.column(c).iter().map(|&x| f64::from(x)).sum::<f64>()), "column {}", c);
+    }
+}
+
+#[test]
+fn posit32_test() {
+    // posit16 activations with posit32 statistics
+    let x = Array1::from(vec![1.0, 2.0, 3.0]).mapv(P16::from);
+    let y = transformer::layer_norm::<16, 1, 32, 2, _>(x.view(), Axis(0), None, None, 0.0).unwrap();
+    assert_eq!(y, Array1::from(vec![-1.5f64.sqrt(), 0.0, 1.5f64.sqrt()]).mapv(P16::from));
+
+    let y = transformer::softmax::<16, 1, 32, 2, _>(Array1::from_elem(4, P16::from(3.0)).view(), Axis(0));
+    assert!(y.iter().all(|&p| p == P16::from(0.25)));
+
+    let mut rng = XorShift::new(4);
+    let (q, k, v) = (random::<16, 1>(&mut rng, (6, 4)), random::<16, 1>(&mut rng, (6, 4)), random::<16, 1>(&mut rng, (6, 5)));
+    let expect = attention_f64(&to_f64(&q), &to_f64(&k), &to_f64(&v), None);
+    let a = transformer::attention::<16, 1, 32, 2>(q.view(), k.view(), v.view(), None).unwrap();
+    assert!(max_error(&to_f64(&a), &expect) < 2e-3, "{}", max_error(&to_f64(&a), &expect));
+}
+
+#[test]
+fn error_test() {
+    let x = Array2::<P16>::zeros((2, 4));
+    let g = Array1::<P16>::zeros(3);
+    assert_eq!(transformer::layer_norm::<16, 1, 16, 1, _>(x.view(), Axis(1), Some(g.view()), None, 1e-5), Err(TransformerError::Parameters(3, 4)));
+    assert_eq!(transformer::layer_norm::<16, 1, 16, 1, _>(x.view(), Axis(1), None, Some(g.view()), 1e-5), Err(TransformerError::Parameters(3, 4)));
+    assert!(transformer::layer_norm::<16, 1, 16, 1, _>(x.view(), Axis(0), Some(g.view().slice_move(ndarray::s![..2])), None, 1e-5).is_ok());
+
+    let (q, k) = (Array2::<P16>::zeros((3, 4)), Array2::<P16>::zeros((5, 4)));
+    let v = Array2::<P16>::zeros((5, 2));
+    assert_eq!(transformer::attention::<16, 1, 16, 1>(q.view(), Array2::zeros((5, 3)).view(), v.view(), None), Err(TransformerError::Shape));
+    assert_eq!(transformer::attention::<16, 1, 16, 1>(q.view(), k.view(), Array2::zeros((4, 2)).view(), None), Err(TransformerError::Shape));
+    let mask = transformer::causal_mask(5);
+    assert_eq!(transformer::attention::<16, 1, 16, 1>(q.view(), k.view(), v.view(), Some(mask.view())), Err(TransformerError::Mask((5, 5), (3, 5))));
+}